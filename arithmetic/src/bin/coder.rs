@@ -1,17 +1,21 @@
 use std::{env::args, fs::File, io::{BufReader, BufWriter}, time::Instant};
 
-use arithmetic::coder::Coder;
+use arithmetic::{coder::Coder, model::Model};
 
 pub fn main() {
     let args = args().collect::<Vec<_>>();
 
     let input_path = args.get(1).expect("Please input the input file path");
     let output_path = args.get(2).expect("Please input the output file path");
+    let model = args.get(3).map(|path| Model::read(path).expect("Error while reading the model file"));
 
     let input_stream = BufReader::new(File::open(input_path).unwrap());
     let mut output_stream = BufWriter::new(File::create(output_path).unwrap());
 
-    let mut coder = Coder::new(input_stream, &mut output_stream);
+    let mut coder = match model {
+        Some(model) => Coder::with_model(input_stream, &mut output_stream, model),
+        None => Coder::new(input_stream, &mut output_stream),
+    };
     
     let time_start = Instant::now();
     coder.code_all();
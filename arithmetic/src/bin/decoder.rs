@@ -1,24 +1,33 @@
 use std::{env::args, fs::File, io::{BufReader, BufWriter}, time::Instant};
 
-use arithmetic::decoder::Decoder;
+use arithmetic::{decoder::Decoder, model::Model};
 
 pub fn main() {
     let args = args().collect::<Vec<_>>();
 
     let input_path = args.get(1).expect("Please input the input file path");
     let output_path = args.get(2).expect("Please input the output file path");
+    let model = args.get(3).map(|path| Model::read(path).expect("Error while reading the model file"));
 
     let input_stream = BufReader::new(File::open(input_path).unwrap());
     let mut output_stream = BufWriter::new(File::create(output_path).unwrap());
 
-    let mut decoder = Decoder::new(input_stream, &mut output_stream);
+    let mut decoder = match model {
+        Some(model) => Decoder::with_model(input_stream, &mut output_stream, model),
+        None => Decoder::new(input_stream, &mut output_stream),
+    };
 
     let time_start = Instant::now();
-    decoder.decode_all();
+    let result = decoder.decode_all();
     let time_end = Instant::now();
 
     let dur = (time_end - time_start).as_secs_f64();
 
+    if let Err(err) = result {
+        eprintln!("Decoding failed: {}", err);
+        std::process::exit(1);
+    }
+
     let output_len = decoder.get_stat();
 
     println!("Speed: {:.2} MB/s", output_len as f64 / (dur * 1_000_000.0));
@@ -0,0 +1,24 @@
+use std::{env::args, fs::read};
+
+use arithmetic::model::{Model, DEFAULT_TRAINED_COUNT};
+
+pub fn main() {
+    let args = args().collect::<Vec<_>>();
+
+    let sample_path = args.get(1).expect("Please input the sample file path");
+    let model_path = args.get(2).expect("Please input the model file path");
+    let max_total = args
+        .get(3)
+        .map(|s| s.parse().expect("Please input a correct maximal model count"))
+        .unwrap_or(DEFAULT_TRAINED_COUNT);
+
+    let sample = read(sample_path).unwrap();
+
+    let mut model = Model::new();
+    model.train(sample.iter().map(|&byte| byte as u32));
+    model.rescale(max_total);
+    model.save(model_path).unwrap();
+
+    println!("Trained on {} bytes", sample.len());
+    println!("Model total count: {}", model.total_symbols);
+}
@@ -2,7 +2,11 @@
 
 use std::io::{Read, Write};
 
-use crate::model::{Model, PRange};
+use crate::{
+    checksum::Crc32,
+    model::{Model, PRange, SymbolModel, BYTE_ALPHABET_SIZE},
+    TRAILER_LEN,
+};
 
 pub struct BitOutputStream<O> {
     buf: u8,
@@ -34,6 +38,12 @@ impl<O: Write> BitOutputStream<O> {
         }
     }
 
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        assert_eq!(self.n, 0, "Bytes can only be written on a byte boundary");
+        self.output_stream.write_all(bytes).unwrap();
+        self.written_bytes += bytes.len();
+    }
+
     pub fn finish(&mut self) {
         if self.n != 0 {
             self.buf <<= 8 - self.n;
@@ -45,13 +55,18 @@ impl<O: Write> BitOutputStream<O> {
     }
 }
 
-pub struct Coder<I, O> {
-    input_stream: I,
+pub struct SymbolCoder<O> {
     output_stream: BitOutputStream<O>,
     low: u32,
     high: u32,
     not_yet_written_bits: u32,
     model: Model,
+}
+
+pub struct Coder<I, O> {
+    input_stream: I,
+    symbol_coder: SymbolCoder<O>,
+    checksum: Crc32,
 
     input_len: usize,
 }
@@ -63,13 +78,16 @@ const ONE_FOURTH_U32: u32 = THREE_FOURTHS_U32 - HALF_U32;
 impl<I: Read, O: Write> Coder<I, O> {
 
     pub fn new(input_stream: I, output_stream: O) -> Self {
+        Self::with_model(input_stream, output_stream, Model::with_alphabet(BYTE_ALPHABET_SIZE))
+    }
+
+    pub fn with_model(input_stream: I, output_stream: O, model: Model) -> Self {
+        assert_eq!(model.alphabet_size(), BYTE_ALPHABET_SIZE, "Byte coder needs a byte model");
+
         Self {
             input_stream,
-            output_stream: BitOutputStream::new(output_stream),
-            high: u32::MAX,
-            low: 0,
-            not_yet_written_bits: 0,
-            model: Model::default(),
+            symbol_coder: SymbolCoder::with_model(output_stream, model),
+            checksum: Crc32::new(),
             input_len: 0,
         }
     }
@@ -77,18 +95,69 @@ impl<I: Read, O: Write> Coder<I, O> {
     pub fn code_all(&mut self) {
         while let Some(byte) = self.read_byte() {
             self.input_len += 1;
+            self.checksum.update(&[byte]);
+            self.symbol_coder.code_symbol(byte as u32);
+        }
+
+        self.symbol_coder.finish();
+
+        let mut trailer = [0; TRAILER_LEN];
+        trailer[..8].copy_from_slice(&(self.input_len as u64).to_le_bytes());
+        trailer[8..].copy_from_slice(&self.checksum.finish().to_le_bytes());
+        self.symbol_coder.output_stream.write_bytes(&trailer);
+    }
+
+    pub fn get_stat(&self) -> (usize, usize) {
+        (self.input_len, self.symbol_coder.written_bytes())
+    }
 
-            let p_range = self.model.get_p_range(byte);
+    fn read_byte(&mut self) -> Option<u8> {
+        let mut buf = [0];
+        self.input_stream.read_exact(&mut buf).ok().and(Some(buf[0]))
+    }
+}
 
-            self.write_p_range(p_range);
+impl<O: Write> SymbolCoder<O> {
+    pub fn new(output_stream: O, alphabet_size: usize) -> Self {
+        Self::with_model(output_stream, Model::with_alphabet(alphabet_size))
+    }
 
-            self.model.save_symbol(byte);
+    pub fn with_model(output_stream: O, model: Model) -> Self {
+        Self {
+            output_stream: BitOutputStream::new(output_stream),
+            high: u32::MAX,
+            low: 0,
+            not_yet_written_bits: 0,
+            model,
         }
+    }
+
+    pub fn code_symbol(&mut self, symbol: u32) {
+        let p_range = self.model.get_p_range(symbol);
+
+        self.write_p_range(p_range);
+
+        self.model.save_symbol(symbol);
+    }
+
+    pub fn code_symbol_with<M: SymbolModel>(&mut self, model: &mut M, symbol: u32) {
+        let p_range = model.get_p_range(symbol);
+
+        self.write_p_range(p_range);
 
+        model.save_symbol(symbol);
+    }
+
+    pub fn finish(&mut self) {
         let p_range = self.model.get_eof_range();
 
         self.write_p_range(p_range);
 
+        self.terminate();
+    }
+
+    // Ends the code without an end of stream symbol, for streams that know their length
+    pub fn terminate(&mut self) {
         self.not_yet_written_bits += 1;
         if self.low < ONE_FOURTH_U32 {
             self.write_all_bits(false);
@@ -99,8 +168,12 @@ impl<I: Read, O: Write> Coder<I, O> {
         self.output_stream.finish();
     }
 
-    pub fn get_stat(&self) -> (usize, usize) {
-        (self.input_len, self.output_stream.written_bytes)
+    pub fn written_bytes(&self) -> usize {
+        self.output_stream.written_bytes
+    }
+
+    pub fn get_mut(&mut self) -> &mut O {
+        &mut self.output_stream.output_stream
     }
 
     fn write_p_range(&mut self, p_range: PRange) {
@@ -127,11 +200,6 @@ impl<I: Read, O: Write> Coder<I, O> {
         }
     }
 
-    fn read_byte(&mut self) -> Option<u8> {
-        let mut buf = [0];
-        self.input_stream.read_exact(&mut buf).ok().and(Some(buf[0]))
-    }
-
     fn write_all_bits(&mut self, bit: bool) {
         self.output_stream.write_bit(bit);
         for _ in 0..self.not_yet_written_bits {
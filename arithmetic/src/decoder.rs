@@ -1,32 +1,78 @@
 #![allow(clippy::assign_op_pattern)]
 
-use std::io::{Read, Write};
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt::Display,
+    io::{self, ErrorKind, Read, Write},
+};
+
+use crate::{
+    checksum::Crc32,
+    model::{Model, SymbolModel, BYTE_ALPHABET_SIZE},
+    TRAILER_LEN,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    Truncated,
+    TrailingData,
+    LengthMismatch { expected: u64, decoded: u64 },
+    ChecksumMismatch { expected: u32, decoded: u32 },
+    Io(ErrorKind),
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::Truncated => write!(f, "truncated input"),
+            DecodeError::TrailingData => write!(f, "unexpected data after the end of the stream"),
+            DecodeError::LengthMismatch { expected, decoded } => write!(
+                f,
+                "corrupted input: expected {} bytes, decoded {}",
+                expected, decoded
+            ),
+            DecodeError::ChecksumMismatch { expected, decoded } => write!(
+                f,
+                "corrupted input: expected checksum {:#010x}, decoded {:#010x}",
+                expected, decoded
+            ),
+            DecodeError::Io(kind) => write!(f, "cannot read the input: {}", kind),
+        }
+    }
+}
 
-use crate::model::Model;
+impl Error for DecodeError {}
 
 pub struct BitInputStream<I> {
     buf: u8,
     n: u8,
     input_stream: I,
+    tail: VecDeque<u8>,
+    tail_len: usize,
+    // Kept until the decoder asks, read_bit then reports the end of the input
+    error: Option<io::Error>,
 }
 
 impl<I: Read> BitInputStream<I> {
     pub fn new(input_stream: I) -> Self {
+        Self::with_tail(input_stream, 0)
+    }
+
+    pub fn with_tail(input_stream: I, tail_len: usize) -> Self {
         Self {
             buf: 0,
             n: 0,
             input_stream,
+            tail: VecDeque::with_capacity(tail_len + 1),
+            tail_len,
+            error: None,
         }
     }
 
     pub fn read_bit(&mut self) -> Option<bool> {
         if self.n == 0 {
-            let mut tmp_buf = [0];
-            let read = self.input_stream.read_exact(&mut tmp_buf);
-            if read.is_err() {
-                return None;
-            }
-            self.buf = tmp_buf[0];
+            self.buf = self.read_byte()?;
         }
 
         let bit = self.buf & 0x80 == 0x80;
@@ -36,15 +82,59 @@ impl<I: Read> BitInputStream<I> {
 
         Some(bit)
     }
+
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    pub fn read_tail(&mut self) -> io::Result<Vec<u8>> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+        let mut rest = vec![];
+        self.input_stream.read_to_end(&mut rest)?;
+        self.tail.extend(rest);
+        Ok(self.tail.drain(..).collect())
+    }
+
+    fn read_byte(&mut self) -> Option<u8> {
+        while self.tail.len() <= self.tail_len {
+            let mut tmp_buf = [0];
+            if self.error.is_some() {
+                break;
+            }
+            if let Err(err) = self.input_stream.read_exact(&mut tmp_buf) {
+                if err.kind() != ErrorKind::UnexpectedEof {
+                    self.error = Some(err);
+                }
+                break;
+            }
+            self.tail.push_back(tmp_buf[0]);
+        }
+
+        if self.tail.len() > self.tail_len {
+            self.tail.pop_front()
+        } else {
+            None
+        }
+    }
 }
 
-pub struct Decoder<I, O> {
+pub struct SymbolDecoder<I> {
     input_stream: BitInputStream<I>,
-    output_stream: O,
     low: u32,
     high: u32,
     read_bits: u32,
+    bits_past_end: u32,
     model: Model,
+    started: bool,
+    finished: bool,
+}
+
+pub struct Decoder<I, O> {
+    symbol_decoder: SymbolDecoder<I>,
+    output_stream: O,
+    checksum: Crc32,
 
     output_len: usize,
 }
@@ -53,75 +143,175 @@ const HALF_U32: u32 = u32::MAX - (u32::MAX >> 1);
 const THREE_FOURTHS_U32: u32 = u32::MAX - (u32::MAX >> 2);
 const ONE_FOURTH_U32: u32 = THREE_FOURTHS_U32 - HALF_U32;
 
+const MAX_BITS_PAST_END: u32 = 32;
+
 impl<I: Read, O: Write> Decoder<I, O> {
     pub fn new(input_stream: I, output_stream: O) -> Self {
+        Self::with_model(input_stream, output_stream, Model::with_alphabet(BYTE_ALPHABET_SIZE))
+    }
+
+    pub fn with_model(input_stream: I, output_stream: O, model: Model) -> Self {
+        assert_eq!(model.alphabet_size(), BYTE_ALPHABET_SIZE, "Byte decoder needs a byte model");
+
         Self {
-            input_stream: BitInputStream::new(input_stream),
+            symbol_decoder: SymbolDecoder::from_bit_stream(
+                BitInputStream::with_tail(input_stream, TRAILER_LEN),
+                model,
+            ),
             output_stream,
+            checksum: Crc32::new(),
+
+            output_len: 0,
+        }
+    }
+
+    pub fn decode_all(&mut self) -> Result<(), DecodeError> {
+        while let Some(symbol) = self.symbol_decoder.decode_symbol()? {
+            self.write_byte(symbol as u8);
+        }
+
+        let trailer = self
+            .symbol_decoder
+            .input_stream
+            .read_tail()
+            .map_err(|err| DecodeError::Io(err.kind()))?;
+        if trailer.len() < TRAILER_LEN {
+            return Err(DecodeError::Truncated);
+        }
+        if trailer.len() > TRAILER_LEN {
+            return Err(DecodeError::TrailingData);
+        }
+
+        let expected_len = u64::from_le_bytes(trailer[..8].try_into().unwrap());
+        if expected_len != self.output_len as u64 {
+            return Err(DecodeError::LengthMismatch {
+                expected: expected_len,
+                decoded: self.output_len as u64,
+            });
+        }
+
+        let expected_checksum = u32::from_le_bytes(trailer[8..].try_into().unwrap());
+        let checksum = self.checksum.finish();
+        if expected_checksum != checksum {
+            return Err(DecodeError::ChecksumMismatch {
+                expected: expected_checksum,
+                decoded: checksum,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn get_stat(&self) -> usize {
+        self.output_len
+    }
+
+    fn write_byte(&mut self, byte: u8) {
+        self.output_stream.write_all(&[byte]).unwrap();
+        self.checksum.update(&[byte]);
+        self.output_len += 1;
+    }
+}
+
+impl<I: Read> SymbolDecoder<I> {
+    pub fn new(input_stream: I, alphabet_size: usize) -> Self {
+        Self::with_model(input_stream, Model::with_alphabet(alphabet_size))
+    }
+
+    pub fn with_model(input_stream: I, model: Model) -> Self {
+        Self::from_bit_stream(BitInputStream::new(input_stream), model)
+    }
+
+    fn from_bit_stream(input_stream: BitInputStream<I>, model: Model) -> Self {
+        Self {
+            input_stream,
             high: u32::MAX,
             low: 0,
             read_bits: 0,
-            model: Model::default(),
-
-            output_len: 0,
+            bits_past_end: 0,
+            model,
+            started: false,
+            finished: false,
         }
     }
 
-    pub fn decode_all(&mut self) {
-        for _ in 0..32 {
-            self.read_bit();
+    pub fn decode_symbol(&mut self) -> Result<Option<u32>, DecodeError> {
+        self.decode(None)
+    }
+
+    pub fn decode_symbol_with<M: SymbolModel>(&mut self, model: &mut M) -> Result<Option<u32>, DecodeError> {
+        self.decode(Some(model))
+    }
+
+    pub fn get_mut(&mut self) -> &mut I {
+        &mut self.input_stream.input_stream
+    }
+
+    fn decode(&mut self, external_model: Option<&mut dyn SymbolModel>) -> Result<Option<u32>, DecodeError> {
+        if self.finished {
+            return Ok(None);
         }
 
-        loop {
-            let range = self.high as u64 - self.low as u64 + 1;
-            let scaled_bits = ((self.read_bits - self.low + 1) as u64 * self.model.total_symbols as u64 - 1) / range;
+        if !self.started {
+            for _ in 0..32 {
+                self.read_bit()?;
+            }
+            self.started = true;
+        }
 
-            let symbol = self.model.get_symbol(scaled_bits as u32);
+        let model = match external_model {
+            Some(model) => model,
+            None => &mut self.model,
+        };
 
-            let Some(symbol) = symbol else {
-                break;
-            };
+        let range = self.high as u64 - self.low as u64 + 1;
+        let scaled_bits = ((self.read_bits - self.low + 1) as u64 * model.total() as u64 - 1) / range;
 
-            self.write_byte(symbol);
+        let symbol = model.get_symbol(scaled_bits as u32);
 
-            let p_range = self.model.get_p_range(symbol);
+        let Some(symbol) = symbol else {
+            self.finished = true;
+            return Ok(None);
+        };
 
-            self.model.save_symbol(symbol);
+        let p_range = model.get_p_range(symbol);
 
-            self.high = self.low + (((range * p_range.upper as u64) / p_range.denom as u64) - 1) as u32;
-            self.low = self.low + ((range * p_range.lower as u64) / p_range.denom as u64) as u32;
+        model.save_symbol(symbol);
 
-            loop {
-                if self.high < HALF_U32 || self.low >= HALF_U32 {
-                    self.shift_range();
-                    self.read_bit();
-                } else if self.low >= ONE_FOURTH_U32 && self.high < THREE_FOURTHS_U32 {
-                    self.low -= ONE_FOURTH_U32;
-                    self.high -= ONE_FOURTH_U32;
-                    self.read_bits -= ONE_FOURTH_U32;
-                    self.shift_range();
-                    self.read_bit();
-                } else {
-                    break;
-                }
+        self.high = self.low + (((range * p_range.upper as u64) / p_range.denom as u64) - 1) as u32;
+        self.low = self.low + ((range * p_range.lower as u64) / p_range.denom as u64) as u32;
+
+        loop {
+            if self.high < HALF_U32 || self.low >= HALF_U32 {
+                self.shift_range();
+                self.read_bit()?;
+            } else if self.low >= ONE_FOURTH_U32 && self.high < THREE_FOURTHS_U32 {
+                self.low -= ONE_FOURTH_U32;
+                self.high -= ONE_FOURTH_U32;
+                self.read_bits -= ONE_FOURTH_U32;
+                self.shift_range();
+                self.read_bit()?;
+            } else {
+                break;
             }
         }
-    }
 
-    pub fn get_stat(&self) -> usize {
-        self.output_len
+        Ok(Some(symbol))
     }
 
-    fn read_bit(&mut self) {
+    fn read_bit(&mut self) -> Result<(), DecodeError> {
         self.read_bits <<= 1;
         if let Some(input_bit) = self.input_stream.read_bit() {
             self.read_bits += if input_bit { 1 } else { 0 };
+        } else if let Some(err) = self.input_stream.take_error() {
+            return Err(DecodeError::Io(err.kind()));
+        } else {
+            self.bits_past_end += 1;
+            if self.bits_past_end > MAX_BITS_PAST_END {
+                return Err(DecodeError::Truncated);
+            }
         }
-    }
-
-    fn write_byte(&mut self, byte: u8) {
-        self.output_stream.write_all(&[byte]).unwrap();
-        self.output_len += 1;
+        Ok(())
     }
 
     fn shift_range(&mut self) {
@@ -133,7 +323,10 @@ impl<I: Read, O: Write> Decoder<I, O> {
 
 #[cfg(test)]
 mod tests {
-    use crate::coder::Coder;
+    use crate::{
+        coder::{Coder, SymbolCoder},
+        model::DEFAULT_TRAINED_COUNT,
+    };
 
     use super::*;
 
@@ -155,9 +348,152 @@ mod tests {
 
         let mut decoder = Decoder::new(output_stream.as_slice(), &mut decode_output_stream);
 
-        decoder.decode_all();
+        decoder.decode_all().unwrap();
 
         dbg!(String::from_utf8_lossy(&decode_output_stream));
         dbg!(input_len, comp_len);
     }
+
+    #[test]
+    fn symbols() {
+        let alphabet_size = 1 << 16;
+        let symbols = (0..100_000u32)
+            .map(|i| (i * 7919) % 1000 + if i % 3 == 0 { 60_000 } else { 0 })
+            .collect::<Vec<_>>();
+        let mut output_stream = Vec::<u8>::new();
+
+        let mut coder = SymbolCoder::new(&mut output_stream, alphabet_size);
+
+        for &symbol in &symbols {
+            coder.code_symbol(symbol);
+        }
+        coder.finish();
+
+        let mut decoder = SymbolDecoder::new(output_stream.as_slice(), alphabet_size);
+        let mut decoded = vec![];
+
+        while let Some(symbol) = decoder.decode_symbol().unwrap() {
+            decoded.push(symbol);
+        }
+
+        assert_eq!(symbols, decoded);
+    }
+
+    #[test]
+    fn truncated() {
+        let input_stream = include_str!("../../testy/pan_tadeusz.txt").as_bytes();
+        let mut output_stream = Vec::<u8>::new();
+
+        Coder::new(input_stream, &mut output_stream).code_all();
+
+        for cut in [1, TRAILER_LEN, output_stream.len() / 2, output_stream.len() - 1] {
+            let truncated = &output_stream[..output_stream.len() - cut];
+            let mut decode_output_stream = Vec::<u8>::new();
+            let mut decoder = Decoder::new(truncated, &mut decode_output_stream);
+
+            assert!(decoder.decode_all().is_err());
+        }
+
+        let mut symbol_stream = Vec::<u8>::new();
+        let mut coder = SymbolCoder::new(&mut symbol_stream, 1000);
+        for i in 0..10_000 {
+            coder.code_symbol(i % 1000);
+        }
+        coder.finish();
+
+        let truncated = &symbol_stream[..symbol_stream.len() / 2];
+        let mut decoder = SymbolDecoder::new(truncated, 1000);
+
+        let result = loop {
+            match decoder.decode_symbol() {
+                Ok(Some(_)) => continue,
+                result => break result,
+            }
+        };
+        assert_eq!(result, Err(DecodeError::Truncated));
+    }
+
+    // Gives the stream in small reads, then fails
+    struct FailingReader<'a> {
+        data: &'a [u8],
+    }
+
+    impl Read for FailingReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.data.is_empty() {
+                return Err(io::Error::new(ErrorKind::ConnectionReset, "gone"));
+            }
+            let len = buf.len().min(self.data.len()).min(7);
+            buf[..len].copy_from_slice(&self.data[..len]);
+            self.data = &self.data[len..];
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn read_error() {
+        let input_stream = include_str!("../../testy/pan_tadeusz.txt").as_bytes();
+        let mut output_stream = Vec::<u8>::new();
+
+        Coder::new(input_stream, &mut output_stream).code_all();
+
+        let mut decode_output_stream = Vec::<u8>::new();
+        let mut decoder = Decoder::new(FailingReader { data: &output_stream }, &mut decode_output_stream);
+        assert_eq!(decoder.decode_all(), Err(DecodeError::Io(ErrorKind::ConnectionReset)));
+
+        let mut decode_output_stream = Vec::<u8>::new();
+        let half = &output_stream[..output_stream.len() / 2];
+        let mut decoder = Decoder::new(FailingReader { data: half }, &mut decode_output_stream);
+        assert_eq!(decoder.decode_all(), Err(DecodeError::Io(ErrorKind::ConnectionReset)));
+    }
+
+    #[test]
+    fn corrupted() {
+        let input_stream = include_str!("../../testy/pan_tadeusz.txt").as_bytes();
+        let mut output_stream = Vec::<u8>::new();
+
+        Coder::new(input_stream, &mut output_stream).code_all();
+
+        let mut corrupted = output_stream.clone();
+        corrupted[100] ^= 0x10;
+
+        let mut decode_output_stream = Vec::<u8>::new();
+        let mut decoder = Decoder::new(corrupted.as_slice(), &mut decode_output_stream);
+
+        assert!(decoder.decode_all().is_err());
+
+        let mut trailing = output_stream.clone();
+        trailing.push(0);
+
+        let mut decode_output_stream = Vec::<u8>::new();
+        let mut decoder = Decoder::new(trailing.as_slice(), &mut decode_output_stream);
+
+        assert!(decoder.decode_all().is_err());
+    }
+
+    #[test]
+    fn pretrained() {
+        let sample = br#"{"level":"info","service":"auth","msg":"user logged in","user":42}
+{"level":"warn","service":"auth","msg":"password expired","user":7}
+{"level":"info","service":"billing","msg":"invoice sent","user":42}"#;
+        let record = br#"{"level":"info","service":"billing","msg":"user logged in","user":7}"#;
+
+        let mut model = Model::new();
+        model.train(sample.iter().map(|&b| b as u32));
+        model.rescale(DEFAULT_TRAINED_COUNT);
+
+        let mut plain_stream = Vec::<u8>::new();
+        Coder::new(record.as_slice(), &mut plain_stream).code_all();
+
+        let mut primed_stream = Vec::<u8>::new();
+        Coder::with_model(record.as_slice(), &mut primed_stream, model.clone()).code_all();
+
+        assert!(primed_stream.len() < plain_stream.len());
+
+        let mut decode_output_stream = Vec::<u8>::new();
+        let mut decoder = Decoder::with_model(primed_stream.as_slice(), &mut decode_output_stream, model);
+        decoder.decode_all().unwrap();
+
+        assert_eq!(record.as_slice(), decode_output_stream);
+    }
 }
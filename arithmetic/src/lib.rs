@@ -1,3 +1,6 @@
+pub mod checksum;
 pub mod coder;
 pub mod decoder;
-pub mod model;
\ No newline at end of file
+pub mod model;
+
+pub const TRAILER_LEN: usize = 12;
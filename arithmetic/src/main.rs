@@ -16,7 +16,7 @@ fn main() {
     
     let mut decoder = Decoder::new(output_stream.as_slice(), &mut decode_output_stream);
     
-    decoder.decode_all();
+    decoder.decode_all().unwrap();
     
     println!("{}", String::from_utf8(decode_output_stream).unwrap());
     dbg!(input_len,comp_len, input_len as f32 / comp_len as f32);
@@ -1,9 +1,21 @@
 #![allow(clippy::needless_range_loop)]
 
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, ErrorKind, Read, Write},
+    path::Path,
+};
+
 pub const CHARACTER_BUFFER_SIZE: usize = 256;
 
 pub const MAX_CHARACTER_COUNT: usize = 1 << 30;
 
+pub const BYTE_ALPHABET_SIZE: usize = 256;
+
+pub const DEFAULT_TRAINED_COUNT: u32 = 1 << 16;
+
+const MODEL_MAGIC: &[u8; 4] = b"AMDL";
+
 #[derive(Debug, Clone, Copy)]
 pub struct PRange {
     pub upper: u32,
@@ -11,22 +23,49 @@ pub struct PRange {
     pub denom: u32,
 }
 
+// Lets the symbol coders work with models other than the counting Model below
+pub trait SymbolModel {
+    fn total(&self) -> u32;
+    fn get_p_range(&self, symbol: u32) -> PRange;
+    fn get_symbol(&self, value: u32) -> Option<u32>;
+    fn save_symbol(&mut self, symbol: u32);
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Model {
-    symbol_upper_range: [u32; 257],
-    character_update_buffer: Vec<u8>,
+    symbol_upper_range: Vec<u32>,
+    character_update_buffer: Vec<u32>,
+    alphabet_size: usize,
     pub total_symbols: u32,
 }
 
 impl Model {
     pub fn new() -> Self {
+        Self::with_alphabet(BYTE_ALPHABET_SIZE)
+    }
+
+    pub fn with_alphabet(alphabet_size: usize) -> Self {
+        assert!(
+            alphabet_size < MAX_CHARACTER_COUNT,
+            "Alphabet size has to be smaller than {}",
+            MAX_CHARACTER_COUNT
+        );
+
         Self {
             character_update_buffer: Vec::new(),
-            symbol_upper_range: std::array::from_fn(|i| i as u32 + 1),
-            total_symbols: 257,
+            symbol_upper_range: (1..=alphabet_size as u32 + 1).collect(),
+            alphabet_size,
+            total_symbols: alphabet_size as u32 + 1,
         }
     }
 
-    pub fn get_p_range(&self, symbol: u8) -> PRange {
+    pub fn alphabet_size(&self) -> usize {
+        self.alphabet_size
+    }
+
+    pub fn get_p_range(&self, symbol: u32) -> PRange {
+        assert!((symbol as usize) < self.alphabet_size, "Symbol {} outside of the alphabet", symbol);
+
         let upper = self.symbol_upper_range[symbol as usize];
         let lower = (symbol as usize)
             .checked_sub(1)
@@ -41,8 +80,12 @@ impl Model {
     }
 
     pub fn get_eof_range(&self) -> PRange {
-        let upper = self.symbol_upper_range[256];
-        let lower = self.symbol_upper_range[255];
+        let upper = self.symbol_upper_range[self.alphabet_size];
+        let lower = self
+            .alphabet_size
+            .checked_sub(1)
+            .map(|i| self.symbol_upper_range[i])
+            .unwrap_or(0);
 
         PRange {
             upper,
@@ -51,46 +94,183 @@ impl Model {
         }
     }
 
-    pub fn get_symbol(&self, value: u32) -> Option<u8> {
-        let mut s = 0;
-        while value >= self.symbol_upper_range[s] {
-            s += 1;
-        }
-        if s == 256 {
+    pub fn get_symbol(&self, value: u32) -> Option<u32> {
+        let s = self.symbol_upper_range.partition_point(|&upper| upper <= value);
+        if s >= self.alphabet_size {
             None
         } else {
-            Some(s as u8)
+            Some(s as u32)
         }
     }
 
-    pub fn save_symbol(&mut self, symbol: u8) {
+    pub fn save_symbol(&mut self, symbol: u32) {
         if (self.total_symbols as usize) < MAX_CHARACTER_COUNT {
             self.character_update_buffer.push(symbol);
             if self.character_update_buffer.len() >= CHARACTER_BUFFER_SIZE {
                 self.update_model();
             }
         }
-
     }
 
     pub fn update_model(&mut self) {
-        let mut new_ranges = [0; 257];
+        let mut new_ranges = vec![0; self.alphabet_size + 1];
 
         for symbol in self.character_update_buffer.drain(..) {
             new_ranges[symbol as usize] += 1;
         }
 
-        for i in 1..257 {
+        for i in 1..=self.alphabet_size {
             new_ranges[i] += new_ranges[i - 1];
         }
 
-        for i in 0..257 {
+        for i in 0..=self.alphabet_size {
             self.symbol_upper_range[i] += new_ranges[i];
         }
 
-        self.total_symbols = self.symbol_upper_range[256];
+        self.total_symbols = self.symbol_upper_range[self.alphabet_size];
         //dbg!(self.symbol_upper_range);
     }
+
+    pub fn train(&mut self, symbols: impl IntoIterator<Item = u32>) {
+        // Counted in u64 so that long samples cannot overflow
+        let mut counts = self.counts().into_iter().map(u64::from).collect::<Vec<_>>();
+
+        for symbol in symbols {
+            assert!((symbol as usize) < self.alphabet_size, "Symbol {} outside of the alphabet", symbol);
+            counts[symbol as usize] += 1;
+        }
+
+        // Halved like in rescale until the total fits the coder
+        while counts.iter().sum::<u64>() >= MAX_CHARACTER_COUNT as u64 && counts.iter().any(|&c| c > 1) {
+            for count in counts.iter_mut() {
+                *count = count.div_ceil(2);
+            }
+        }
+
+        let counts = counts.into_iter().map(|c| c as u32).collect::<Vec<_>>();
+        self.set_counts(&counts);
+        self.check_counts();
+    }
+
+    pub fn rescale(&mut self, max_total: u32) {
+        assert!(max_total as usize > self.alphabet_size, "Total has to leave room for every symbol");
+
+        let mut counts = self.counts();
+
+        while counts.iter().map(|&c| c as u64).sum::<u64>() > max_total as u64 {
+            for count in counts.iter_mut() {
+                *count = count.div_ceil(2);
+            }
+        }
+
+        self.set_counts(&counts);
+        self.check_counts();
+    }
+
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(MODEL_MAGIC)?;
+        writer.write_all(&(self.alphabet_size as u32).to_le_bytes())?;
+        for count in self.counts() {
+            writer.write_all(&count.to_le_bytes())?;
+        }
+        writer.flush()
+    }
+
+    pub fn read_from<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != MODEL_MAGIC {
+            return Err(io::Error::new(ErrorKind::InvalidData, "not a model file"));
+        }
+
+        let mut buf = [0; 4];
+        reader.read_exact(&mut buf)?;
+        let alphabet_size = u32::from_le_bytes(buf) as usize;
+        if alphabet_size >= MAX_CHARACTER_COUNT {
+            return Err(io::Error::new(ErrorKind::InvalidData, "alphabet size too large"));
+        }
+
+        let mut counts = Vec::with_capacity(alphabet_size + 1);
+        let mut total = 0u64;
+        for _ in 0..=alphabet_size {
+            reader.read_exact(&mut buf)?;
+            let count = u32::from_le_bytes(buf);
+            if count == 0 {
+                return Err(io::Error::new(ErrorKind::InvalidData, "symbol with zero count"));
+            }
+            total += count as u64;
+            counts.push(count);
+        }
+        if total >= MAX_CHARACTER_COUNT as u64 {
+            return Err(io::Error::new(ErrorKind::InvalidData, "model total too large"));
+        }
+
+        let mut model = Self::with_alphabet(alphabet_size);
+        model.set_counts(&counts);
+        Ok(model)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write_to(BufWriter::new(File::create(path)?))
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::read_from(BufReader::new(File::open(path)?))
+    }
+
+    fn counts(&self) -> Vec<u32> {
+        let mut counts = Vec::with_capacity(self.alphabet_size + 1);
+        let mut lower = 0;
+        for &upper in &self.symbol_upper_range {
+            counts.push(upper - lower);
+            lower = upper;
+        }
+
+        for &symbol in &self.character_update_buffer {
+            counts[symbol as usize] += 1;
+        }
+
+        counts
+    }
+
+    // What read_from checks in a model file
+    fn check_counts(&self) {
+        assert!(self.counts().iter().all(|&count| count > 0), "Every symbol needs a non-zero count");
+        assert!(
+            (self.total_symbols as usize) < MAX_CHARACTER_COUNT,
+            "Model total has to be smaller than {}",
+            MAX_CHARACTER_COUNT
+        );
+    }
+
+    fn set_counts(&mut self, counts: &[u32]) {
+        let mut upper = 0;
+        for (i, &count) in counts.iter().enumerate() {
+            upper += count;
+            self.symbol_upper_range[i] = upper;
+        }
+
+        self.character_update_buffer.clear();
+        self.total_symbols = upper;
+    }
+}
+
+impl SymbolModel for Model {
+    fn total(&self) -> u32 {
+        self.total_symbols
+    }
+
+    fn get_p_range(&self, symbol: u32) -> PRange {
+        Model::get_p_range(self, symbol)
+    }
+
+    fn get_symbol(&self, value: u32) -> Option<u32> {
+        Model::get_symbol(self, value)
+    }
+
+    fn save_symbol(&mut self, symbol: u32) {
+        Model::save_symbol(self, symbol)
+    }
 }
 
 impl Default for Model {
@@ -98,3 +278,57 @@ impl Default for Model {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn train_save_read() {
+        let mut model = Model::new();
+        model.train(b"{\"level\":\"info\",\"msg\":\"ok\"}".iter().map(|&b| b as u32));
+        model.rescale(1000);
+
+        let mut buf = vec![];
+        model.write_to(&mut buf).unwrap();
+
+        let read_model = Model::read_from(buf.as_slice()).unwrap();
+        assert_eq!(model, read_model);
+
+        assert!(Model::read_from(&buf[..buf.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn rescale_keeps_symbols() {
+        let mut model = Model::with_alphabet(4);
+        model.train(std::iter::repeat_n(0, 100_000));
+        model.rescale(100);
+
+        assert!(model.total_symbols <= 100);
+        for symbol in 0..4 {
+            let p_range = model.get_p_range(symbol);
+            assert!(p_range.upper > p_range.lower);
+        }
+    }
+
+    #[test]
+    fn train_keeps_total_in_range() {
+        // A model file close to the largest total
+        let mut buf = b"AMDL".to_vec();
+        buf.extend_from_slice(&2u32.to_le_bytes());
+        for count in [MAX_CHARACTER_COUNT as u32 - 300, 100, 1] {
+            buf.extend_from_slice(&count.to_le_bytes());
+        }
+        let mut model = Model::read_from(buf.as_slice()).unwrap();
+
+        model.train(std::iter::repeat_n(1, 1000));
+        println!("total after training: {}", model.total_symbols);
+        assert!((model.total_symbols as usize) < MAX_CHARACTER_COUNT);
+        for symbol in 0..2 {
+            let p_range = model.get_p_range(symbol);
+            assert!(p_range.upper > p_range.lower);
+        }
+        let eof_range = model.get_eof_range();
+        assert!(eof_range.upper > eof_range.lower);
+    }
+}
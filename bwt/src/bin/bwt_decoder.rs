@@ -0,0 +1,29 @@
+use std::{env::args, io::{BufReader, BufWriter}, fs::File, time::Instant};
+
+use bwt::decoder::BwtDecoder;
+
+pub fn main() {
+    let args = args().collect::<Vec<_>>();
+
+    let input_path = args.get(1).expect("Please input the input file path");
+    let output_path = args.get(2).expect("Please input the output file path");
+
+    let input_stream = BufReader::new(File::open(input_path).unwrap());
+    let mut output_stream = BufWriter::new(File::create(output_path).unwrap());
+
+    let time_start = Instant::now();
+
+    let mut decoder = BwtDecoder::new(input_stream, &mut output_stream);
+    if let Err(err) = decoder.decode_all() {
+        eprintln!("Decoding failed: {}", err);
+        std::process::exit(1);
+    }
+    let output_len = decoder.get_stat();
+
+    let time_end = Instant::now();
+    let dur = (time_end - time_start).as_secs_f64();
+
+    println!("Compression statistics:");
+    println!("Output size: {:.3} MB", output_len as f64 / 1_000_000.0);
+    println!("Speed: {:.2} MB/s", output_len as f64 / (dur * 1_000_000.0));
+}
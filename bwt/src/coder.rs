@@ -0,0 +1,84 @@
+use std::io::{Read, Write};
+
+use arithmetic::coder::SymbolCoder;
+
+use crate::{mtf, rle, transform::transform, BWT_MAGIC, MAX_BLOCK_SIZE};
+
+pub struct BwtCoder<I, O> {
+    input_stream: I,
+    output_stream: O,
+    block_size: usize,
+
+    input_len: usize,
+    output_len: usize,
+}
+
+impl<I: Read, O: Write> BwtCoder<I, O> {
+    pub fn new(input_stream: I, output_stream: O, block_size: usize) -> Self {
+        assert!(
+            block_size > 0 && block_size <= MAX_BLOCK_SIZE,
+            "Block size has to be between 1 and {}",
+            MAX_BLOCK_SIZE
+        );
+
+        Self {
+            input_stream,
+            output_stream,
+            block_size,
+            input_len: 0,
+            output_len: 0,
+        }
+    }
+
+    pub fn code_all(&mut self) {
+        self.write(BWT_MAGIC);
+        self.write(&(self.block_size as u32).to_le_bytes());
+
+        loop {
+            let block = self.read_block();
+            if block.is_empty() {
+                break;
+            }
+            self.code_block(&block);
+        }
+
+        self.output_stream.flush().unwrap();
+    }
+
+    pub fn get_stat(&self) -> (usize, usize) {
+        (self.input_len, self.output_len)
+    }
+
+    fn code_block(&mut self, block: &[u8]) {
+        let bwt_block = transform(block);
+        let ranks = mtf::encode(&bwt_block.data);
+        let symbols = rle::encode(&ranks);
+
+        let mut payload = vec![];
+        let mut coder = SymbolCoder::new(&mut payload, rle::RLE_ALPHABET_SIZE);
+        for symbol in symbols {
+            coder.code_symbol(symbol);
+        }
+        coder.finish();
+
+        self.write(&(block.len() as u32).to_le_bytes());
+        self.write(&(bwt_block.primary_index as u32).to_le_bytes());
+        self.write(&(payload.len() as u32).to_le_bytes());
+        self.write(&payload);
+    }
+
+    fn read_block(&mut self) -> Vec<u8> {
+        let mut block = vec![];
+        (&mut self.input_stream)
+            .take(self.block_size as u64)
+            .read_to_end(&mut block)
+            .unwrap();
+        self.input_len += block.len();
+        block
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.output_stream.write_all(bytes).unwrap();
+        self.output_len += bytes.len();
+    }
+}
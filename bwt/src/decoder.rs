@@ -0,0 +1,233 @@
+use std::{
+    error::Error,
+    fmt::Display,
+    io::{ErrorKind, Read, Write},
+};
+
+use arithmetic::decoder::SymbolDecoder;
+
+use crate::{
+    mtf, rle,
+    transform::{inverse, BwtBlock},
+    BWT_MAGIC, MAX_BLOCK_SIZE,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    InvalidHeader,
+    InvalidBlock,
+    Truncated,
+    Arithmetic(arithmetic::decoder::DecodeError),
+    Io(ErrorKind),
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::InvalidHeader => write!(f, "not a BWT stream"),
+            DecodeError::InvalidBlock => write!(f, "corrupted block"),
+            DecodeError::Truncated => write!(f, "truncated input"),
+            DecodeError::Arithmetic(err) => write!(f, "{}", err),
+            DecodeError::Io(kind) => write!(f, "io error: {}", kind),
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+impl From<std::io::Error> for DecodeError {
+    fn from(err: std::io::Error) -> Self {
+        DecodeError::Io(err.kind())
+    }
+}
+
+impl From<arithmetic::decoder::DecodeError> for DecodeError {
+    fn from(err: arithmetic::decoder::DecodeError) -> Self {
+        DecodeError::Arithmetic(err)
+    }
+}
+
+pub struct BwtDecoder<I, O> {
+    input_stream: I,
+    output_stream: O,
+
+    output_len: usize,
+}
+
+impl<I: Read, O: Write> BwtDecoder<I, O> {
+    pub fn new(input_stream: I, output_stream: O) -> Self {
+        Self {
+            input_stream,
+            output_stream,
+            output_len: 0,
+        }
+    }
+
+    pub fn decode_all(&mut self) -> Result<(), DecodeError> {
+        let mut magic = [0; 4];
+        self.input_stream
+            .read_exact(&mut magic)
+            .map_err(|_| DecodeError::InvalidHeader)?;
+        if &magic != BWT_MAGIC {
+            return Err(DecodeError::InvalidHeader);
+        }
+        let block_size = self.read_u32()?.ok_or(DecodeError::InvalidHeader)? as usize;
+        if block_size == 0 || block_size > MAX_BLOCK_SIZE {
+            return Err(DecodeError::InvalidHeader);
+        }
+
+        while let Some(block_len) = self.read_u32()? {
+            let block_len = block_len as usize;
+            let primary_index = self.read_u32()?.ok_or(DecodeError::Truncated)? as usize;
+            let payload_len = self.read_u32()?.ok_or(DecodeError::Truncated)? as usize;
+
+            if block_len == 0 || block_len > block_size || !(1..=block_len).contains(&primary_index) {
+                return Err(DecodeError::InvalidBlock);
+            }
+
+            let mut payload = vec![];
+            (&mut self.input_stream)
+                .take(payload_len as u64)
+                .read_to_end(&mut payload)?;
+            if payload.len() != payload_len {
+                return Err(DecodeError::Truncated);
+            }
+
+            self.decode_block(&payload, block_len, primary_index)?;
+        }
+
+        self.output_stream.flush()?;
+        Ok(())
+    }
+
+    pub fn get_stat(&self) -> usize {
+        self.output_len
+    }
+
+    fn decode_block(&mut self, payload: &[u8], block_len: usize, primary_index: usize) -> Result<(), DecodeError> {
+        let mut decoder = SymbolDecoder::new(payload, rle::RLE_ALPHABET_SIZE);
+        let mut symbols = vec![];
+
+        while let Some(symbol) = decoder.decode_symbol()? {
+            if symbols.len() >= block_len {
+                return Err(DecodeError::InvalidBlock);
+            }
+            symbols.push(symbol);
+        }
+
+        let ranks = rle::decode(&symbols, block_len).ok_or(DecodeError::InvalidBlock)?;
+        if ranks.len() != block_len {
+            return Err(DecodeError::InvalidBlock);
+        }
+
+        let block = inverse(&BwtBlock {
+            data: mtf::decode(&ranks),
+            primary_index,
+        });
+
+        self.output_stream.write_all(&block)?;
+        self.output_len += block.len();
+        Ok(())
+    }
+
+    fn read_u32(&mut self) -> Result<Option<u32>, DecodeError> {
+        let mut buf = [0; 4];
+        let mut read = 0;
+        while read < buf.len() {
+            match self.input_stream.read(&mut buf[read..]) {
+                Ok(0) => break,
+                Ok(n) => read += n,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        match read {
+            0 => Ok(None),
+            4 => Ok(Some(u32::from_le_bytes(buf))),
+            _ => Err(DecodeError::Truncated),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coder::BwtCoder;
+
+    use super::*;
+
+    fn round_trip(buf: &[u8], block_size: usize) -> usize {
+        let mut coded_buf = vec![];
+        let mut output_buf = vec![];
+
+        let mut coder = BwtCoder::new(buf, &mut coded_buf, block_size);
+        coder.code_all();
+
+        let mut decoder = BwtDecoder::new(coded_buf.as_slice(), &mut output_buf);
+        decoder.decode_all().unwrap();
+
+        assert_eq!(buf, output_buf);
+        coded_buf.len()
+    }
+
+    #[test]
+    fn bwt_text() {
+        let buf = include_bytes!("./../../testy/pan_tadeusz.txt");
+        let coded_len = round_trip(buf, 50_000);
+
+        println!("original len: {}", buf.len());
+        println!("coded len: {}", coded_len);
+    }
+
+    #[test]
+    fn bwt_binary() {
+        round_trip(include_bytes!("./../../testy/test1.bin"), 30_000);
+        round_trip(include_bytes!("./../../testy/test3.bin"), 1 << 20);
+        round_trip(b"", 10);
+        round_trip(b"a", 10);
+    }
+
+    #[test]
+    fn bwt_truncated() {
+        let buf = include_bytes!("./../../testy/pan_tadeusz.txt");
+        let mut coded_buf = vec![];
+        BwtCoder::new(buf.as_slice(), &mut coded_buf, 50_000).code_all();
+
+        for cut in [1, 5, coded_buf.len() / 2] {
+            let mut output_buf = vec![];
+            let mut decoder = BwtDecoder::new(&coded_buf[..coded_buf.len() - cut], &mut output_buf);
+            assert!(decoder.decode_all().is_err());
+        }
+    }
+
+    #[test]
+    fn bwt_huge_block_size() {
+        let mut coded_buf = BWT_MAGIC.to_vec();
+        coded_buf.extend_from_slice(&u32::MAX.to_le_bytes());
+
+        let mut output_buf = vec![];
+        let mut decoder = BwtDecoder::new(coded_buf.as_slice(), &mut output_buf);
+        assert_eq!(decoder.decode_all(), Err(DecodeError::InvalidHeader));
+    }
+
+    struct FullDisk;
+
+    impl Write for FullDisk {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            Err(ErrorKind::StorageFull.into())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn bwt_write_error() {
+        let mut coded_buf = vec![];
+        BwtCoder::new(b"banana".as_slice(), &mut coded_buf, 10).code_all();
+
+        let mut decoder = BwtDecoder::new(coded_buf.as_slice(), FullDisk);
+        assert_eq!(decoder.decode_all(), Err(DecodeError::Io(ErrorKind::StorageFull)));
+    }
+}
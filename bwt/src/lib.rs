@@ -0,0 +1,14 @@
+pub mod suffix_array;
+pub mod transform;
+pub mod mtf;
+pub mod rle;
+pub mod coder;
+pub mod decoder;
+
+pub const BWT_MAGIC: &[u8; 4] = b"BWT0";
+
+pub const DEFAULT_BLOCK_SIZE: usize = 900_000;
+
+// The decoder needs several arrays of this length per block, so larger sizes
+// from a corrupted header are rejected before allocating
+pub const MAX_BLOCK_SIZE: usize = 1 << 22;
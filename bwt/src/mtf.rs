@@ -0,0 +1,55 @@
+pub struct MoveToFront {
+    table: [u8; 256],
+}
+
+impl MoveToFront {
+    pub fn new() -> Self {
+        Self {
+            table: std::array::from_fn(|i| i as u8),
+        }
+    }
+
+    pub fn encode(&mut self, byte: u8) -> u8 {
+        let rank = self.table.iter().position(|&b| b == byte).unwrap();
+        self.move_to_front(rank);
+        rank as u8
+    }
+
+    pub fn decode(&mut self, rank: u8) -> u8 {
+        let byte = self.table[rank as usize];
+        self.move_to_front(rank as usize);
+        byte
+    }
+
+    fn move_to_front(&mut self, rank: usize) {
+        self.table[..=rank].rotate_right(1);
+    }
+}
+
+impl Default for MoveToFront {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn encode(data: &[u8]) -> Vec<u8> {
+    let mut mtf = MoveToFront::new();
+    data.iter().map(|&byte| mtf.encode(byte)).collect()
+}
+
+pub fn decode(ranks: &[u8]) -> Vec<u8> {
+    let mut mtf = MoveToFront::new();
+    ranks.iter().map(|&rank| mtf.decode(rank)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mtf() {
+        let ranks = encode(b"aaabbbaaa");
+        assert_eq!(ranks, [97, 0, 0, 98, 0, 0, 1, 0, 0]);
+        assert_eq!(decode(&ranks), b"aaabbbaaa");
+    }
+}
@@ -0,0 +1,92 @@
+pub const RUN_A: u32 = 0;
+pub const RUN_B: u32 = 1;
+
+pub const RLE_ALPHABET_SIZE: usize = 257;
+
+pub fn encode(ranks: &[u8]) -> Vec<u32> {
+    let mut symbols = vec![];
+    let mut run = 0;
+
+    for &rank in ranks {
+        if rank == 0 {
+            run += 1;
+            continue;
+        }
+
+        push_run(&mut symbols, run);
+        run = 0;
+        symbols.push(rank as u32 + 1);
+    }
+    push_run(&mut symbols, run);
+
+    symbols
+}
+
+pub fn decode(symbols: &[u32], max_len: usize) -> Option<Vec<u8>> {
+    let mut ranks = vec![];
+    let mut run = 0;
+    let mut weight = 1;
+
+    for &symbol in symbols {
+        match symbol {
+            RUN_A | RUN_B => {
+                run += weight * (symbol as usize + 1);
+                weight *= 2;
+                if run > max_len {
+                    return None;
+                }
+            }
+            _ => {
+                if ranks.len() + run + 1 > max_len || symbol as usize >= RLE_ALPHABET_SIZE {
+                    return None;
+                }
+                ranks.resize(ranks.len() + run, 0);
+                run = 0;
+                weight = 1;
+                ranks.push((symbol - 1) as u8);
+            }
+        }
+    }
+
+    if ranks.len() + run > max_len {
+        return None;
+    }
+    ranks.resize(ranks.len() + run, 0);
+
+    Some(ranks)
+}
+
+fn push_run(symbols: &mut Vec<u32>, mut run: usize) {
+    while run > 0 {
+        if run % 2 == 1 {
+            symbols.push(RUN_A);
+            run = (run - 1) / 2;
+        } else {
+            symbols.push(RUN_B);
+            run = (run - 2) / 2;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs() {
+        for run in 0..100 {
+            let mut ranks = vec![0; run];
+            ranks.push(5);
+            ranks.extend(std::iter::repeat_n(0, run));
+
+            let symbols = encode(&ranks);
+            assert_eq!(decode(&symbols, ranks.len()).unwrap(), ranks);
+        }
+    }
+
+    #[test]
+    fn too_long() {
+        let symbols = encode(&[0; 100]);
+        assert!(decode(&symbols, 99).is_none());
+    }
+}
@@ -0,0 +1,54 @@
+pub fn suffix_array(input: &[u8]) -> Vec<usize> {
+    let n = input.len();
+    let mut sa = (0..n).collect::<Vec<_>>();
+    let mut rank = input.iter().map(|&byte| byte as usize + 1).collect::<Vec<_>>();
+    let mut new_rank = vec![0; n];
+
+    if n < 2 {
+        return sa;
+    }
+
+    let mut k = 1;
+    loop {
+        let key = |rank: &[usize], i: usize| (rank[i], if i + k < n { rank[i + k] } else { 0 });
+
+        sa.sort_unstable_by_key(|&i| key(&rank, i));
+
+        new_rank[sa[0]] = 1;
+        for j in 1..n {
+            let step = if key(&rank, sa[j - 1]) != key(&rank, sa[j]) { 1 } else { 0 };
+            new_rank[sa[j]] = new_rank[sa[j - 1]] + step;
+        }
+        std::mem::swap(&mut rank, &mut new_rank);
+
+        if rank[sa[n - 1]] == n {
+            break;
+        }
+        k *= 2;
+    }
+
+    sa
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn naive_suffix_array(input: &[u8]) -> Vec<usize> {
+        let mut sa = (0..input.len()).collect::<Vec<_>>();
+        sa.sort_by_key(|&i| &input[i..]);
+        sa
+    }
+
+    #[test]
+    fn banana() {
+        assert_eq!(suffix_array(b"banana"), vec![5, 3, 1, 0, 4, 2]);
+    }
+
+    #[test]
+    fn matches_naive() {
+        for input in [&b""[..], b"a", b"aaaaaaaa", b"abababab", b"mississippi", b"\x00\xff\x00\xff\x00"] {
+            assert_eq!(suffix_array(input), naive_suffix_array(input));
+        }
+    }
+}
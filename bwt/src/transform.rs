@@ -0,0 +1,97 @@
+use crate::suffix_array::suffix_array;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BwtBlock {
+    pub data: Vec<u8>,
+    pub primary_index: usize,
+}
+
+pub fn transform(block: &[u8]) -> BwtBlock {
+    let n = block.len();
+    if n == 0 {
+        return BwtBlock {
+            data: vec![],
+            primary_index: 0,
+        };
+    }
+
+    let sa = suffix_array(block);
+
+    let mut data = Vec::with_capacity(n);
+    let mut primary_index = 0;
+
+    data.push(block[n - 1]);
+    for (row, &pos) in sa.iter().enumerate() {
+        if pos == 0 {
+            primary_index = row + 1;
+        } else {
+            data.push(block[pos - 1]);
+        }
+    }
+
+    BwtBlock {
+        data,
+        primary_index,
+    }
+}
+
+pub fn inverse(block: &BwtBlock) -> Vec<u8> {
+    let n = block.data.len();
+    if n == 0 {
+        return vec![];
+    }
+    assert!(
+        (1..=n).contains(&block.primary_index),
+        "Primary index outside of the block"
+    );
+
+    let last_column = block.data[..block.primary_index]
+        .iter()
+        .map(|&byte| byte as usize + 1)
+        .chain(std::iter::once(0))
+        .chain(block.data[block.primary_index..].iter().map(|&byte| byte as usize + 1))
+        .collect::<Vec<_>>();
+
+    let mut first_row = [0; 258];
+    for &symbol in &last_column {
+        first_row[symbol + 1] += 1;
+    }
+    for symbol in 1..258 {
+        first_row[symbol] += first_row[symbol - 1];
+    }
+
+    let mut lf = vec![0; n + 1];
+    for (row, &symbol) in last_column.iter().enumerate() {
+        lf[row] = first_row[symbol];
+        first_row[symbol] += 1;
+    }
+
+    let mut output = vec![0; n];
+    let mut row = 0;
+    for k in (0..n).rev() {
+        output[k] = (last_column[row] - 1) as u8;
+        row = lf[row];
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn banana() {
+        let block = transform(b"banana");
+        assert_eq!(block.data, b"annbaa");
+        assert_eq!(inverse(&block), b"banana");
+    }
+
+    #[test]
+    fn round_trip() {
+        let text = include_bytes!("./../../testy/pan_tadeusz.txt");
+        for input in [&b""[..], b"x", b"aaaaaaaa", b"abracadabra", &text[..20_000]] {
+            assert_eq!(inverse(&transform(input)), input);
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use std::{env::args, fs, time::Instant};
+
+use deflate::{Format, DEFAULT_LEVEL, MAX_LEVEL};
+
+pub fn main() {
+    let args = args().collect::<Vec<_>>();
+
+    let format = args.get(1).expect("Input format raw/zlib/gzip");
+    let input_path = args.get(2).expect("Please input the input file path");
+    let output_path = args.get(3).expect("Please input the output file path");
+    let level = args
+        .get(4)
+        .map(|level| level.parse().expect("Level has to be a number 0-9"))
+        .unwrap_or(DEFAULT_LEVEL);
+    assert!(level <= MAX_LEVEL, "Level has to be a number 0-9");
+
+    let format = Format::from_name(format).expect("Input format as first argument (raw/zlib/gzip)");
+
+    let input = fs::read(input_path).unwrap();
+
+    
+    let time_start = Instant::now();
+
+    let output = format.compress(&input, level);
+    fs::write(output_path, &output).unwrap();
+    let (input_len, output_len) = (input.len(), output.len());
+    
+    let time_end = Instant::now();
+    let dur = (time_end - time_start).as_secs_f64();
+    
+    
+    println!("Compression statistics:");
+    println!("Format: {:?}, level: {}", format, level);
+    println!("Input size: {:.3} MB", input_len as f64 / 1_000_000.0);
+    println!("Output size: {:.3} MB", output_len as f64 / 1_000_000.0);
+    println!("Compression ratio: {:.3}", input_len as f64 / output_len as f64);
+    println!("Average code length: {:.3}", output_len as f64 * 8.0 / input_len as f64);
+    println!("Speed: {:.2} MB/s", input_len as f64 / (dur * 1_000_000.0));
+    
+    let entropy = entropy::quick_entropy_of_file(input_path);
+    println!("Entropy of source: {}", entropy);
+
+    let entropy = entropy::quick_entropy_of_file(output_path);
+    println!("Entropy of code: {}", entropy);
+}
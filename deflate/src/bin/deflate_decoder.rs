@@ -0,0 +1,36 @@
+use std::{env::args, fs, process::exit, time::Instant};
+
+use deflate::Format;
+
+pub fn main() {
+    let args = args().collect::<Vec<_>>();
+
+    let format = args.get(1).expect("Input format raw/zlib/gzip");
+    let input_path = args.get(2).expect("Please input the input file path");
+    let output_path = args.get(3).expect("Please input the output file path");
+
+    let format = Format::from_name(format).expect("Input format as first argument (raw/zlib/gzip)");
+
+    let input = fs::read(input_path).unwrap();
+
+    
+    let time_start = Instant::now();
+
+    let output = match format.decompress(&input) {
+        Ok(output) => output,
+        Err(err) => {
+            eprintln!("Decoding failed: {}", err);
+            exit(1);
+        }
+    };
+    fs::write(output_path, &output).unwrap();
+    let output_len = output.len();
+    
+    let time_end = Instant::now();
+    let dur = (time_end - time_start).as_secs_f64();
+    
+    
+    println!("Compression statistics:");
+    println!("Output size: {:.3} MB", output_len as f64 / 1_000_000.0);
+    println!("Speed: {:.2} MB/s", output_len as f64 / (dur * 1_000_000.0));
+}
@@ -0,0 +1,53 @@
+const ADLER_MOD: u32 = 65_521;
+
+// Largest block for which the sums cannot overflow before reduction
+const ADLER_BLOCK: usize = 5552;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Adler32 {
+    a: u32,
+    b: u32,
+}
+
+impl Adler32 {
+    pub fn new() -> Self {
+        Self { a: 1, b: 0 }
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        for block in bytes.chunks(ADLER_BLOCK) {
+            for &byte in block {
+                self.a += byte as u32;
+                self.b += self.a;
+            }
+            self.a %= ADLER_MOD;
+            self.b %= ADLER_MOD;
+        }
+    }
+
+    pub fn finish(&self) -> u32 {
+        (self.b << 16) | self.a
+    }
+}
+
+impl Default for Adler32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adler32_check_value() {
+        let mut adler = Adler32::new();
+        adler.update(b"Wikipedia");
+        assert_eq!(adler.finish(), 0x11E6_0398);
+
+        let mut adler = Adler32::new();
+        adler.update(&vec![0xFF; 100_000]);
+        assert_eq!(adler.finish(), 0x149A_302C);
+    }
+}
@@ -0,0 +1,330 @@
+use std::io::Write;
+
+use lzss::{
+    coder::{parse, LzssConfig},
+    level::FinderKind,
+    match_finder::Match,
+    token::Token,
+};
+use lzw::universal_coder::BitOutputStream;
+
+use crate::{
+    huffman::{canonical_codes, code_lengths, MAX_CODE_LEN, MAX_CODE_LENGTH_LEN},
+    tables::{
+        distance_code, fixed_distance_lengths, fixed_literal_lengths, length_code, CODE_LENGTH_CODES,
+        CODE_LENGTH_ORDER, DISTANCE_CODES, DISTANCE_EXTRA, END_OF_BLOCK, LENGTH_BASE, LENGTH_EXTRA,
+        LITERAL_LENGTH_CODES, DISTANCE_BASE,
+    },
+    WINDOW_BITS,
+};
+
+pub const MAX_STORED_LEN: usize = 65_535;
+
+const BLOCK_TOKENS: usize = 1 << 14;
+
+const STORED: u32 = 0b00;
+
+const FIXED: u32 = 0b01;
+
+const DYNAMIC: u32 = 0b10;
+
+struct Tree {
+    lengths: Vec<u8>,
+    codes: Vec<u16>,
+}
+
+impl Tree {
+    fn new(lengths: Vec<u8>) -> Self {
+        let codes = canonical_codes(&lengths);
+        Self { lengths, codes }
+    }
+
+    fn write<O: Write>(&self, output_stream: &mut BitOutputStream<O>, symbol: usize) {
+        output_stream.write_bits_lsb(self.codes[symbol] as u32, self.lengths[symbol] as u32);
+    }
+}
+
+struct DynamicHeader {
+    literal_count: usize,
+    distance_count: usize,
+    code_length_count: usize,
+    code_lengths: Vec<(u8, u8)>,
+    code_length_tree: Tree,
+}
+
+impl DynamicHeader {
+    fn new(literals: &Tree, distances: &Tree) -> Self {
+        let literal_count = last_used(&literals.lengths).max(257);
+        let distance_count = last_used(&distances.lengths).max(1);
+
+        let mut all_lengths = literals.lengths[..literal_count].to_vec();
+        all_lengths.extend_from_slice(&distances.lengths[..distance_count]);
+        let code_lengths = run_length(&all_lengths);
+
+        let mut freqs = [0; CODE_LENGTH_CODES];
+        for &(symbol, _) in &code_lengths {
+            freqs[symbol as usize] += 1;
+        }
+        let code_length_tree = Tree::new(code_lengths_for(&freqs, MAX_CODE_LENGTH_LEN));
+
+        let code_length_count = CODE_LENGTH_ORDER
+            .iter()
+            .rposition(|&symbol| code_length_tree.lengths[symbol] != 0)
+            .map_or(4, |last| (last + 1).max(4));
+
+        Self {
+            literal_count,
+            distance_count,
+            code_length_count,
+            code_lengths,
+            code_length_tree,
+        }
+    }
+
+    fn cost(&self) -> usize {
+        let mut cost = 5 + 5 + 4 + 3 * self.code_length_count;
+        for &(symbol, _) in &self.code_lengths {
+            cost += self.code_length_tree.lengths[symbol as usize] as usize + extra_run_bits(symbol) as usize;
+        }
+        cost
+    }
+
+    fn write<O: Write>(&self, output_stream: &mut BitOutputStream<O>) {
+        output_stream.write_bits_lsb((self.literal_count - 257) as u32, 5);
+        output_stream.write_bits_lsb((self.distance_count - 1) as u32, 5);
+        output_stream.write_bits_lsb((self.code_length_count - 4) as u32, 4);
+
+        for &symbol in &CODE_LENGTH_ORDER[..self.code_length_count] {
+            output_stream.write_bits_lsb(self.code_length_tree.lengths[symbol] as u32, 3);
+        }
+
+        for &(symbol, extra) in &self.code_lengths {
+            self.code_length_tree.write(output_stream, symbol as usize);
+            output_stream.write_bits_lsb(extra as u32, extra_run_bits(symbol));
+        }
+    }
+}
+
+fn last_used(lengths: &[u8]) -> usize {
+    lengths.iter().rposition(|&len| len != 0).map_or(0, |last| last + 1)
+}
+
+fn extra_run_bits(symbol: u8) -> u32 {
+    match symbol {
+        16 => 2,
+        17 => 3,
+        18 => 7,
+        _ => 0,
+    }
+}
+
+fn code_lengths_for(freqs: &[u32], max_len: u8) -> Vec<u8> {
+    let mut lengths = code_lengths(freqs, max_len);
+    // zlib rejects incomplete code length trees, so always keep two codes
+    let used = lengths.iter().filter(|&&len| len != 0).count();
+    for len in lengths.iter_mut().filter(|len| **len == 0).take(2usize.saturating_sub(used)) {
+        *len = 1;
+    }
+    lengths
+}
+
+// Code length alphabet of RFC 1951 3.2.7 as (symbol, extra bits value) pairs
+fn run_length(lengths: &[u8]) -> Vec<(u8, u8)> {
+    let mut symbols = vec![];
+    let mut i = 0;
+
+    while i < lengths.len() {
+        let len = lengths[i];
+        let mut run = lengths[i..].iter().take_while(|&&l| l == len).count();
+        i += run;
+
+        if len == 0 {
+            while run >= 11 {
+                let part = run.min(138);
+                symbols.push((18, (part - 11) as u8));
+                run -= part;
+            }
+            if run >= 3 {
+                symbols.push((17, (run - 3) as u8));
+                run = 0;
+            }
+        } else {
+            symbols.push((len, 0));
+            run -= 1;
+            while run >= 3 {
+                let part = run.min(6);
+                symbols.push((16, (part - 3) as u8));
+                run -= part;
+            }
+        }
+
+        for _ in 0..run {
+            symbols.push((len, 0));
+        }
+    }
+
+    symbols
+}
+
+fn token_len(token: &Token) -> usize {
+    match token {
+        Token::Literal(_) => 1,
+        Token::Match(m) => m.len,
+    }
+}
+
+fn tokens_cost(tokens: &[Token], literals: &Tree, distances: &Tree) -> usize {
+    let mut cost = literals.lengths[END_OF_BLOCK] as usize;
+    for token in tokens {
+        cost += match *token {
+            Token::Literal(byte) => literals.lengths[byte as usize] as usize,
+            Token::Match(Match { len, dist }) => {
+                let length_symbol = length_code(len);
+                let distance_symbol = distance_code(dist);
+                literals.lengths[257 + length_symbol] as usize
+                    + LENGTH_EXTRA[length_symbol] as usize
+                    + distances.lengths[distance_symbol] as usize
+                    + DISTANCE_EXTRA[distance_symbol] as usize
+            }
+        };
+    }
+    cost
+}
+
+fn write_tokens<O: Write>(output_stream: &mut BitOutputStream<O>, tokens: &[Token], literals: &Tree, distances: &Tree) {
+    for token in tokens {
+        match *token {
+            Token::Literal(byte) => literals.write(output_stream, byte as usize),
+            Token::Match(Match { len, dist }) => {
+                let length_symbol = length_code(len);
+                literals.write(output_stream, 257 + length_symbol);
+                output_stream.write_bits_lsb(
+                    (len - LENGTH_BASE[length_symbol] as usize) as u32,
+                    LENGTH_EXTRA[length_symbol] as u32,
+                );
+
+                let distance_symbol = distance_code(dist);
+                distances.write(output_stream, distance_symbol);
+                output_stream.write_bits_lsb(
+                    (dist - DISTANCE_BASE[distance_symbol] as usize) as u32,
+                    DISTANCE_EXTRA[distance_symbol] as u32,
+                );
+            }
+        }
+    }
+    literals.write(output_stream, END_OF_BLOCK);
+}
+
+fn write_stored<O: Write>(output_stream: &mut BitOutputStream<O>, data: &[u8], is_final: bool) {
+    let mut chunks = data.chunks(MAX_STORED_LEN).peekable();
+
+    loop {
+        let chunk = chunks.next().unwrap_or(&[]);
+        let last = chunks.peek().is_none();
+
+        output_stream.write_bits_lsb((is_final && last) as u32, 1);
+        output_stream.write_bits_lsb(STORED, 2);
+        output_stream.align_lsb();
+        output_stream.write_bytes(&(chunk.len() as u16).to_le_bytes());
+        output_stream.write_bytes(&(!(chunk.len() as u16)).to_le_bytes());
+        output_stream.write_bytes(chunk);
+
+        if last {
+            break;
+        }
+    }
+}
+
+fn write_block<O: Write>(output_stream: &mut BitOutputStream<O>, tokens: &[Token], data: &[u8], is_final: bool) {
+    let mut literal_freqs = [0; LITERAL_LENGTH_CODES];
+    let mut distance_freqs = [0; DISTANCE_CODES];
+    literal_freqs[END_OF_BLOCK] = 1;
+    for token in tokens {
+        match *token {
+            Token::Literal(byte) => literal_freqs[byte as usize] += 1,
+            Token::Match(Match { len, dist }) => {
+                literal_freqs[257 + length_code(len)] += 1;
+                distance_freqs[distance_code(dist)] += 1;
+            }
+        }
+    }
+
+    let fixed_literals = Tree::new(fixed_literal_lengths());
+    let fixed_distances = Tree::new(fixed_distance_lengths());
+    let literals = Tree::new(code_lengths_for(&literal_freqs, MAX_CODE_LEN));
+    let distances = Tree::new(code_lengths_for(&distance_freqs, MAX_CODE_LEN));
+    let header = DynamicHeader::new(&literals, &distances);
+
+    let fixed_cost = tokens_cost(tokens, &fixed_literals, &fixed_distances);
+    let dynamic_cost = header.cost() + tokens_cost(tokens, &literals, &distances);
+    let stored_cost = data.len().div_ceil(MAX_STORED_LEN).max(1) * (8 + 32) + data.len() * 8;
+
+    if stored_cost < fixed_cost.min(dynamic_cost) {
+        write_stored(output_stream, data, is_final);
+    } else if fixed_cost <= dynamic_cost {
+        output_stream.write_bits_lsb(is_final as u32, 1);
+        output_stream.write_bits_lsb(FIXED, 2);
+        write_tokens(output_stream, tokens, &fixed_literals, &fixed_distances);
+    } else {
+        output_stream.write_bits_lsb(is_final as u32, 1);
+        output_stream.write_bits_lsb(DYNAMIC, 2);
+        header.write(output_stream);
+        write_tokens(output_stream, tokens, &literals, &distances);
+    }
+}
+
+pub fn deflate(data: &[u8], level: u8) -> Vec<u8> {
+    let mut output = vec![];
+    let mut output_stream = BitOutputStream::new(&mut output);
+    deflate_into(&mut output_stream, data, level);
+    output_stream.align_lsb();
+    output_stream.flush();
+    output
+}
+
+// Writes the raw DEFLATE stream; the caller aligns and adds any framing.
+pub fn deflate_into<O: Write>(output_stream: &mut BitOutputStream<O>, data: &[u8], level: u8) {
+    if level == 0 {
+        write_stored(output_stream, data, true);
+        return;
+    }
+
+    let config = LzssConfig::new(WINDOW_BITS, level, FinderKind::HashChain);
+    let tokens = parse(data, &config);
+
+    if tokens.is_empty() {
+        write_block(output_stream, &[], &[], true);
+        return;
+    }
+
+    let mut start = 0;
+    let mut blocks = tokens.chunks(BLOCK_TOKENS).peekable();
+    while let Some(block) = blocks.next() {
+        let len = block.iter().map(token_len).sum::<usize>();
+        write_block(output_stream, block, &data[start..start + len], blocks.peek().is_none());
+        start += len;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_length_runs() {
+        let lengths = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 8, 8, 8, 8, 8, 8, 8, 8, 0, 0, 5];
+        let runs = run_length(&lengths);
+        assert_eq!(runs, [(18, 1), (8, 0), (16, 3), (8, 0), (0, 0), (0, 0), (5, 0)]);
+
+        let mut expanded = vec![];
+        for (symbol, extra) in runs {
+            match symbol {
+                16 => expanded.extend(std::iter::repeat_n(*expanded.last().unwrap(), 3 + extra as usize)),
+                17 => expanded.extend(std::iter::repeat_n(0, 3 + extra as usize)),
+                18 => expanded.extend(std::iter::repeat_n(0, 11 + extra as usize)),
+                len => expanded.push(len),
+            }
+        }
+        assert_eq!(expanded, lengths);
+    }
+}
@@ -0,0 +1,161 @@
+use arithmetic::checksum::Crc32;
+use lzw::{universal_coder::BitOutputStream, universal_decoder::BitInputStream};
+
+use crate::{deflater::deflate_into, inflater::inflate_from, DeflateError};
+
+const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+
+const CM_DEFLATE: u8 = 8;
+
+const FHCRC: u8 = 0x02;
+
+const FEXTRA: u8 = 0x04;
+
+const FNAME: u8 = 0x08;
+
+const FCOMMENT: u8 = 0x10;
+
+const RESERVED_FLAGS: u8 = 0xE0;
+
+const OS_UNKNOWN: u8 = 255;
+
+const HEADER_LEN: usize = 10;
+
+pub fn compress(data: &[u8], level: u8) -> Vec<u8> {
+    let xfl = match level {
+        1 => 4,
+        9 => 2,
+        _ => 0,
+    };
+
+    let mut output = vec![];
+    output.extend_from_slice(&GZIP_MAGIC);
+    output.extend_from_slice(&[CM_DEFLATE, 0, 0, 0, 0, 0, xfl, OS_UNKNOWN]);
+
+    let mut output_stream = BitOutputStream::new(&mut output);
+    deflate_into(&mut output_stream, data, level);
+    output_stream.align_lsb();
+
+    let mut crc = Crc32::new();
+    crc.update(data);
+    output_stream.write_bytes(&crc.finish().to_le_bytes());
+    output_stream.write_bytes(&(data.len() as u32).to_le_bytes());
+    output_stream.flush();
+
+    output
+}
+
+// Decodes every member and concatenates their contents, like gzip -d does.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, DeflateError> {
+    let mut output = vec![];
+    let mut rest = data;
+
+    loop {
+        rest = decompress_member(rest, &mut output)?;
+        if rest.is_empty() {
+            return Ok(output);
+        }
+    }
+}
+
+fn decompress_member<'a>(data: &'a [u8], output: &mut Vec<u8>) -> Result<&'a [u8], DeflateError> {
+    let header = data.get(..HEADER_LEN).ok_or(DeflateError::Truncated)?;
+    if header[..2] != GZIP_MAGIC || header[2] != CM_DEFLATE || header[3] & RESERVED_FLAGS != 0 {
+        return Err(DeflateError::InvalidHeader);
+    }
+    let flags = header[3];
+    let mut pos = HEADER_LEN;
+
+    if flags & FEXTRA != 0 {
+        let len = data.get(pos..pos + 2).ok_or(DeflateError::Truncated)?;
+        pos += 2 + u16::from_le_bytes([len[0], len[1]]) as usize;
+    }
+    for flag in [FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            let len = data.get(pos..).unwrap_or(&[]).iter().position(|&byte| byte == 0);
+            pos += len.ok_or(DeflateError::Truncated)? + 1;
+        }
+    }
+    if flags & FHCRC != 0 {
+        let stored = data.get(pos..pos + 2).ok_or(DeflateError::Truncated)?;
+        let mut crc = Crc32::new();
+        crc.update(&data[..pos]);
+        if u16::from_le_bytes([stored[0], stored[1]]) != crc.finish() as u16 {
+            return Err(DeflateError::InvalidHeader);
+        }
+        pos += 2;
+    }
+
+    let start = output.len();
+    let mut input_stream = BitInputStream::new(data.get(pos..).ok_or(DeflateError::Truncated)?);
+    inflate_from(&mut input_stream, output)?;
+    let rest = &data[pos + (input_stream.position() / 8) as usize..];
+
+    let trailer = rest.get(..8).ok_or(DeflateError::Truncated)?;
+    let mut crc = Crc32::new();
+    crc.update(&output[start..]);
+    let expected = u32::from_le_bytes(trailer[..4].try_into().unwrap());
+    let computed = crc.finish();
+    if expected != computed {
+        return Err(DeflateError::ChecksumMismatch { expected, computed });
+    }
+
+    let expected = u32::from_le_bytes(trailer[4..].try_into().unwrap());
+    let computed = (output.len() - start) as u32;
+    if expected != computed {
+        return Err(DeflateError::LengthMismatch { expected, computed });
+    }
+
+    Ok(&rest[8..])
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::DEFAULT_LEVEL;
+
+    use super::*;
+
+    #[test]
+    fn known_streams() {
+        // gzip.compress(b"hello", mtime=0)
+        let hello = [
+            0x1F, 0x8B, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xCB, 0x48, 0xCD, 0xC9, 0xC9, 0x07, 0x00, 0x86,
+            0xA6, 0x10, 0x36, 0x05, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(decompress(&hello).unwrap(), b"hello");
+        assert_eq!(compress(b"hello", DEFAULT_LEVEL)[HEADER_LEN..], hello[HEADER_LEN..]);
+
+        // Same member with FNAME "a" and FCOMMENT "b", followed by a second member
+        let mut named = hello[..HEADER_LEN].to_vec();
+        named[3] = FNAME | FCOMMENT;
+        named.extend_from_slice(b"a\0b\0");
+        named.extend_from_slice(&hello[HEADER_LEN..]);
+        named.extend_from_slice(&compress(b" world", 9));
+        assert_eq!(decompress(&named).unwrap(), b"hello world");
+    }
+
+    #[test]
+    fn round_trip() {
+        let buf = include_bytes!("./../../testy/test1.bin");
+
+        for level in [0, 1, DEFAULT_LEVEL, 9] {
+            let coded = compress(buf, level);
+            println!("level {} coded len: {}", level, coded.len());
+            assert_eq!(decompress(&coded).unwrap(), buf);
+        }
+    }
+
+    #[test]
+    fn corrupted() {
+        let mut coded = compress(b"hello", DEFAULT_LEVEL);
+        let len = coded.len();
+        coded[len - 1] ^= 1;
+        assert!(matches!(decompress(&coded), Err(DeflateError::LengthMismatch { .. })));
+        coded[len - 5] ^= 1;
+        assert!(matches!(decompress(&coded), Err(DeflateError::ChecksumMismatch { .. })));
+
+        coded[0] = 0;
+        assert_eq!(decompress(&coded), Err(DeflateError::InvalidHeader));
+        assert_eq!(decompress(&coded[..5]), Err(DeflateError::Truncated));
+    }
+}
@@ -0,0 +1,216 @@
+use std::{cmp::Reverse, collections::BinaryHeap, io::Read};
+
+use lzw::universal_decoder::BitInputStream;
+
+use crate::DeflateError;
+
+pub const MAX_CODE_LEN: u8 = 15;
+
+pub const MAX_CODE_LENGTH_LEN: u8 = 7;
+
+// Plain Huffman lengths, rebuilt from halved frequencies until they fit in
+// max_len. Symbols with zero frequency get no code.
+pub fn code_lengths(freqs: &[u32], max_len: u8) -> Vec<u8> {
+    let mut freqs = freqs.to_vec();
+
+    loop {
+        let lengths = huffman_lengths(&freqs);
+        if lengths.iter().all(|&len| len <= max_len) {
+            return lengths;
+        }
+
+        for freq in freqs.iter_mut().filter(|freq| **freq > 0) {
+            *freq = freq.div_ceil(2);
+        }
+    }
+}
+
+fn huffman_lengths(freqs: &[u32]) -> Vec<u8> {
+    let mut lengths = vec![0; freqs.len()];
+    let mut parents = vec![usize::MAX; freqs.len()];
+    let mut heap = BinaryHeap::new();
+
+    for (symbol, &freq) in freqs.iter().enumerate() {
+        if freq > 0 {
+            heap.push(Reverse((freq as u64, symbol)));
+        }
+    }
+
+    if heap.len() == 1 {
+        let Reverse((_, symbol)) = heap.pop().unwrap();
+        lengths[symbol] = 1;
+        return lengths;
+    }
+
+    while heap.len() > 1 {
+        let Reverse((freq_a, a)) = heap.pop().unwrap();
+        let Reverse((freq_b, b)) = heap.pop().unwrap();
+        let node = parents.len();
+        parents.push(usize::MAX);
+        parents[a] = node;
+        parents[b] = node;
+        heap.push(Reverse((freq_a + freq_b, node)));
+    }
+
+    for (symbol, length) in lengths.iter_mut().enumerate() {
+        if freqs[symbol] == 0 {
+            continue;
+        }
+        let mut node = symbol;
+        while parents[node] != usize::MAX {
+            node = parents[node];
+            *length += 1;
+        }
+    }
+
+    lengths
+}
+
+// Canonical codes as in RFC 1951 3.2.2, already bit-reversed for LSB-first output.
+pub fn canonical_codes(lengths: &[u8]) -> Vec<u16> {
+    let mut counts = [0u16; MAX_CODE_LEN as usize + 1];
+    for &len in lengths {
+        counts[len as usize] += 1;
+    }
+    counts[0] = 0;
+
+    let mut next_code = [0u16; MAX_CODE_LEN as usize + 1];
+    let mut code = 0;
+    for bits in 1..=MAX_CODE_LEN as usize {
+        code = (code + counts[bits - 1]) << 1;
+        next_code[bits] = code;
+    }
+
+    lengths
+        .iter()
+        .map(|&len| {
+            if len == 0 {
+                return 0;
+            }
+            let code = next_code[len as usize];
+            next_code[len as usize] += 1;
+            code.reverse_bits() >> (16 - len)
+        })
+        .collect()
+}
+
+// Codes up to this long are looked up in one step
+const FAST_BITS: u32 = 9;
+
+pub struct HuffmanDecoder {
+    counts: [u16; MAX_CODE_LEN as usize + 1],
+    symbols: Vec<u16>,
+    // Symbol and code length for every FAST_BITS bits a short code starts
+    // with, 0 for the longer codes
+    fast: Vec<u16>,
+}
+
+impl HuffmanDecoder {
+    pub fn new(lengths: &[u8]) -> Result<Self, DeflateError> {
+        let mut counts = [0u16; MAX_CODE_LEN as usize + 1];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut left: i32 = 1;
+        for &count in &counts[1..] {
+            left = (left << 1) - count as i32;
+            if left < 0 {
+                return Err(DeflateError::InvalidHuffmanTable);
+            }
+        }
+
+        let mut offsets = [0u16; MAX_CODE_LEN as usize + 2];
+        for len in 1..=MAX_CODE_LEN as usize {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+
+        let mut symbols = vec![0; offsets[MAX_CODE_LEN as usize + 1] as usize];
+        for (symbol, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                symbols[offsets[len as usize] as usize] = symbol as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+
+        let mut fast = vec![0; 1 << FAST_BITS];
+        for (symbol, (&len, code)) in lengths.iter().zip(canonical_codes(lengths)).enumerate() {
+            if len != 0 && len as u32 <= FAST_BITS {
+                for index in (code as usize..fast.len()).step_by(1 << len) {
+                    fast[index] = (symbol as u16) << 4 | len as u16;
+                }
+            }
+        }
+
+        Ok(Self { counts, symbols, fast })
+    }
+
+    pub fn decode<I: Read>(&self, input_stream: &mut BitInputStream<I>) -> Result<u16, DeflateError> {
+        let (mut bits, available) = input_stream.peek_lsb();
+        let entry = self.fast[(bits & ((1 << FAST_BITS) - 1)) as usize];
+        if entry != 0 && (entry & 0xF) as u32 <= available {
+            input_stream.consume((entry & 0xF) as u32);
+            return Ok(entry >> 4);
+        }
+
+        // Any code fits in a full buffer, so it is consumed once its length is known
+        if available >= MAX_CODE_LEN as u32 {
+            let (symbol, len) = self.walk(|| {
+                let bit = bits & 1;
+                bits >>= 1;
+                Ok(bit as i32)
+            })?;
+            input_stream.consume(len);
+            return Ok(symbol);
+        }
+
+        let (symbol, _) = self.walk(|| Ok(input_stream.read_bit_lsb().ok_or(DeflateError::Truncated)? as i32))?;
+        Ok(symbol)
+    }
+
+    // Canonical decoding one bit at a time, as (symbol, code length)
+    fn walk(&self, mut next_bit: impl FnMut() -> Result<i32, DeflateError>) -> Result<(u16, u32), DeflateError> {
+        let mut code: i32 = 0;
+        let mut first: i32 = 0;
+        let mut index: i32 = 0;
+
+        for len in 1..=MAX_CODE_LEN as usize {
+            code |= next_bit()?;
+            let count = self.counts[len] as i32;
+            if code - count < first {
+                return Ok((self.symbols[(index + code - first) as usize], len as u32));
+            }
+            index += count;
+            first += count;
+            first <<= 1;
+            code <<= 1;
+        }
+
+        Err(DeflateError::InvalidSymbol)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limited_lengths() {
+        let freqs = (0..40).map(|i| 1u32 << (i % 31)).collect::<Vec<_>>();
+        let lengths = code_lengths(&freqs, 7);
+
+        assert!(lengths.iter().all(|&len| (1..=7).contains(&len)));
+        let kraft: f64 = lengths.iter().map(|&len| 0.5f64.powi(len as i32)).sum();
+        assert!(kraft <= 1.0);
+    }
+
+    #[test]
+    fn rfc_example() {
+        // RFC 1951 3.2.2: lengths (3, 3, 3, 3, 3, 2, 4, 4) give F = 00, H = 1111
+        let codes = canonical_codes(&[3, 3, 3, 3, 3, 2, 4, 4]);
+        assert_eq!(codes[5], 0b00);
+        assert_eq!(codes[0], 0b010u16.reverse_bits() >> 13);
+        assert_eq!(codes[7], 0b1111);
+    }
+}
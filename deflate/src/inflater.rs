@@ -0,0 +1,201 @@
+use std::io::Read;
+
+use lzw::universal_decoder::BitInputStream;
+
+use crate::{
+    huffman::HuffmanDecoder,
+    tables::{
+        fixed_distance_lengths, fixed_literal_lengths, CODE_LENGTH_CODES, CODE_LENGTH_ORDER, DISTANCE_BASE,
+        DISTANCE_CODES, DISTANCE_EXTRA, END_OF_BLOCK, LENGTH_BASE, LENGTH_EXTRA, LITERAL_LENGTH_CODES,
+    },
+    DeflateError,
+};
+
+pub fn inflate(data: &[u8]) -> Result<Vec<u8>, DeflateError> {
+    let mut output = vec![];
+    let mut input_stream = BitInputStream::new(data);
+    inflate_from(&mut input_stream, &mut output)?;
+    Ok(output)
+}
+
+// Appends one raw DEFLATE stream to output and leaves the input aligned to the
+// byte after the final block. Matches may only reach back to where this stream
+// started in output. Codes are decoded from peeked bits, so up to 8 bytes past
+// the stream may be taken from the input, position tells where it ended.
+pub fn inflate_from<I: Read>(input_stream: &mut BitInputStream<I>, output: &mut Vec<u8>) -> Result<(), DeflateError> {
+    let start = output.len();
+
+    loop {
+        let is_final = read_bits(input_stream, 1)? == 1;
+
+        match read_bits(input_stream, 2)? {
+            0b00 => inflate_stored(input_stream, output)?,
+            0b01 => {
+                let literals = HuffmanDecoder::new(&fixed_literal_lengths())?;
+                let distances = HuffmanDecoder::new(&fixed_distance_lengths())?;
+                inflate_codes(input_stream, output, start, &literals, &distances)?;
+            }
+            0b10 => {
+                let (literals, distances) = read_dynamic_header(input_stream)?;
+                inflate_codes(input_stream, output, start, &literals, &distances)?;
+            }
+            _ => return Err(DeflateError::InvalidBlockType),
+        }
+
+        if is_final {
+            break;
+        }
+    }
+
+    input_stream.align();
+    Ok(())
+}
+
+fn read_bits<I: Read>(input_stream: &mut BitInputStream<I>, count: u32) -> Result<u32, DeflateError> {
+    input_stream.read_bits_lsb(count).ok_or(DeflateError::Truncated)
+}
+
+fn inflate_stored<I: Read>(input_stream: &mut BitInputStream<I>, output: &mut Vec<u8>) -> Result<(), DeflateError> {
+    input_stream.align();
+
+    let mut header = [0; 4];
+    input_stream.read_bytes(&mut header).ok_or(DeflateError::Truncated)?;
+    let len = u16::from_le_bytes([header[0], header[1]]);
+    let nlen = u16::from_le_bytes([header[2], header[3]]);
+    if len != !nlen {
+        return Err(DeflateError::StoredLengthMismatch);
+    }
+
+    let from = output.len();
+    output.resize(from + len as usize, 0);
+    input_stream.read_bytes(&mut output[from..]).ok_or(DeflateError::Truncated)
+}
+
+fn read_dynamic_header<I: Read>(
+    input_stream: &mut BitInputStream<I>,
+) -> Result<(HuffmanDecoder, HuffmanDecoder), DeflateError> {
+    let literal_count = read_bits(input_stream, 5)? as usize + 257;
+    let distance_count = read_bits(input_stream, 5)? as usize + 1;
+    let code_length_count = read_bits(input_stream, 4)? as usize + 4;
+    if literal_count > LITERAL_LENGTH_CODES || distance_count > DISTANCE_CODES {
+        return Err(DeflateError::InvalidHuffmanTable);
+    }
+
+    let mut code_length_lengths = [0; CODE_LENGTH_CODES];
+    for &symbol in &CODE_LENGTH_ORDER[..code_length_count] {
+        code_length_lengths[symbol] = read_bits(input_stream, 3)? as u8;
+    }
+    let code_length_decoder = HuffmanDecoder::new(&code_length_lengths)?;
+
+    let mut lengths = Vec::with_capacity(literal_count + distance_count);
+    while lengths.len() < literal_count + distance_count {
+        let (len, repeat) = match code_length_decoder.decode(input_stream)? {
+            len @ 0..=15 => (len as u8, 1),
+            16 => {
+                let &previous = lengths.last().ok_or(DeflateError::InvalidHuffmanTable)?;
+                (previous, 3 + read_bits(input_stream, 2)?)
+            }
+            17 => (0, 3 + read_bits(input_stream, 3)?),
+            _ => (0, 11 + read_bits(input_stream, 7)?),
+        };
+
+        if lengths.len() + repeat as usize > literal_count + distance_count {
+            return Err(DeflateError::InvalidHuffmanTable);
+        }
+        lengths.extend(std::iter::repeat_n(len, repeat as usize));
+    }
+
+    if lengths[END_OF_BLOCK] == 0 {
+        return Err(DeflateError::InvalidHuffmanTable);
+    }
+
+    let literals = HuffmanDecoder::new(&lengths[..literal_count])?;
+    let distances = HuffmanDecoder::new(&lengths[literal_count..])?;
+    Ok((literals, distances))
+}
+
+fn inflate_codes<I: Read>(
+    input_stream: &mut BitInputStream<I>,
+    output: &mut Vec<u8>,
+    start: usize,
+    literals: &HuffmanDecoder,
+    distances: &HuffmanDecoder,
+) -> Result<(), DeflateError> {
+    loop {
+        let symbol = literals.decode(input_stream)? as usize;
+
+        if symbol < END_OF_BLOCK {
+            output.push(symbol as u8);
+            continue;
+        }
+        if symbol == END_OF_BLOCK {
+            return Ok(());
+        }
+
+        let length_symbol = symbol - 257;
+        if length_symbol >= LENGTH_BASE.len() {
+            return Err(DeflateError::InvalidSymbol);
+        }
+        let len = LENGTH_BASE[length_symbol] as usize + read_bits(input_stream, LENGTH_EXTRA[length_symbol] as u32)? as usize;
+
+        let distance_symbol = distances.decode(input_stream)? as usize;
+        if distance_symbol >= DISTANCE_BASE.len() {
+            return Err(DeflateError::InvalidSymbol);
+        }
+        let dist =
+            DISTANCE_BASE[distance_symbol] as usize + read_bits(input_stream, DISTANCE_EXTRA[distance_symbol] as u32)? as usize;
+
+        let available = output.len() - start;
+        if dist > available {
+            return Err(DeflateError::InvalidDistance { dist, available });
+        }
+
+        let from = output.len() - dist;
+        for i in 0..len {
+            output.push(output[from + i]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{deflater::deflate, DEFAULT_LEVEL};
+
+    use super::*;
+
+    #[test]
+    fn known_streams() {
+        // zlib.compressobj(wbits=-15) output for b"hello hello hello hello"
+        let fixed = [0xCB, 0x48, 0xCD, 0xC9, 0xC9, 0x57, 0xC8, 0x40, 0x27, 0x01];
+        assert_eq!(inflate(&fixed).unwrap(), b"hello hello hello hello");
+
+        let stored = [0x01, 0x03, 0x00, 0xFC, 0xFF, b'a', b'b', b'c'];
+        assert_eq!(inflate(&stored).unwrap(), b"abc");
+    }
+
+    #[test]
+    fn round_trip() {
+        let buf = include_bytes!("./../../testy/pan_tadeusz.txt");
+
+        for level in [0, 1, DEFAULT_LEVEL, 9] {
+            let coded = deflate(buf, level);
+            println!("level {} coded len: {}", level, coded.len());
+            assert_eq!(inflate(&coded).unwrap(), buf);
+        }
+
+        for input in [&b""[..], b"a", &[0; 70_000], include_bytes!("./../../testy/test3.bin")] {
+            assert_eq!(inflate(&deflate(input, DEFAULT_LEVEL)).unwrap(), input);
+            assert_eq!(inflate(&deflate(input, 0)).unwrap(), input);
+        }
+    }
+
+    #[test]
+    fn corrupted() {
+        assert_eq!(inflate(&[0x07]), Err(DeflateError::InvalidBlockType));
+        assert_eq!(inflate(&[0x01, 0x03, 0x00, 0xFC, 0xFE]), Err(DeflateError::StoredLengthMismatch));
+        assert_eq!(inflate(&[0x01, 0x03, 0x00, 0xFC, 0xFF, b'a']), Err(DeflateError::Truncated));
+
+        let coded = deflate(include_bytes!("./../../testy/pan_tadeusz.txt"), DEFAULT_LEVEL);
+        assert_eq!(inflate(&coded[..coded.len() / 2]), Err(DeflateError::Truncated));
+    }
+}
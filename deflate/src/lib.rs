@@ -0,0 +1,93 @@
+use std::{
+    error::Error,
+    fmt::{self, Display},
+};
+
+pub mod checksum;
+pub mod deflater;
+pub mod gzip;
+pub mod huffman;
+pub mod inflater;
+pub mod tables;
+pub mod zlib;
+
+pub const WINDOW_BITS: u8 = 15;
+
+pub const MIN_LEVEL: u8 = 0;
+
+pub const MAX_LEVEL: u8 = 9;
+
+pub const DEFAULT_LEVEL: u8 = 6;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeflateError {
+    InvalidBlockType,
+    StoredLengthMismatch,
+    InvalidHuffmanTable,
+    InvalidSymbol,
+    InvalidDistance { dist: usize, available: usize },
+    Truncated,
+    InvalidHeader,
+    UnsupportedDictionary,
+    ChecksumMismatch { expected: u32, computed: u32 },
+    LengthMismatch { expected: u32, computed: u32 },
+}
+
+impl Display for DeflateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeflateError::InvalidBlockType => write!(f, "reserved block type"),
+            DeflateError::StoredLengthMismatch => write!(f, "stored block length does not match its complement"),
+            DeflateError::InvalidHuffmanTable => write!(f, "invalid huffman table"),
+            DeflateError::InvalidSymbol => write!(f, "invalid symbol"),
+            DeflateError::InvalidDistance { dist, available } => {
+                write!(f, "distance {} reaches before the {} decoded bytes", dist, available)
+            }
+            DeflateError::Truncated => write!(f, "stream ends before the final block"),
+            DeflateError::InvalidHeader => write!(f, "invalid header"),
+            DeflateError::UnsupportedDictionary => write!(f, "preset dictionaries are not supported"),
+            DeflateError::ChecksumMismatch { expected, computed } => {
+                write!(f, "checksum mismatch: expected {:#010x}, computed {:#010x}", expected, computed)
+            }
+            DeflateError::LengthMismatch { expected, computed } => {
+                write!(f, "length mismatch: expected {}, computed {}", expected, computed)
+            }
+        }
+    }
+}
+
+impl Error for DeflateError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Raw,
+    Zlib,
+    Gzip,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "raw" => Some(Format::Raw),
+            "zlib" => Some(Format::Zlib),
+            "gzip" => Some(Format::Gzip),
+            _ => None,
+        }
+    }
+
+    pub fn compress(self, data: &[u8], level: u8) -> Vec<u8> {
+        match self {
+            Format::Raw => deflater::deflate(data, level),
+            Format::Zlib => zlib::compress(data, level),
+            Format::Gzip => gzip::compress(data, level),
+        }
+    }
+
+    pub fn decompress(self, data: &[u8]) -> Result<Vec<u8>, DeflateError> {
+        match self {
+            Format::Raw => inflater::inflate(data),
+            Format::Zlib => zlib::decompress(data),
+            Format::Gzip => gzip::decompress(data),
+        }
+    }
+}
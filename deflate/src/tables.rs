@@ -0,0 +1,50 @@
+pub const END_OF_BLOCK: usize = 256;
+
+pub const LITERAL_LENGTH_CODES: usize = 286;
+
+pub const DISTANCE_CODES: usize = 30;
+
+pub const CODE_LENGTH_CODES: usize = 19;
+
+pub const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+
+pub const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+pub const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145,
+    8193, 12289, 16385, 24577,
+];
+
+pub const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+
+pub const CODE_LENGTH_ORDER: [usize; CODE_LENGTH_CODES] =
+    [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+pub fn length_code(len: usize) -> usize {
+    LENGTH_BASE.partition_point(|&base| base as usize <= len) - 1
+}
+
+pub fn distance_code(dist: usize) -> usize {
+    DISTANCE_BASE.partition_point(|&base| base as usize <= dist) - 1
+}
+
+pub fn fixed_literal_lengths() -> Vec<u8> {
+    (0..288)
+        .map(|symbol| match symbol {
+            0..=143 => 8,
+            144..=255 => 9,
+            256..=279 => 7,
+            _ => 8,
+        })
+        .collect()
+}
+
+pub fn fixed_distance_lengths() -> Vec<u8> {
+    vec![5; 32]
+}
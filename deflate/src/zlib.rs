@@ -0,0 +1,98 @@
+use lzw::{universal_coder::BitOutputStream, universal_decoder::BitInputStream};
+
+use crate::{checksum::Adler32, deflater::deflate_into, inflater::inflate_from, DeflateError, WINDOW_BITS};
+
+const CM_DEFLATE: u8 = 8;
+
+const FDICT: u8 = 0x20;
+
+pub fn compress(data: &[u8], level: u8) -> Vec<u8> {
+    let cmf = ((WINDOW_BITS - 8) << 4) | CM_DEFLATE;
+    let flevel = match level {
+        0..=1 => 0,
+        2..=5 => 1,
+        6 => 2,
+        _ => 3,
+    };
+    let mut flg = flevel << 6;
+    flg |= ((31 - (cmf as u16 * 256 + flg as u16) % 31) % 31) as u8;
+
+    let mut output = vec![cmf, flg];
+    let mut output_stream = BitOutputStream::new(&mut output);
+    deflate_into(&mut output_stream, data, level);
+    output_stream.align_lsb();
+
+    let mut adler = Adler32::new();
+    adler.update(data);
+    output_stream.write_bytes(&adler.finish().to_be_bytes());
+    output_stream.flush();
+
+    output
+}
+
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, DeflateError> {
+    let [cmf, flg, ..] = *data else {
+        return Err(DeflateError::Truncated);
+    };
+    if cmf & 0x0F != CM_DEFLATE || cmf >> 4 > WINDOW_BITS - 8 || !(cmf as u16 * 256 + flg as u16).is_multiple_of(31) {
+        return Err(DeflateError::InvalidHeader);
+    }
+    if flg & FDICT != 0 {
+        return Err(DeflateError::UnsupportedDictionary);
+    }
+
+    let mut output = vec![];
+    let mut input_stream = BitInputStream::new(&data[2..]);
+    inflate_from(&mut input_stream, &mut output)?;
+    let rest = &data[2 + (input_stream.position() / 8) as usize..];
+
+    let trailer = rest.get(..4).ok_or(DeflateError::Truncated)?;
+    let expected = u32::from_be_bytes(trailer.try_into().unwrap());
+    let mut adler = Adler32::new();
+    adler.update(&output);
+    let computed = adler.finish();
+    if expected != computed {
+        return Err(DeflateError::ChecksumMismatch { expected, computed });
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::DEFAULT_LEVEL;
+
+    use super::*;
+
+    #[test]
+    fn known_streams() {
+        assert_eq!(compress(b"", DEFAULT_LEVEL), [0x78, 0x9C, 0x03, 0x00, 0x00, 0x00, 0x00, 0x01]);
+
+        // zlib.compress(b"hello")
+        let hello = [0x78, 0x9C, 0xCB, 0x48, 0xCD, 0xC9, 0xC9, 0x07, 0x00, 0x06, 0x2C, 0x02, 0x15];
+        assert_eq!(decompress(&hello).unwrap(), b"hello");
+        assert_eq!(compress(b"hello", DEFAULT_LEVEL), hello);
+    }
+
+    #[test]
+    fn round_trip() {
+        let buf = include_bytes!("./../../testy/pride_and_prejudice.txt");
+
+        for level in [0, 1, DEFAULT_LEVEL, 9] {
+            let coded = compress(buf, level);
+            println!("level {} coded len: {}", level, coded.len());
+            assert_eq!(decompress(&coded).unwrap(), buf);
+        }
+    }
+
+    #[test]
+    fn corrupted() {
+        let mut coded = compress(b"hello", DEFAULT_LEVEL);
+        let last = coded.len() - 1;
+        coded[last] ^= 1;
+        assert!(matches!(decompress(&coded), Err(DeflateError::ChecksumMismatch { .. })));
+
+        assert_eq!(decompress(&[0x78, 0x9D]), Err(DeflateError::InvalidHeader));
+        assert_eq!(decompress(&[0x78, 0xBB, 0x00]), Err(DeflateError::UnsupportedDictionary));
+    }
+}
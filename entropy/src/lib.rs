@@ -59,6 +59,21 @@ pub fn conditional_count(content: &[u8]) -> ConditionalCountTable {
     table
 }
 
+// Streaming variant of conditional_count, the first byte follows a zero
+pub fn conditional_count_iter(content: impl Iterator<Item = u8>) -> ConditionalCountTable {
+    let mut table = ConditionalCountTable::zeroed();
+    let mut previous = 0;
+
+    for byte in content {
+        table.tables[previous as usize].counters[byte as usize] += 1;
+        table.tables[previous as usize].sum += 1;
+        table.sum += 1;
+        previous = byte;
+    }
+
+    table
+}
+
 pub fn entropy(table: &CountTable) -> f64 {
     table
         .counters
@@ -95,6 +110,20 @@ impl ConditionalCountTable {
             sum: 0,
         }
     }
+
+    // Counts of the second bytes regardless of the first, the same as count
+    pub fn marginal(&self) -> CountTable {
+        let mut table = CountTable::zeroed();
+
+        for t in &self.tables {
+            for (counter, c) in table.counters.iter_mut().zip(t.counters) {
+                *counter += c;
+            }
+        }
+        table.sum = self.sum;
+
+        table
+    }
 }
 
 impl Display for CountTable {
@@ -128,12 +157,9 @@ impl Display for ConditionalCountTable {
 pub fn quick_entropy_of_file(file_path: impl AsRef<std::path::Path>) -> f64 {
     use std::{io::{Read, BufReader}, fs::File};
 
-    let mut reader = BufReader::new(File::open(file_path).unwrap());
+    let reader = BufReader::new(File::open(file_path).unwrap());
 
-    let mut buf = Vec::new();
-    reader.read_to_end(&mut buf).unwrap();
-
-    let count_table = count(&buf);
+    let count_table = count_iter(reader.bytes().map(Result::unwrap));
     entropy(&count_table)
 }
 
@@ -189,4 +215,14 @@ mod test {
         println!("{}", &count_table);
         println!("{}", conditional_entropy(&count_table));
     }
+
+    #[test]
+    fn conditional_count_iter_test() {
+        let content = [0, 1, 3, 1, 0, 255, 12, 12, 12, 1];
+
+        let count_table = conditional_count_iter(content.iter().copied());
+
+        assert_eq!(count_table.to_string(), conditional_count(&content).to_string());
+        assert_eq!(count_table.marginal().to_string(), count(&content).to_string());
+    }
 }
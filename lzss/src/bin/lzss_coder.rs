@@ -0,0 +1,54 @@
+use std::{env::args, io::{BufReader, BufWriter}, fs::File, time::Instant};
+
+use lzss::{coder::{LzssCoder, LzssConfig}, level::{FinderKind, DEFAULT_LEVEL}, Backend, DEFAULT_WINDOW_BITS};
+
+pub fn main() {
+    let args = args().collect::<Vec<_>>();
+
+    let coding = args.get(1).expect("Input coding arithmetic/gamma/delta/omega/fib/binary");
+    let input_path = args.get(2).expect("Please input the input file path");
+    let output_path = args.get(3).expect("Please input the output file path");
+    let level = args
+        .get(4)
+        .map(|level| level.parse().expect("Level has to be a number 1-9"))
+        .unwrap_or(DEFAULT_LEVEL);
+    let window_bits = args
+        .get(5)
+        .map(|bits| bits.parse().expect("Window bits have to be a number"))
+        .unwrap_or(DEFAULT_WINDOW_BITS);
+    let finder = args
+        .get(6)
+        .map(|name| FinderKind::from_name(name).expect("Match finder chain/tree"))
+        .unwrap_or(FinderKind::HashChain);
+
+    let backend = Backend::from_name(coding).expect("Input coding as first argument (arithmetic/gamma/delta/omega/fib/binary)");
+    let config = LzssConfig::new(window_bits, level, finder);
+
+    let input_stream = BufReader::new(File::open(input_path).unwrap());
+    let mut output_stream = BufWriter::new(File::create(output_path).unwrap());
+
+    
+    let time_start = Instant::now();
+
+    let mut coder = LzssCoder::with_config(input_stream, &mut output_stream, backend, config);
+    coder.code_all();
+    let (input_len, output_len) = coder.get_stat();
+    
+    let time_end = Instant::now();
+    let dur = (time_end - time_start).as_secs_f64();
+    
+    
+    println!("Compression statistics:");
+    println!("Level: {}, window: 2^{}, match finder: {:?}", level, window_bits, finder);
+    println!("Input size: {:.3} MB", input_len as f64 / 1_000_000.0);
+    println!("Output size: {:.3} MB", output_len as f64 / 1_000_000.0);
+    println!("Compression ratio: {:.3}", input_len as f64 / output_len as f64);
+    println!("Average code length: {:.3}", output_len as f64 * 8.0 / input_len as f64);
+    println!("Speed: {:.2} MB/s", input_len as f64 / (dur * 1_000_000.0));
+    
+    let entropy = entropy::quick_entropy_of_file(input_path);
+    println!("Entropy of source: {}", entropy);
+
+    let entropy = entropy::quick_entropy_of_file(output_path);
+    println!("Entropy of code: {}", entropy);
+}
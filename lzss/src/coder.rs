@@ -0,0 +1,155 @@
+use std::io::{Read, Write};
+
+use lzw::universal_coder::{BinaryCoder, DeltaCoder, FibonacciCoder, GammaCoder, OmegaCoder};
+
+use crate::{
+    level::{FinderKind, Level, DEFAULT_LEVEL},
+    token::{ArithmeticTokenWriter, Token, TokenWriter, UniversalTokenWriter},
+    Backend, DEFAULT_WINDOW_BITS, HEADER_LEN, LZSS_MAGIC, MAX_WINDOW_BITS, MIN_MATCH, MIN_WINDOW_BITS,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LzssConfig {
+    pub window_bits: u8,
+    pub level: u8,
+    pub finder: FinderKind,
+}
+
+impl LzssConfig {
+    pub fn new(window_bits: u8, level: u8, finder: FinderKind) -> Self {
+        assert!(
+            (MIN_WINDOW_BITS..=MAX_WINDOW_BITS).contains(&window_bits),
+            "Window bits have to be between {} and {}",
+            MIN_WINDOW_BITS,
+            MAX_WINDOW_BITS
+        );
+
+        Self {
+            window_bits,
+            level,
+            finder,
+        }
+    }
+
+    pub fn window(&self) -> usize {
+        1 << self.window_bits
+    }
+}
+
+impl Default for LzssConfig {
+    fn default() -> Self {
+        Self::new(DEFAULT_WINDOW_BITS, DEFAULT_LEVEL, FinderKind::HashChain)
+    }
+}
+
+pub struct LzssCoder<I, O> {
+    input_stream: I,
+    output_stream: O,
+    backend: Backend,
+    config: LzssConfig,
+
+    input_len: usize,
+    output_len: usize,
+}
+
+impl<I: Read, O: Write> LzssCoder<I, O> {
+    pub fn new(input_stream: I, output_stream: O, backend: Backend) -> Self {
+        Self::with_config(input_stream, output_stream, backend, LzssConfig::default())
+    }
+
+    pub fn with_config(input_stream: I, output_stream: O, backend: Backend, config: LzssConfig) -> Self {
+        Self {
+            input_stream,
+            output_stream,
+            backend,
+            config,
+            input_len: 0,
+            output_len: 0,
+        }
+    }
+
+    pub fn code_all(&mut self) {
+        let mut data = vec![];
+        self.input_stream.read_to_end(&mut data).unwrap();
+        self.input_len = data.len();
+
+        let tokens = parse(&data, &self.config);
+
+        let mut header = [0; HEADER_LEN];
+        header[..4].copy_from_slice(LZSS_MAGIC);
+        header[4] = self.backend.id();
+        header[5] = self.config.window_bits;
+        header[6..].copy_from_slice(&(data.len() as u64).to_le_bytes());
+        self.output_stream.write_all(&header).unwrap();
+
+        let window = self.config.window();
+        let output_stream = &mut self.output_stream;
+        let written = match self.backend {
+            Backend::Arithmetic => write_tokens(ArithmeticTokenWriter::new(output_stream), &tokens),
+            Backend::Gamma => write_tokens(UniversalTokenWriter::new(output_stream, GammaCoder, window), &tokens),
+            Backend::Delta => write_tokens(UniversalTokenWriter::new(output_stream, DeltaCoder, window), &tokens),
+            Backend::Omega => write_tokens(UniversalTokenWriter::new(output_stream, OmegaCoder, window), &tokens),
+            Backend::Fib => write_tokens(
+                UniversalTokenWriter::new(output_stream, FibonacciCoder::new(), window),
+                &tokens,
+            ),
+            Backend::Binary => write_tokens(
+                UniversalTokenWriter::new(output_stream, BinaryCoder::new(), window),
+                &tokens,
+            ),
+        };
+        self.output_stream.flush().unwrap();
+
+        self.output_len = HEADER_LEN + written;
+    }
+
+    pub fn get_stat(&self) -> (usize, usize) {
+        (self.input_len, self.output_len)
+    }
+}
+
+fn write_tokens(mut writer: impl TokenWriter, tokens: &[Token]) -> usize {
+    for &token in tokens {
+        writer.write_token(token);
+    }
+    writer.finish();
+    writer.written_bytes()
+}
+
+pub fn parse(data: &[u8], config: &LzssConfig) -> Vec<Token> {
+    let level = Level::new(config.level);
+    let mut finder = level.finder(config.finder, config.window());
+
+    let mut tokens = vec![];
+    let mut lookahead = None;
+    let mut pos = 0;
+
+    while pos < data.len() {
+        let found = lookahead.take().unwrap_or_else(|| finder.find(data, pos));
+        let Some(current) = found.filter(|m| m.len >= MIN_MATCH) else {
+            tokens.push(Token::Literal(data[pos]));
+            pos += 1;
+            continue;
+        };
+
+        let mut inserted = pos + 1;
+        if level.lazy && current.len < level.nice_len && pos + 1 < data.len() {
+            let next = finder.find(data, pos + 1);
+            if next.is_some_and(|m| m.len > current.len) {
+                tokens.push(Token::Literal(data[pos]));
+                lookahead = Some(next);
+                pos += 1;
+                continue;
+            }
+            inserted = pos + 2;
+        }
+
+        for skipped in inserted..pos + current.len {
+            finder.skip(data, skipped);
+        }
+        tokens.push(Token::Match(current));
+        pos += current.len;
+    }
+
+    tokens
+}
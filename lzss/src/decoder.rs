@@ -0,0 +1,204 @@
+use std::io::{Read, Write};
+
+use lzw::universal_decoder::{BinaryDecoder, DeltaDecoder, FibonacciDecoder, GammaDecoder, OmegaDecoder};
+
+use crate::{
+    match_finder::Match,
+    token::{ArithmeticTokenReader, Token, TokenReader, UniversalTokenReader},
+    Backend, LzssError, HEADER_LEN, LZSS_MAGIC, MAX_WINDOW_BITS, MIN_WINDOW_BITS,
+};
+
+pub struct LzssDecoder<I, O> {
+    input_stream: I,
+    output_stream: O,
+
+    output_len: usize,
+}
+
+impl<I: Read, O: Write> LzssDecoder<I, O> {
+    pub fn new(input_stream: I, output_stream: O) -> Self {
+        Self {
+            input_stream,
+            output_stream,
+            output_len: 0,
+        }
+    }
+
+    pub fn decode_all(&mut self) -> Result<(), LzssError> {
+        let mut header = [0; HEADER_LEN];
+        self.input_stream.read_exact(&mut header)?;
+        if &header[..4] != LZSS_MAGIC {
+            return Err(LzssError::InvalidHeader);
+        }
+
+        let backend = Backend::from_id(header[4]).ok_or(LzssError::InvalidHeader)?;
+        let window_bits = header[5];
+        if !(MIN_WINDOW_BITS..=MAX_WINDOW_BITS).contains(&window_bits) {
+            return Err(LzssError::InvalidHeader);
+        }
+        let window = 1 << window_bits;
+        let len = u64::from_le_bytes(header[6..].try_into().unwrap()) as usize;
+
+        let input_stream = &mut self.input_stream;
+        let output = match backend {
+            Backend::Arithmetic => read_tokens(ArithmeticTokenReader::new(input_stream), len, window),
+            Backend::Gamma => read_tokens(UniversalTokenReader::new(input_stream, GammaDecoder, window), len, window),
+            Backend::Delta => read_tokens(UniversalTokenReader::new(input_stream, DeltaDecoder, window), len, window),
+            Backend::Omega => read_tokens(UniversalTokenReader::new(input_stream, OmegaDecoder, window), len, window),
+            Backend::Fib => read_tokens(
+                UniversalTokenReader::new(input_stream, FibonacciDecoder::new(), window),
+                len,
+                window,
+            ),
+            Backend::Binary => read_tokens(
+                UniversalTokenReader::new(input_stream, BinaryDecoder::new(), window),
+                len,
+                window,
+            ),
+        }?;
+
+        self.output_stream.write_all(&output)?;
+        self.output_stream.flush()?;
+        self.output_len = output.len();
+
+        Ok(())
+    }
+
+    pub fn get_stat(&self) -> usize {
+        self.output_len
+    }
+}
+
+// The length comes from the header, so only this much is reserved up front
+const MAX_PREALLOC: usize = 1 << 20;
+
+fn read_tokens(mut reader: impl TokenReader, len: usize, window: usize) -> Result<Vec<u8>, LzssError> {
+    let mut output = Vec::with_capacity(len.min(MAX_PREALLOC));
+
+    while output.len() < len {
+        match reader.read_token()? {
+            Token::Literal(byte) => output.push(byte),
+            Token::Match(Match { len: match_len, dist }) => {
+                if dist == 0 || dist > output.len() || dist >= window || match_len > len - output.len() {
+                    return Err(LzssError::InvalidMatch { len: match_len, dist });
+                }
+
+                let start = output.len() - dist;
+                for i in 0..match_len {
+                    output.push(output[start + i]);
+                }
+            }
+        }
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        coder::{LzssCoder, LzssConfig},
+        level::FinderKind,
+    };
+    use lzw::universal_coder::{BitOutputStream, GammaCoder, UniversalCoder};
+
+    use super::*;
+
+    fn round_trip(input: &[u8], backend: Backend, config: LzssConfig) -> usize {
+        let mut coded_buf = vec![];
+        let mut output_buf = vec![];
+
+        let mut coder = LzssCoder::with_config(input, &mut coded_buf, backend, config);
+        coder.code_all();
+
+        let mut decoder = LzssDecoder::new(coded_buf.as_slice(), &mut output_buf);
+        decoder.decode_all().unwrap();
+
+        assert_eq!(input, output_buf);
+        coded_buf.len()
+    }
+
+    #[test]
+    fn backends() {
+        let buf = include_bytes!("./../../testy/pan_tadeusz.txt");
+
+        for backend in [
+            Backend::Arithmetic,
+            Backend::Gamma,
+            Backend::Delta,
+            Backend::Omega,
+            Backend::Fib,
+            Backend::Binary,
+        ] {
+            let coded_len = round_trip(buf, backend, LzssConfig::default());
+            println!("{:?} coded len: {}", backend, coded_len);
+        }
+    }
+
+    #[test]
+    fn levels_and_finders() {
+        let buf = include_bytes!("./../../testy/test2.bin");
+
+        for finder in [FinderKind::HashChain, FinderKind::BinaryTree] {
+            for level in [1, 4, 9] {
+                for window_bits in [8, 16] {
+                    let config = LzssConfig::new(window_bits, level, finder);
+                    let coded_len = round_trip(buf, Backend::Gamma, config);
+                    println!("{:?} level {} window {} coded len: {}", finder, level, window_bits, coded_len);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn corrupted() {
+        let buf = include_bytes!("./../../testy/pan_tadeusz.txt");
+        let mut coded_buf = vec![];
+        LzssCoder::new(buf.as_slice(), &mut coded_buf, Backend::Gamma).code_all();
+
+        let mut output_buf = vec![];
+        let mut decoder = LzssDecoder::new(&coded_buf[..coded_buf.len() / 2], &mut output_buf);
+        assert_eq!(decoder.decode_all(), Err(LzssError::Truncated));
+
+        coded_buf[0] = b'X';
+        let mut decoder = LzssDecoder::new(coded_buf.as_slice(), &mut output_buf);
+        assert_eq!(decoder.decode_all(), Err(LzssError::InvalidHeader));
+    }
+
+    #[test]
+    fn huge_header_len() {
+        for backend in [Backend::Arithmetic, Backend::Gamma] {
+            let mut header = LZSS_MAGIC.to_vec();
+            header.push(backend.id());
+            header.push(MIN_WINDOW_BITS);
+            header.extend_from_slice(&(u64::MAX / 2).to_le_bytes());
+            assert_eq!(header.len(), HEADER_LEN);
+
+            let mut output_buf = vec![];
+            let mut decoder = LzssDecoder::new(header.as_slice(), &mut output_buf);
+            let result = decoder.decode_all();
+            println!("{:?}: {:?}", backend, result);
+            assert!(result.is_err());
+        }
+    }
+
+    #[test]
+    fn huge_match_len() {
+        let mut coded_buf = LZSS_MAGIC.to_vec();
+        coded_buf.push(Backend::Gamma.id());
+        coded_buf.push(MIN_WINDOW_BITS);
+        coded_buf.extend_from_slice(&1000u64.to_le_bytes());
+
+        let mut bit_stream = BitOutputStream::new(&mut coded_buf);
+        bit_stream.write_bit(false);
+        bit_stream.write_byte(b'a');
+        bit_stream.write_bit(true);
+        GammaCoder.code_number(&mut bit_stream, usize::MAX - 2);
+        GammaCoder.code_number(&mut bit_stream, 1);
+        bit_stream.pad_with_zeros();
+
+        let mut output_buf = vec![];
+        let mut decoder = LzssDecoder::new(coded_buf.as_slice(), &mut output_buf);
+        assert!(matches!(decoder.decode_all(), Err(LzssError::InvalidMatch { .. })));
+    }
+}
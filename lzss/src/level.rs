@@ -0,0 +1,62 @@
+use crate::{
+    match_finder::{BinaryTree, HashChain, MatchFinder},
+    MAX_MATCH,
+};
+
+pub const MIN_LEVEL: u8 = 1;
+
+pub const MAX_LEVEL: u8 = 9;
+
+pub const DEFAULT_LEVEL: u8 = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FinderKind {
+    HashChain,
+    BinaryTree,
+}
+
+impl FinderKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "chain" => Some(FinderKind::HashChain),
+            "tree" => Some(FinderKind::BinaryTree),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Level {
+    pub max_chain: usize,
+    pub nice_len: usize,
+    pub lazy: bool,
+}
+
+impl Level {
+    pub fn new(level: u8) -> Self {
+        let (max_chain, nice_len, lazy) = match level.clamp(MIN_LEVEL, MAX_LEVEL) {
+            1 => (4, 8, false),
+            2 => (8, 16, false),
+            3 => (16, 32, false),
+            4 => (16, 32, true),
+            5 => (32, 64, true),
+            6 => (128, 128, true),
+            7 => (256, 128, true),
+            8 => (1024, MAX_MATCH, true),
+            _ => (4096, MAX_MATCH, true),
+        };
+
+        Self {
+            max_chain,
+            nice_len,
+            lazy,
+        }
+    }
+
+    pub fn finder(&self, kind: FinderKind, window: usize) -> Box<dyn MatchFinder> {
+        match kind {
+            FinderKind::HashChain => Box::new(HashChain::new(window, self.max_chain, self.nice_len)),
+            FinderKind::BinaryTree => Box::new(BinaryTree::new(window, self.max_chain, self.nice_len)),
+        }
+    }
+}
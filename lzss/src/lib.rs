@@ -0,0 +1,106 @@
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    io,
+};
+
+use arithmetic::decoder::DecodeError;
+
+pub mod coder;
+pub mod decoder;
+pub mod level;
+pub mod match_finder;
+pub mod token;
+
+pub const LZSS_MAGIC: &[u8; 4] = b"LZS0";
+
+pub const HEADER_LEN: usize = 14;
+
+pub const MIN_MATCH: usize = 3;
+
+pub const MAX_MATCH: usize = 258;
+
+pub const MIN_WINDOW_BITS: u8 = 8;
+
+pub const MAX_WINDOW_BITS: u8 = 24;
+
+pub const DEFAULT_WINDOW_BITS: u8 = 15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Arithmetic,
+    Gamma,
+    Delta,
+    Omega,
+    Fib,
+    Binary,
+}
+
+impl Backend {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "arithmetic" => Some(Backend::Arithmetic),
+            "gamma" => Some(Backend::Gamma),
+            "delta" => Some(Backend::Delta),
+            "omega" => Some(Backend::Omega),
+            "fib" => Some(Backend::Fib),
+            "binary" => Some(Backend::Binary),
+            _ => None,
+        }
+    }
+
+    pub fn id(self) -> u8 {
+        match self {
+            Backend::Arithmetic => 0,
+            Backend::Gamma => 1,
+            Backend::Delta => 2,
+            Backend::Omega => 3,
+            Backend::Fib => 4,
+            Backend::Binary => 5,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Backend::Arithmetic),
+            1 => Some(Backend::Gamma),
+            2 => Some(Backend::Delta),
+            3 => Some(Backend::Omega),
+            4 => Some(Backend::Fib),
+            5 => Some(Backend::Binary),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LzssError {
+    InvalidHeader,
+    Truncated,
+    InvalidMatch { len: usize, dist: usize },
+    Arithmetic(DecodeError),
+    Io(io::ErrorKind),
+}
+
+impl Display for LzssError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LzssError::InvalidHeader => write!(f, "not an LZSS stream"),
+            LzssError::Truncated => write!(f, "stream ends before all data was decoded"),
+            LzssError::InvalidMatch { len, dist } => write!(f, "invalid match of length {} at distance {}", len, dist),
+            LzssError::Arithmetic(err) => write!(f, "arithmetic decoding failed: {}", err),
+            LzssError::Io(kind) => write!(f, "io error: {}", kind),
+        }
+    }
+}
+
+impl Error for LzssError {}
+
+impl From<io::Error> for LzssError {
+    fn from(err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::UnexpectedEof => LzssError::Truncated,
+            kind => LzssError::Io(kind),
+        }
+    }
+}
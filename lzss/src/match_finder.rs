@@ -0,0 +1,217 @@
+use crate::{MAX_MATCH, MIN_MATCH};
+
+const HASH_BITS: u32 = 16;
+
+const HASH_SIZE: usize = 1 << HASH_BITS;
+
+const NIL: usize = usize::MAX;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match {
+    pub len: usize,
+    pub dist: usize,
+}
+
+pub trait MatchFinder {
+    // Every position has to be passed to exactly one of find/skip, in order.
+    fn find(&mut self, data: &[u8], pos: usize) -> Option<Match>;
+    fn skip(&mut self, data: &[u8], pos: usize);
+}
+
+fn hash3(data: &[u8], pos: usize) -> usize {
+    let value = (data[pos] as u32) | (data[pos + 1] as u32) << 8 | (data[pos + 2] as u32) << 16;
+    (value.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize
+}
+
+fn match_len(data: &[u8], a: usize, b: usize, max_len: usize) -> usize {
+    data[a..a + max_len]
+        .iter()
+        .zip(&data[b..b + max_len])
+        .take_while(|(x, y)| x == y)
+        .count()
+}
+
+pub struct HashChain {
+    head: Vec<usize>,
+    prev: Vec<usize>,
+    window: usize,
+    max_chain: usize,
+    nice_len: usize,
+}
+
+impl HashChain {
+    pub fn new(window: usize, max_chain: usize, nice_len: usize) -> Self {
+        Self {
+            head: vec![NIL; HASH_SIZE],
+            prev: vec![NIL; window],
+            window,
+            max_chain,
+            nice_len,
+        }
+    }
+
+    fn insert(&mut self, data: &[u8], pos: usize) -> usize {
+        let hash = hash3(data, pos);
+        let first = self.head[hash];
+        self.prev[pos % self.window] = first;
+        self.head[hash] = pos;
+        first
+    }
+}
+
+impl MatchFinder for HashChain {
+    fn find(&mut self, data: &[u8], pos: usize) -> Option<Match> {
+        if pos + MIN_MATCH > data.len() {
+            return None;
+        }
+
+        let max_len = MAX_MATCH.min(data.len() - pos);
+        let mut best: Option<Match> = None;
+        let mut candidate = self.insert(data, pos);
+
+        for _ in 0..self.max_chain {
+            if candidate == NIL || candidate >= pos || pos - candidate >= self.window {
+                break;
+            }
+
+            let len = match_len(data, candidate, pos, max_len);
+            if len >= MIN_MATCH && best.is_none_or(|m| len > m.len) {
+                best = Some(Match { len, dist: pos - candidate });
+                if len >= self.nice_len.min(max_len) {
+                    break;
+                }
+            }
+
+            candidate = self.prev[candidate % self.window];
+        }
+
+        best
+    }
+
+    fn skip(&mut self, data: &[u8], pos: usize) {
+        if pos + MIN_MATCH <= data.len() {
+            self.insert(data, pos);
+        }
+    }
+}
+
+// Each hash bucket holds a binary search tree of the window positions ordered
+// by the strings that start there, rebuilt around every new position as in LZMA.
+pub struct BinaryTree {
+    head: Vec<usize>,
+    children: Vec<usize>,
+    window: usize,
+    max_depth: usize,
+    nice_len: usize,
+}
+
+impl BinaryTree {
+    pub fn new(window: usize, max_depth: usize, nice_len: usize) -> Self {
+        Self {
+            head: vec![NIL; HASH_SIZE],
+            children: vec![NIL; 2 * window],
+            window,
+            max_depth,
+            nice_len,
+        }
+    }
+
+    fn find_and_insert(&mut self, data: &[u8], pos: usize, search: bool) -> Option<Match> {
+        if pos + MIN_MATCH > data.len() {
+            return None;
+        }
+
+        let max_len = MAX_MATCH.min(data.len() - pos);
+        let cut_len = self.nice_len.min(max_len);
+        let hash = hash3(data, pos);
+        let mut candidate = self.head[hash];
+        self.head[hash] = pos;
+
+        let node = 2 * (pos % self.window);
+        let mut smaller_slot = node;
+        let mut larger_slot = node + 1;
+        let mut smaller_len = 0;
+        let mut larger_len = 0;
+        let mut best: Option<Match> = None;
+
+        for _ in 0..self.max_depth {
+            if candidate == NIL || candidate >= pos || pos - candidate >= self.window {
+                break;
+            }
+
+            let pair = 2 * (candidate % self.window);
+            let known = smaller_len.min(larger_len);
+            let len = known + match_len(data, candidate + known, pos + known, max_len - known);
+
+            if search && len >= MIN_MATCH && best.is_none_or(|m| len > m.len) {
+                best = Some(Match { len, dist: pos - candidate });
+            }
+
+            if len >= cut_len {
+                self.children[smaller_slot] = self.children[pair];
+                self.children[larger_slot] = self.children[pair + 1];
+                return best;
+            }
+
+            if data[candidate + len] < data[pos + len] {
+                self.children[smaller_slot] = candidate;
+                smaller_slot = pair + 1;
+                smaller_len = len;
+                candidate = self.children[smaller_slot];
+            } else {
+                self.children[larger_slot] = candidate;
+                larger_slot = pair;
+                larger_len = len;
+                candidate = self.children[larger_slot];
+            }
+        }
+
+        self.children[smaller_slot] = NIL;
+        self.children[larger_slot] = NIL;
+        best
+    }
+}
+
+impl MatchFinder for BinaryTree {
+    fn find(&mut self, data: &[u8], pos: usize) -> Option<Match> {
+        self.find_and_insert(data, pos, true)
+    }
+
+    fn skip(&mut self, data: &[u8], pos: usize) {
+        self.find_and_insert(data, pos, false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brute_force(data: &[u8], pos: usize, window: usize) -> usize {
+        let max_len = MAX_MATCH.min(data.len() - pos);
+        (pos.saturating_sub(window - 1)..pos)
+            .map(|candidate| match_len(data, candidate, pos, max_len))
+            .max()
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn finders_find_longest() {
+        let data = include_bytes!("./../../testy/pan_tadeusz.txt");
+        let data = &data[..8_000];
+        let window = 1 << 10;
+
+        let mut hash_chain = HashChain::new(window, usize::MAX, MAX_MATCH);
+        let mut binary_tree = BinaryTree::new(window, usize::MAX, MAX_MATCH);
+
+        for pos in 0..data.len() {
+            let expected = brute_force(data, pos, window);
+            let expected = if expected >= MIN_MATCH && pos + MIN_MATCH <= data.len() { expected } else { 0 };
+
+            let chain_match = hash_chain.find(data, pos).map_or(0, |m| m.len);
+            let tree_match = binary_tree.find(data, pos).map_or(0, |m| m.len);
+
+            assert_eq!(chain_match, expected, "hash chain at {}", pos);
+            assert_eq!(tree_match, expected, "binary tree at {}", pos);
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use std::io::{Read, Write};
+
+use arithmetic::{
+    coder::SymbolCoder,
+    decoder::{DecodeError, SymbolDecoder},
+    model::Model,
+};
+use lzw::{
+    universal_coder::{BitOutputStream, UniversalCoder},
+    universal_decoder::{BitInputStream, UniversalDecoder},
+};
+
+use crate::{match_finder::Match, LzssError, MAX_MATCH, MIN_MATCH};
+
+const LENGTH_CODES: usize = MAX_MATCH - MIN_MATCH + 1;
+
+const LITERAL_LENGTH_ALPHABET: usize = 256 + LENGTH_CODES;
+
+const DISTANCE_SLOTS: usize = usize::BITS as usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Token {
+    Literal(u8),
+    Match(Match),
+}
+
+pub trait TokenWriter {
+    fn write_token(&mut self, token: Token);
+    fn finish(&mut self);
+    fn written_bytes(&self) -> usize;
+}
+
+pub trait TokenReader {
+    fn read_token(&mut self) -> Result<Token, LzssError>;
+}
+
+pub struct UniversalTokenWriter<O, C> {
+    output_stream: BitOutputStream<O>,
+    universal_coder: C,
+    window: usize,
+}
+
+impl<O: Write, C: UniversalCoder> UniversalTokenWriter<O, C> {
+    pub fn new(output_stream: O, universal_coder: C, window: usize) -> Self {
+        Self {
+            output_stream: BitOutputStream::new(output_stream),
+            universal_coder,
+            window,
+        }
+    }
+}
+
+impl<O: Write, C: UniversalCoder> TokenWriter for UniversalTokenWriter<O, C> {
+    fn write_token(&mut self, token: Token) {
+        match token {
+            Token::Literal(byte) => {
+                self.output_stream.write_bit(false);
+                for i in (0..8).rev() {
+                    self.output_stream.write_bit((byte >> i) & 1 == 1);
+                }
+            }
+            Token::Match(Match { len, dist }) => {
+                // Fixed width codes are sized for dict_size + 1 numbers
+                self.output_stream.write_bit(true);
+                self.universal_coder.set_dict_size(LENGTH_CODES - 1);
+                self.universal_coder.code_number(&mut self.output_stream, len - MIN_MATCH + 1);
+                self.universal_coder.set_dict_size(self.window - 1);
+                self.universal_coder.code_number(&mut self.output_stream, dist);
+            }
+        }
+    }
+
+    fn finish(&mut self) {
+        self.universal_coder.pad(&mut self.output_stream);
+    }
+
+    fn written_bytes(&self) -> usize {
+        self.output_stream.written_bytes
+    }
+}
+
+pub struct UniversalTokenReader<I, D> {
+    input_stream: BitInputStream<I>,
+    universal_decoder: D,
+    window: usize,
+}
+
+impl<I: Read, D: UniversalDecoder> UniversalTokenReader<I, D> {
+    pub fn new(input_stream: I, universal_decoder: D, window: usize) -> Self {
+        Self {
+            input_stream: BitInputStream::new(input_stream),
+            universal_decoder,
+            window,
+        }
+    }
+}
+
+impl<I: Read, D: UniversalDecoder> TokenReader for UniversalTokenReader<I, D> {
+    fn read_token(&mut self) -> Result<Token, LzssError> {
+        let is_match = self.input_stream.read_bit().ok_or(LzssError::Truncated)?;
+
+        if !is_match {
+            let mut byte = 0;
+            for _ in 0..8 {
+                let bit = self.input_stream.read_bit().ok_or(LzssError::Truncated)?;
+                byte = byte << 1 | bit as u8;
+            }
+            return Ok(Token::Literal(byte));
+        }
+
+        self.universal_decoder.set_dict_size(LENGTH_CODES - 1);
+        let len = self
+            .universal_decoder
+            .decode_number(&mut self.input_stream)
+            .ok_or(LzssError::Truncated)?;
+        if len == 0 || len > LENGTH_CODES {
+            return Err(LzssError::InvalidMatch { len, dist: 0 });
+        }
+        self.universal_decoder.set_dict_size(self.window - 1);
+        let dist = self
+            .universal_decoder
+            .decode_number(&mut self.input_stream)
+            .ok_or(LzssError::Truncated)?;
+
+        Ok(Token::Match(Match {
+            len: len + MIN_MATCH - 1,
+            dist,
+        }))
+    }
+}
+
+// Literals and lengths share one adaptive model like in DEFLATE. Distances are
+// coded as their bit length followed by the remaining bits, each with its own model.
+pub struct ArithmeticTokenWriter<O> {
+    symbol_coder: SymbolCoder<O>,
+    distance_slots: Model,
+    distance_bits: Model,
+}
+
+impl<O: Write> ArithmeticTokenWriter<O> {
+    pub fn new(output_stream: O) -> Self {
+        Self {
+            symbol_coder: SymbolCoder::new(output_stream, LITERAL_LENGTH_ALPHABET),
+            distance_slots: Model::with_alphabet(DISTANCE_SLOTS),
+            distance_bits: Model::with_alphabet(2),
+        }
+    }
+}
+
+impl<O: Write> TokenWriter for ArithmeticTokenWriter<O> {
+    fn write_token(&mut self, token: Token) {
+        match token {
+            Token::Literal(byte) => self.symbol_coder.code_symbol(byte as u32),
+            Token::Match(Match { len, dist }) => {
+                self.symbol_coder.code_symbol((256 + len - MIN_MATCH) as u32);
+
+                let slot = dist.ilog2();
+                self.symbol_coder.code_symbol_with(&mut self.distance_slots, slot);
+                for i in (0..slot).rev() {
+                    self.symbol_coder.code_symbol_with(&mut self.distance_bits, ((dist >> i) & 1) as u32);
+                }
+            }
+        }
+    }
+
+    fn finish(&mut self) {
+        self.symbol_coder.finish();
+    }
+
+    fn written_bytes(&self) -> usize {
+        self.symbol_coder.written_bytes()
+    }
+}
+
+pub struct ArithmeticTokenReader<I> {
+    symbol_decoder: SymbolDecoder<I>,
+    distance_slots: Model,
+    distance_bits: Model,
+}
+
+impl<I: Read> ArithmeticTokenReader<I> {
+    pub fn new(input_stream: I) -> Self {
+        Self {
+            symbol_decoder: SymbolDecoder::new(input_stream, LITERAL_LENGTH_ALPHABET),
+            distance_slots: Model::with_alphabet(DISTANCE_SLOTS),
+            distance_bits: Model::with_alphabet(2),
+        }
+    }
+}
+
+fn decoded(symbol: Result<Option<u32>, DecodeError>) -> Result<u32, LzssError> {
+    symbol.map_err(LzssError::Arithmetic)?.ok_or(LzssError::Truncated)
+}
+
+impl<I: Read> TokenReader for ArithmeticTokenReader<I> {
+    fn read_token(&mut self) -> Result<Token, LzssError> {
+        let symbol = decoded(self.symbol_decoder.decode_symbol())? as usize;
+        if symbol < 256 {
+            return Ok(Token::Literal(symbol as u8));
+        }
+
+        let slot = decoded(self.symbol_decoder.decode_symbol_with(&mut self.distance_slots))?;
+        let mut dist = 1;
+        for _ in 0..slot {
+            dist = dist << 1 | decoded(self.symbol_decoder.decode_symbol_with(&mut self.distance_bits))? as usize;
+        }
+
+        Ok(Token::Match(Match {
+            len: symbol - 256 + MIN_MATCH,
+            dist,
+        }))
+    }
+}
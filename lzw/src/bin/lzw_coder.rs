@@ -1,58 +1,157 @@
-use std::{env::args, io::{BufReader, BufWriter}, fs::File, time::Instant};
+use std::{env::args, io::{self, BufReader, BufWriter, Read, Write}, fs::File, process::exit, time::Instant};
 
-use lzw::{coder::LZWCoder, universal_coder::{GammaCoder, DeltaCoder, OmegaCoder, FibonacciCoder}};
+use lzw::{chunked::{default_threads, ChunkedCoder}, coder::LZWCoder, diagnostics::Diagnostics, dict_policy::{DictConfig, DictPolicy, DictVariant}, header::Coding, preset::PresetDict, words::TokenCounter, MAX_DICT_SIZE, universal_coder::{UniversalCoder, GammaCoder, DeltaCoder, OmegaCoder, FibonacciCoder, BinaryCoder, GolombCoder, RiceCoder, ExpGolombCoder, AdaptiveRiceCoder, ArithmeticCoder}};
+
+fn code<I: Read, O: Write, C: UniversalCoder>(
+    input_stream: I,
+    output_stream: O,
+    universal_coder: C,
+    input_len: Option<u64>,
+    config: DictConfig,
+    preset: Option<PresetDict>,
+    report: bool,
+) -> io::Result<(usize, usize, Option<Diagnostics>)> {
+    let mut coder = LZWCoder::with_config(input_stream, output_stream, universal_coder, config);
+    if let Some(len) = input_len {
+        coder = coder.with_input_len(len);
+    }
+    if let Some(preset) = preset {
+        coder = coder.with_preset(preset);
+    }
+    if report {
+        coder = coder.with_diagnostics();
+    }
+    coder.code_to_end()?;
+    let (input_len, output_len) = coder.get_stat();
+    Ok((input_len, output_len, coder.diagnostics().cloned()))
+}
+
+// One more pass over the input, streamed so that large inputs are not loaded whole
+fn print_source_stats(input_path: &str, words: bool) {
+    let mut tokens = words.then(TokenCounter::new);
+    let bytes = BufReader::new(File::open(input_path).unwrap()).bytes().map(Result::unwrap);
+    let table = entropy::conditional_count_iter(bytes.inspect(|&byte| {
+        if let Some(tokens) = &mut tokens {
+            tokens.push(byte);
+        }
+    }));
+
+    println!("Entropy of source: {}", entropy::entropy(&table.marginal()));
+    if let Some((count, distinct)) = tokens.map(TokenCounter::finish) {
+        println!("Tokens: {} ({} distinct)", count, distinct);
+    }
+    println!("Order-1 entropy of source: {}", entropy::conditional_entropy(&table));
+}
 
 pub fn main() {
     let args = args().collect::<Vec<_>>();
 
-    let coding = args.get(1).expect("Input coding gamma/delta/omega/fib");
+    let coding = args.get(1).expect("Input coding gamma/delta/omega/fib/binary/golomb:m/rice:k/expgolomb:k/adaptive-rice/arithmetic");
     let input_path = args.get(2).expect("Please input the input file path");
     let output_path = args.get(3).expect("Please input the output file path");
+    let policy = args
+        .get(4)
+        .map(|name| DictPolicy::from_name(name).expect("Dictionary policy freeze/reset/adaptive/lru"))
+        .unwrap_or(DictPolicy::Freeze);
+    let max_size = args
+        .get(5)
+        .map(|size| size.parse().expect("Dictionary size has to be a number"))
+        .unwrap_or(MAX_DICT_SIZE);
+    let variant = args
+        .get(6)
+        .map(|name| DictVariant::from_name(name).expect("Dictionary variant lzw/lzmw/lzap/words"))
+        .unwrap_or(DictVariant::Lzw);
+    // "-" skips the preset when only a report is wanted
+    let preset_path = args.get(7).filter(|path| path.as_str() != "-");
+    let preset = preset_path.map(|path| PresetDict::read(path).expect("Cannot read the preset dictionary"));
+    let report_path = args.get(8).filter(|path| path.as_str() != "-");
+    // Chunk size with an optional thread count, e.g. 4194304:8
+    let chunking = args.get(9).map(|arg| {
+        let (size, threads) = arg.split_once(':').unwrap_or((arg, ""));
+        let size = size.parse::<usize>().expect("Chunk size has to be a number");
+        let threads = if threads.is_empty() { default_threads() } else { threads.parse().expect("Thread count has to be a number") };
+        (size, threads)
+    });
+    assert!(report_path.is_none() || chunking.is_none(), "Reports are not collected for chunked coding");
+    let config = DictConfig::new(policy, max_size).with_variant(variant);
+    let coding = Coding::from_name(coding).expect("Input coding as first argument (gamma/delta/omega/fib/binary/golomb:m/rice:k/expgolomb:k/adaptive-rice/arithmetic)");
 
-    let input_stream = BufReader::new(File::open(input_path).unwrap());
+    let input_file = File::open(input_path).unwrap();
+    // Known up front for regular files, so the header keeps the length without
+    // buffering the input. Pipes and devices end with an end code instead.
+    let metadata = input_file.metadata().unwrap();
+    let file_len = metadata.is_file().then_some(metadata.len());
+    let input_stream = BufReader::new(input_file);
     let mut output_stream = BufWriter::new(File::create(output_path).unwrap());
 
     
     let time_start = Instant::now();
 
-    let (input_len, output_len) = match coding.as_str() {
-        "gamma" => {
-            let mut coder = LZWCoder::new(input_stream, &mut output_stream, GammaCoder);
-            coder.code_to_end();
-            coder.get_stat()
-        },
-        "delta" => {
-            let mut coder = LZWCoder::new(input_stream, &mut output_stream, DeltaCoder);
-            coder.code_to_end();
-            coder.get_stat()
-        },
-        "omega" => {
-            let mut coder = LZWCoder::new(input_stream, &mut output_stream, OmegaCoder);
-            coder.code_to_end();
-            coder.get_stat()
-        },
-        "fib" => {
-            let mut coder = LZWCoder::new(input_stream, &mut output_stream, FibonacciCoder::new());
-            coder.code_to_end();
-            coder.get_stat()
-        },
-        _ => { panic!("Input coding as first argument (gamma/delta/omega/fib)") }
-    };
+    let result = if let Some((chunk_size, threads)) = chunking {
+        let mut coder = ChunkedCoder::new(input_stream, &mut output_stream, coding)
+            .with_config(config)
+            .with_chunk_size(chunk_size)
+            .with_threads(threads);
+        if let Some(preset) = preset {
+            coder = coder.with_preset(preset);
+        }
+        coder.code_to_end();
+        println!("Chunks: {} on {} threads", coder.table().chunks.len(), threads);
+        let (input_len, output_len) = coder.get_stat();
+        Ok((input_len, output_len, None))
+    } else { match coding {
+        Coding::Gamma => code(input_stream, &mut output_stream, GammaCoder, file_len, config, preset, report_path.is_some()),
+        Coding::Delta => code(input_stream, &mut output_stream, DeltaCoder, file_len, config, preset, report_path.is_some()),
+        Coding::Omega => code(input_stream, &mut output_stream, OmegaCoder, file_len, config, preset, report_path.is_some()),
+        Coding::Fib => code(input_stream, &mut output_stream, FibonacciCoder::new(), file_len, config, preset, report_path.is_some()),
+        Coding::Binary => code(input_stream, &mut output_stream, BinaryCoder::new(), file_len, config, preset, report_path.is_some()),
+        Coding::Golomb(m) => code(input_stream, &mut output_stream, GolombCoder::new(m as usize), file_len, config, preset, report_path.is_some()),
+        Coding::Rice(k) => code(input_stream, &mut output_stream, RiceCoder::new(k), file_len, config, preset, report_path.is_some()),
+        Coding::ExpGolomb(k) => code(input_stream, &mut output_stream, ExpGolombCoder::new(k), file_len, config, preset, report_path.is_some()),
+        Coding::AdaptiveRice => code(input_stream, &mut output_stream, AdaptiveRiceCoder::new(), file_len, config, preset, report_path.is_some()),
+        Coding::Arithmetic => code(input_stream, &mut output_stream, ArithmeticCoder::new(), file_len, config, preset, report_path.is_some()),
+    } };
+    let (input_len, output_len, diagnostics) = result.unwrap_or_else(|err| {
+        eprintln!("Compression failed: {}", err);
+        drop(output_stream);
+        let _ = std::fs::remove_file(output_path);
+        exit(1);
+    });
     
     let time_end = Instant::now();
     let dur = (time_end - time_start).as_secs_f64();
     
     
     println!("Compression statistics:");
+    println!("Coding: {:?}", coding);
+    println!("Dictionary policy: {:?} (max size {}, {:?})", config.policy, config.max_size, config.variant);
+    if let Some(path) = preset_path {
+        println!("Preset dictionary: {}", path);
+    }
     println!("Input size: {:.3} MB", input_len as f64 / 1_000_000.0);
     println!("Output size: {:.3} MB", output_len as f64 / 1_000_000.0);
     println!("Compression ratio: {:.3}", input_len as f64 / output_len as f64);
-    println!("Average code length: {:.3}", output_len as f64 * 8.0 / input_len as f64);
+    println!("Bits per original byte: {:.3}", output_len as f64 * 8.0 / input_len as f64);
     println!("Speed: {:.2} MB/s", input_len as f64 / (dur * 1_000_000.0));
     
-    let entropy = entropy::quick_entropy_of_file(input_path);
-    println!("Entropy of source: {}", entropy);
+    // Pipes cannot be read a second time
+    if file_len.is_some() {
+        print_source_stats(input_path, config.variant == DictVariant::Words);
+    }
 
     let entropy = entropy::quick_entropy_of_file(output_path);
     println!("Entropy of code: {}", entropy);
+
+    // Written as JSON or CSV depending on the extension
+    if let (Some(path), Some(diagnostics)) = (report_path, diagnostics) {
+        println!("Phrases: {} (mean length {:.3})", diagnostics.phrase_count(), diagnostics.mean_phrase_len());
+        match diagnostics.dict_full_at {
+            Some(pos) => println!("Dictionary full after {} bytes", pos),
+            None => println!("Dictionary never filled up"),
+        }
+
+        let report = if path.ends_with(".json") { diagnostics.to_json() } else { diagnostics.to_csv() };
+        std::fs::write(path, report).unwrap();
+        println!("Report written to {}", path);
+    }
 }
\ No newline at end of file
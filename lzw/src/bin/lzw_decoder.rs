@@ -1,42 +1,46 @@
-use std::{env::args, io::{BufReader, BufWriter}, fs::File, time::Instant};
+use std::{env::args, io::{BufReader, BufWriter, Read}, fs::File, process::exit, time::Instant};
 
-use lzw::{decoder::LZWDecoder, universal_decoder::{FibonacciDecoder, OmegaDecoder, DeltaDecoder, GammaDecoder}};
+use lzw::{chunked::{decode_chunked, default_threads, is_chunked}, decoder::decode_auto_with_preset, preset::PresetDict};
 
 pub fn main() {
     let args = args().collect::<Vec<_>>();
 
-    let coding = args.get(1).expect("Input coding gamma/delta/omega/fib");
-    let input_path = args.get(2).expect("Please input the input file path");
-    let output_path = args.get(3).expect("Please input the output file path");
+    let input_path = args.get(1).expect("Please input the input file path");
+    let output_path = args.get(2).expect("Please input the output file path");
+    let preset = args.get(3).map(|path| PresetDict::read(path).expect("Cannot read the preset dictionary"));
 
+    let mut magic = [0; 4];
+    let chunked = File::open(input_path).unwrap().read_exact(&mut magic).is_ok() && is_chunked(&magic);
     let input_stream = BufReader::new(File::open(input_path).unwrap());
     let mut output_stream = BufWriter::new(File::create(output_path).unwrap());
 
     
     let time_start = Instant::now();
 
-    let output_len = match coding.as_str() {
-        "gamma" => {
-            let mut coder = LZWDecoder::new(input_stream, &mut output_stream, GammaDecoder);
-            coder.decode_to_end();
-            coder.get_stat()
-        },
-        "delta" => {
-            let mut coder = LZWDecoder::new(input_stream, &mut output_stream, DeltaDecoder);
-            coder.decode_to_end();
-            coder.get_stat()
-        },
-        "omega" => {
-            let mut coder = LZWDecoder::new(input_stream, &mut output_stream, OmegaDecoder);
-            coder.decode_to_end();
-            coder.get_stat()
-        },
-        "fib" => {
-            let mut coder = LZWDecoder::new(input_stream, &mut output_stream, FibonacciDecoder::new());
-            coder.decode_to_end();
-            coder.get_stat()
-        },
-        _ => { panic!("Input coding as first argument (gamma/delta/omega/fib)") }
+    if chunked {
+        let threads = default_threads();
+        let (table, output_len) = match decode_chunked(input_stream, &mut output_stream, preset.as_ref(), threads) {
+            Ok(result) => result,
+            Err(err) => {
+                eprintln!("Decoding failed: {}", err);
+                exit(1);
+            }
+        };
+        let dur = time_start.elapsed().as_secs_f64();
+
+        println!("Compression statistics:");
+        println!("Chunks: {} of up to {} bytes on {} threads", table.chunks.len(), table.chunk_size, threads);
+        println!("Output size: {:.3} MB", output_len as f64 / 1_000_000.0);
+        println!("Speed: {:.2} MB/s", output_len as f64 / (dur * 1_000_000.0));
+        return;
+    }
+
+    let (header, output_len) = match decode_auto_with_preset(input_stream, &mut output_stream, preset.as_ref()) {
+        Ok(result) => result,
+        Err(err) => {
+            eprintln!("Decoding failed: {}", err);
+            exit(1);
+        }
     };
     
     let time_end = Instant::now();
@@ -44,6 +48,11 @@ pub fn main() {
     
     
     println!("Compression statistics:");
+    println!("Coding: {:?}", header.coding);
+    println!("Dictionary policy: {:?} (max size {}, {:?})", header.config.policy, header.config.max_size, header.config.variant);
+    if let Some(hash) = header.preset {
+        println!("Preset dictionary: {:08x}", hash);
+    }
     println!("Output size: {:.3} MB", output_len as f64 / 1_000_000.0);
     println!("Speed: {:.2} MB/s", output_len as f64 / (dur * 1_000_000.0));
-}
\ No newline at end of file
+}
@@ -0,0 +1,34 @@
+use std::{env::args, io::{BufReader, BufWriter}, fs::File, time::Instant};
+
+use lzw::unix_compress::{ZCoder, MAX_BITS};
+
+pub fn main() {
+    let args = args().collect::<Vec<_>>();
+
+    let input_path = args.get(1).expect("Please input the input file path");
+    let output_path = args.get(2).expect("Please input the output file path");
+    let max_bits = args
+        .get(3)
+        .map(|bits| bits.parse().expect("Max bits has to be a number"))
+        .unwrap_or(MAX_BITS);
+
+    let input_stream = BufReader::new(File::open(input_path).unwrap());
+    let mut output_stream = BufWriter::new(File::create(output_path).unwrap());
+
+    let time_start = Instant::now();
+
+    let mut coder = ZCoder::with_max_bits(input_stream, &mut output_stream, max_bits);
+    coder.code_to_end();
+    let (input_len, output_len) = coder.get_stat();
+
+    let time_end = Instant::now();
+    let dur = (time_end - time_start).as_secs_f64();
+
+    println!("Compression statistics:");
+    println!("Max bits: {}", max_bits);
+    println!("Input size: {:.3} MB", input_len as f64 / 1_000_000.0);
+    println!("Output size: {:.3} MB", output_len as f64 / 1_000_000.0);
+    println!("Compression ratio: {:.3}", input_len as f64 / output_len as f64);
+    println!("Average code length: {:.3}", output_len as f64 * 8.0 / input_len as f64);
+    println!("Speed: {:.2} MB/s", input_len as f64 / (dur * 1_000_000.0));
+}
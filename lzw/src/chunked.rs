@@ -0,0 +1,427 @@
+use std::{
+    io::{Read, Seek, SeekFrom, Write},
+    num::NonZeroUsize,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use crate::{
+    coder::LZWCoder,
+    decoder::{decode_auto_with_preset, LzwError},
+    dict_policy::DictConfig,
+    header::{Coding, StreamHeader},
+    preset::PresetDict,
+    universal_coder::{
+        AdaptiveRiceCoder, ArithmeticCoder, BinaryCoder, DeltaCoder, ExpGolombCoder, FibonacciCoder, GammaCoder, GolombCoder,
+        OmegaCoder, RiceCoder, UniversalCoder,
+    },
+};
+
+pub const CHUNKED_MAGIC: &[u8; 4] = b"LZWC";
+
+pub const DEFAULT_CHUNK_SIZE: usize = 1 << 22;
+
+// Chunks are read, coded and written in batches of this many per thread,
+// so memory stays bounded however large the input is
+const CHUNKS_PER_THREAD: usize = 2;
+
+const CHUNK_ENTRY_LEN: u64 = 16;
+const FOOTER_TAIL_LEN: u64 = 8 + 8 + 4;
+
+pub fn default_threads() -> usize {
+    thread::available_parallelism().map_or(1, NonZeroUsize::get)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkEntry {
+    pub compressed_len: u64,
+    pub original_len: u64,
+}
+
+// Lengths of the chunks, stored after them together with the chunk size
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkTable {
+    pub chunk_size: u64,
+    pub chunks: Vec<ChunkEntry>,
+}
+
+impl ChunkTable {
+    pub fn new(chunk_size: u64) -> Self {
+        Self { chunk_size, chunks: vec![] }
+    }
+
+    pub fn original_len(&self) -> u64 {
+        self.chunks.iter().map(|chunk| chunk.original_len).sum()
+    }
+
+    pub fn footer_len(&self) -> u64 {
+        self.chunks.len() as u64 * CHUNK_ENTRY_LEN + FOOTER_TAIL_LEN
+    }
+
+    pub fn write_to<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+        for chunk in &self.chunks {
+            writer.write_all(&chunk.compressed_len.to_le_bytes())?;
+            writer.write_all(&chunk.original_len.to_le_bytes())?;
+        }
+        writer.write_all(&self.chunk_size.to_le_bytes())?;
+        writer.write_all(&(self.chunks.len() as u64).to_le_bytes())?;
+        writer.write_all(CHUNKED_MAGIC)
+    }
+
+    pub fn read_from<R: Read + Seek>(mut reader: R) -> Result<Self, LzwError> {
+        let file_len = reader.seek(SeekFrom::End(0))?;
+        if file_len < CHUNKED_MAGIC.len() as u64 + FOOTER_TAIL_LEN {
+            return Err(LzwError::InvalidHeader);
+        }
+
+        let mut magic = [0; 4];
+        reader.seek(SeekFrom::Start(0))?;
+        reader.read_exact(&mut magic)?;
+        let mut tail = [0; FOOTER_TAIL_LEN as usize];
+        reader.seek(SeekFrom::Start(file_len - FOOTER_TAIL_LEN))?;
+        reader.read_exact(&mut tail)?;
+
+        if &magic != CHUNKED_MAGIC || &tail[16..] != CHUNKED_MAGIC {
+            return Err(LzwError::InvalidHeader);
+        }
+        let chunk_size = u64::from_le_bytes(tail[..8].try_into().unwrap());
+        let chunk_count = u64::from_le_bytes(tail[8..16].try_into().unwrap());
+
+        let entries_len = chunk_count
+            .checked_mul(CHUNK_ENTRY_LEN)
+            .filter(|&len| len <= file_len - FOOTER_TAIL_LEN)
+            .ok_or(LzwError::InvalidHeader)?;
+
+        reader.seek(SeekFrom::Start(file_len - FOOTER_TAIL_LEN - entries_len))?;
+        let mut entries = vec![0; entries_len as usize];
+        reader.read_exact(&mut entries)?;
+
+        let chunks = entries
+            .chunks_exact(CHUNK_ENTRY_LEN as usize)
+            .map(|entry| ChunkEntry {
+                compressed_len: u64::from_le_bytes(entry[..8].try_into().unwrap()),
+                original_len: u64::from_le_bytes(entry[8..].try_into().unwrap()),
+            })
+            .collect::<Vec<_>>();
+
+        let valid_chunks = chunks
+            .iter()
+            .all(|chunk| chunk.original_len > 0 && chunk.original_len <= chunk_size);
+        let compressed_len = chunks
+            .iter()
+            .try_fold(CHUNKED_MAGIC.len() as u64, |sum, chunk| sum.checked_add(chunk.compressed_len));
+        let table = Self { chunk_size, chunks };
+        if !valid_chunks || compressed_len.and_then(|len| len.checked_add(table.footer_len())) != Some(file_len) {
+            return Err(LzwError::InvalidHeader);
+        }
+
+        Ok(table)
+    }
+}
+
+// Runs f on every item with a fixed number of workers, results keep the order
+fn parallel_map<T: Sync, R: Send>(items: &[T], threads: usize, f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let next = AtomicUsize::new(0);
+
+    let mut results = thread::scope(|scope| {
+        let workers = (0..threads.min(items.len()))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = vec![];
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(i) else {
+                            break;
+                        };
+                        done.push((i, f(item)));
+                    }
+                    done
+                })
+            })
+            .collect::<Vec<_>>();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+
+    results.sort_by_key(|&(i, _)| i);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+fn code_with<C: UniversalCoder>(chunk: &[u8], universal_coder: C, config: DictConfig, preset: Option<&PresetDict>) -> Vec<u8> {
+    let mut output = vec![];
+    let mut coder = LZWCoder::with_config(chunk, &mut output, universal_coder, config).with_input_len(chunk.len() as u64);
+    if let Some(preset) = preset {
+        coder = coder.with_preset(preset.clone());
+    }
+    coder.code_to_end().expect("Chunks are coded from memory");
+    output
+}
+
+pub fn compress_chunk(chunk: &[u8], coding: Coding, config: DictConfig, preset: Option<&PresetDict>) -> Vec<u8> {
+    match coding {
+        Coding::Gamma => code_with(chunk, GammaCoder, config, preset),
+        Coding::Delta => code_with(chunk, DeltaCoder, config, preset),
+        Coding::Omega => code_with(chunk, OmegaCoder, config, preset),
+        Coding::Fib => code_with(chunk, FibonacciCoder::new(), config, preset),
+        Coding::Binary => code_with(chunk, BinaryCoder::new(), config, preset),
+        Coding::Golomb(m) => code_with(chunk, GolombCoder::new(m as usize), config, preset),
+        Coding::Rice(k) => code_with(chunk, RiceCoder::new(k), config, preset),
+        Coding::ExpGolomb(k) => code_with(chunk, ExpGolombCoder::new(k), config, preset),
+        Coding::AdaptiveRice => code_with(chunk, AdaptiveRiceCoder::new(), config, preset),
+        Coding::Arithmetic => code_with(chunk, ArithmeticCoder::new(), config, preset),
+    }
+}
+
+// Splits the input into chunks coded independently, each with a dictionary of
+// its own, on several threads. Chunks start with an empty dictionary, so
+// smaller chunks compress worse.
+pub struct ChunkedCoder<I, O> {
+    input_stream: I,
+    output_stream: O,
+    coding: Coding,
+    config: DictConfig,
+    preset: Option<PresetDict>,
+    threads: usize,
+    table: ChunkTable,
+
+    input_len: usize,
+    output_len: usize,
+}
+
+impl<I: Read, O: Write> ChunkedCoder<I, O> {
+    pub fn new(input_stream: I, output_stream: O, coding: Coding) -> Self {
+        Self {
+            input_stream,
+            output_stream,
+            coding,
+            config: DictConfig::default(),
+            preset: None,
+            threads: default_threads(),
+            table: ChunkTable::new(DEFAULT_CHUNK_SIZE as u64),
+            input_len: 0,
+            output_len: 0,
+        }
+    }
+
+    pub fn with_config(mut self, config: DictConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "Chunk size has to be positive");
+        self.table.chunk_size = chunk_size as u64;
+        self
+    }
+
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        assert!(threads > 0, "At least one thread is needed");
+        self.threads = threads;
+        self
+    }
+
+    pub fn with_preset(mut self, preset: PresetDict) -> Self {
+        self.preset = Some(preset);
+        self
+    }
+
+    pub fn code_to_end(&mut self) {
+        self.output_stream.write_all(CHUNKED_MAGIC).unwrap();
+        self.output_len = CHUNKED_MAGIC.len();
+
+        let mut input_left = true;
+        while input_left {
+            let mut batch = vec![];
+            while input_left && batch.len() < self.threads * CHUNKS_PER_THREAD {
+                let mut chunk = vec![];
+                (&mut self.input_stream).take(self.table.chunk_size).read_to_end(&mut chunk).unwrap();
+                input_left = chunk.len() as u64 == self.table.chunk_size;
+                if !chunk.is_empty() {
+                    batch.push(chunk);
+                }
+            }
+
+            let (coding, config, preset) = (self.coding, self.config, self.preset.as_ref());
+            let compressed = parallel_map(&batch, self.threads, |chunk| compress_chunk(chunk, coding, config, preset));
+
+            for (chunk, compressed) in batch.iter().zip(compressed) {
+                self.output_stream.write_all(&compressed).unwrap();
+                self.table.chunks.push(ChunkEntry {
+                    compressed_len: compressed.len() as u64,
+                    original_len: chunk.len() as u64,
+                });
+                self.input_len += chunk.len();
+                self.output_len += compressed.len();
+            }
+        }
+
+        self.table.write_to(&mut self.output_stream).unwrap();
+        self.output_len += self.table.footer_len() as usize;
+        self.output_stream.flush().unwrap();
+    }
+
+    pub fn get_stat(&self) -> (usize, usize) {
+        (self.input_len, self.output_len)
+    }
+
+    pub fn table(&self) -> &ChunkTable {
+        &self.table
+    }
+}
+
+pub fn is_chunked(start: &[u8]) -> bool {
+    start.starts_with(CHUNKED_MAGIC)
+}
+
+// Decodes batches of chunks in parallel and writes them out in order
+pub fn decode_chunked<R: Read + Seek, O: Write>(
+    mut input_stream: R,
+    mut output_stream: O,
+    preset: Option<&PresetDict>,
+    threads: usize,
+) -> Result<(ChunkTable, usize), LzwError> {
+    assert!(threads > 0, "At least one thread is needed");
+
+    let table = ChunkTable::read_from(&mut input_stream)?;
+    input_stream.seek(SeekFrom::Start(CHUNKED_MAGIC.len() as u64))?;
+
+    let batch_len = threads * CHUNKS_PER_THREAD;
+    let mut output_len = 0;
+    for (batch_index, batch) in table.chunks.chunks(batch_len).enumerate() {
+        let mut frames = vec![];
+        for chunk in batch {
+            let mut frame = vec![0; chunk.compressed_len as usize];
+            input_stream.read_exact(&mut frame)?;
+            frames.push((frame, chunk.original_len));
+        }
+
+        let decoded = parallel_map(&frames, threads, |(frame, original_len)| {
+            // Checked before decoding, so the table bounds what a chunk may decode to
+            let header = StreamHeader::read(&mut frame.as_slice()).ok_or(LzwError::InvalidHeader)?;
+            if header.len != *original_len {
+                return Err(LzwError::InvalidHeader);
+            }
+            let mut output = vec![];
+            decode_auto_with_preset(frame.as_slice(), &mut output, preset)?;
+            Ok(output)
+        });
+
+        for (i, chunk) in decoded.into_iter().enumerate() {
+            let chunk = chunk.map_err(|error| LzwError::Chunk {
+                index: batch_index * batch_len + i,
+                error: Box::new(error),
+            })?;
+            output_stream.write_all(&chunk)?;
+            output_len += chunk.len();
+        }
+    }
+
+    output_stream.flush()?;
+    Ok((table, output_len))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::{
+        dict_policy::DictPolicy,
+        header::{HEADER_LEN, STREAMED_LEN},
+    };
+
+    use super::*;
+
+    fn code(input: &[u8], coding: Coding, chunk_size: usize, threads: usize) -> Vec<u8> {
+        let mut coded_buf = vec![];
+        let mut coder = ChunkedCoder::new(input, &mut coded_buf, coding)
+            .with_config(DictConfig::new(DictPolicy::Reset, 4096))
+            .with_chunk_size(chunk_size)
+            .with_threads(threads);
+        coder.code_to_end();
+        assert_eq!(coder.get_stat(), (input.len(), coded_buf.len()));
+        coded_buf
+    }
+
+    #[test]
+    fn chunked_round_trip() {
+        let text = include_bytes!("./../../testy/pan_tadeusz.txt");
+
+        for (chunk_size, threads) in [(10_000, 1), (10_000, 3), (33_333, 8), (1 << 20, 4), (1, 2)] {
+            let input = if chunk_size == 1 { &text[..500] } else { text.as_slice() };
+            let coded_buf = code(input, Coding::Fib, chunk_size, threads);
+
+            let mut output_buf = vec![];
+            let (table, output_len) = decode_chunked(Cursor::new(&coded_buf), &mut output_buf, None, threads).unwrap();
+            println!("{} chunks of {}: {} bytes", table.chunks.len(), chunk_size, coded_buf.len());
+            assert_eq!(table.chunks.len(), input.len().div_ceil(chunk_size));
+            assert_eq!(table.original_len(), input.len() as u64);
+            assert_eq!(output_len, input.len());
+            assert_eq!(input, output_buf);
+
+            // The thread count does not change the output
+            assert_eq!(code(input, Coding::Fib, chunk_size, 1), coded_buf);
+        }
+
+        let coded_buf = code(&[], Coding::Gamma, 100, 2);
+        let mut output_buf = vec![];
+        let (table, _) = decode_chunked(Cursor::new(&coded_buf), &mut output_buf, None, 2).unwrap();
+        assert!(table.chunks.is_empty() && output_buf.is_empty());
+    }
+
+    #[test]
+    fn chunked_preset_and_errors() {
+        let text = include_bytes!("./../../testy/pan_tadeusz.txt");
+        let preset = PresetDict::new(text[..5000].to_vec());
+
+        let mut coded_buf = vec![];
+        ChunkedCoder::new(text.as_slice(), &mut coded_buf, Coding::Arithmetic)
+            .with_chunk_size(20_000)
+            .with_threads(4)
+            .with_preset(preset.clone())
+            .code_to_end();
+        let mut output_buf = vec![];
+        decode_chunked(Cursor::new(&coded_buf), &mut output_buf, Some(&preset), 4).unwrap();
+        assert_eq!(text.as_slice(), output_buf);
+
+        let err = decode_chunked(Cursor::new(&coded_buf), &mut vec![], None, 4).unwrap_err();
+        println!("{}", err);
+        assert!(matches!(err, LzwError::Chunk { index: 0, .. }));
+
+        // A damaged chunk is reported with its index
+        let coded_buf = code(text, Coding::Gamma, 20_000, 3);
+        let table = ChunkTable::read_from(Cursor::new(&coded_buf)).unwrap();
+        let third = CHUNKED_MAGIC.len() + table.chunks[..2].iter().map(|chunk| chunk.compressed_len as usize).sum::<usize>();
+        let mut damaged = coded_buf.clone();
+        damaged[third + 4] ^= 0xFF;
+        let err = decode_chunked(Cursor::new(&damaged), &mut vec![], None, 3).unwrap_err();
+        println!("{}", err);
+        assert!(matches!(err, LzwError::Chunk { index: 2, .. }));
+
+        // Chunk headers have to agree with the table, streamed ones included
+        for len in [STREAMED_LEN, table.chunks[2].original_len + 1] {
+            let mut damaged = coded_buf.clone();
+            damaged[third + 18..third + HEADER_LEN].copy_from_slice(&len.to_le_bytes());
+            let err = decode_chunked(Cursor::new(&damaged), &mut vec![], None, 3).unwrap_err();
+            assert_eq!(
+                err,
+                LzwError::Chunk {
+                    index: 2,
+                    error: Box::new(LzwError::InvalidHeader)
+                }
+            );
+        }
+
+        // So is a table that does not add up
+        for cut in [1, 10, coded_buf.len() - 4] {
+            let truncated = &coded_buf[..coded_buf.len() - cut];
+            assert_eq!(decode_chunked(Cursor::new(truncated), &mut vec![], None, 3).unwrap_err(), LzwError::InvalidHeader);
+        }
+        let mut damaged = coded_buf.clone();
+        let count_pos = coded_buf.len() - 12;
+        damaged[count_pos] += 1;
+        assert_eq!(decode_chunked(Cursor::new(&damaged), &mut vec![], None, 3).unwrap_err(), LzwError::InvalidHeader);
+    }
+}
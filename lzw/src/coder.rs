@@ -1,82 +1,350 @@
-use std::io::{Read, Write};
+use std::{
+    io::{self, Read, Write},
+    mem,
+};
 
 use crate::{
+    diagnostics::Diagnostics,
+    dict_policy::{DictConfig, DictPolicy, DictVariant, RATIO_CHECK_GAP},
+    header::{StreamHeader, STREAMED_LEN},
+    lru::LruTracker,
+    phrase_dict::PhraseDict,
     prefix_tree::PrefixTree,
+    preset::PresetDict,
     universal_coder::{BitOutputStream, UniversalCoder},
+    words::code_words,
+    CLEAR_CODE,
 };
 
+const READ_CHUNK: u64 = 1 << 16;
+
 pub struct LZWCoder<I, O, C> {
     input_stream: I,
     output_stream: BitOutputStream<O>,
     universal_coder: C,
-    saved_byte: Option<u8>,
+    data: Vec<u8>,
+    data_start: usize,
+    input_done: bool,
+    declared_len: Option<u64>,
+    current_node: Option<usize>,
+    streamed: bool,
     tree: PrefixTree,
+    phrases: Option<PhraseDict>,
+    previous_code: Option<usize>,
+    phrase_start: usize,
+    config: DictConfig,
+    preset: Option<PresetDict>,
+    diagnostics: Option<Diagnostics>,
+    lru: Option<LruTracker>,
+    decoder_dict_size: usize,
+    emitted_codes: usize,
+    best_ratio: f64,
+    next_ratio_check: usize,
 
     input_len: usize
 }
 
 impl<I: Read, O: Write, C: UniversalCoder> LZWCoder<I, O, C> {
     pub fn new(input_stream: I, output_stream: O, universal_coder: C) -> Self {
+        Self::with_config(input_stream, output_stream, universal_coder, DictConfig::default())
+    }
+
+    pub fn with_config(input_stream: I, output_stream: O, universal_coder: C, config: DictConfig) -> Self {
         Self {
-            tree: PrefixTree::new(),
+            tree: PrefixTree::with_size(config.initial_size(), config.max_size),
+            phrases: matches!(config.variant, DictVariant::Lzmw | DictVariant::Lzap).then(|| PhraseDict::new(config)),
+            previous_code: None,
+            phrase_start: 0,
             input_stream,
-            saved_byte: None,
+            data: vec![],
+            data_start: 0,
+            input_done: false,
+            declared_len: None,
+            current_node: None,
+            streamed: false,
+            config,
+            preset: None,
+            diagnostics: None,
+            lru: (config.policy == DictPolicy::Lru).then(|| LruTracker::new(config.initial_size())),
+            decoder_dict_size: config.initial_size(),
+            emitted_codes: 0,
+            best_ratio: 0.0,
+            next_ratio_check: 0,
             universal_coder,
             output_stream: BitOutputStream::new(output_stream),
             input_len : 0,
         }
     }
 
-    pub fn code_to_end(&mut self) {
-        while let Some(code) = self.code() {
-            self.universal_coder.code_number(&mut self.output_stream, code + 1);
+    // Both sides start from the dictionary built while coding the sample
+    pub fn with_preset(mut self, preset: PresetDict) -> Self {
+        assert!(self.config.variant != DictVariant::Words, "Word dictionaries cannot use a preset");
+
+        self.preset = Some(preset);
+        self.fresh_dict();
+        self
+    }
+
+    pub fn with_diagnostics(mut self) -> Self {
+        self.diagnostics = Some(Diagnostics::new(self.universal_coder.coding(), self.config));
+        self
+    }
+
+    // Known lengths go into the header, otherwise the stream ends with an end code
+    pub fn with_input_len(mut self, len: u64) -> Self {
+        self.declared_len = Some(len);
+        self
+    }
+
+    // Fails when the input cannot be read or its length differs from the declared
+    // one, the stream written so far is then left unfinished
+    pub fn code_to_end(&mut self) -> io::Result<()> {
+        if self.config.variant == DictVariant::Words {
+            // Tokens and their ids are built over the whole input
+            self.input_stream.read_to_end(&mut self.data)?;
+            self.write_header(Some(self.data.len() as u64));
+            code_words(&self.data, &mut self.output_stream, &mut self.universal_coder, self.config.max_size);
+            self.input_len = self.data.len();
+        } else if self.phrases.is_some() {
+            self.write_header(self.declared_len);
+            while let Some(code) = self.code_phrase()? {
+                self.finish_code(code, self.input_len - self.phrase_start);
+            }
+        } else {
+            self.write_header(self.declared_len);
+            while self.read_chunk()? {
+                let chunk = mem::take(&mut self.data);
+                for &byte in &chunk {
+                    self.push(byte);
+                }
+                self.data = chunk;
+            }
+        }
+        if let Some(len) = self.declared_len.filter(|&len| len != self.input_len as u64) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("input has {} bytes, {} were declared", self.input_len, len),
+            ));
+        }
+
+        self.finish();
+        Ok(())
+    }
+
+    // Reads more input after the bytes not coded yet, false at the end
+    fn read_chunk(&mut self) -> io::Result<bool> {
+        self.data.drain(..self.input_len - self.data_start);
+        self.data_start = self.input_len;
+
+        let read = self.input_stream.by_ref().take(READ_CHUNK).read_to_end(&mut self.data)?;
+        self.input_done = read == 0;
+        Ok(!self.input_done)
+    }
+
+    // Streams of unknown length end with an end code instead
+    pub(crate) fn write_header(&mut self, len: Option<u64>) {
+        self.streamed = len.is_none();
+        let header = StreamHeader {
+            coding: self.universal_coder.coding(),
+            config: self.config,
+            len: len.unwrap_or(STREAMED_LEN),
+            preset: self.preset.as_ref().map(PresetDict::hash),
+        };
+        self.output_stream.write_bytes(&header.to_bytes());
+    }
+
+    // Plain LZW, one byte at a time. The phrase read so far waits in
+    // current_node until a byte does not extend it.
+    pub(crate) fn push(&mut self, byte: u8) {
+        self.input_len += 1;
+
+        let Some(node) = self.current_node else {
+            self.current_node = Some(byte as usize);
+            self.phrase_start = self.input_len - 1;
+            return;
+        };
+        if let Some(next_node) = self.tree.travel(node, byte) {
+            self.current_node = Some(next_node);
+        } else {
+            self.add_entry(node, byte);
+            self.finish_code(node, self.input_len - 1 - self.phrase_start);
+            self.current_node = Some(byte as usize);
+            self.phrase_start = self.input_len - 1;
+        }
+    }
+
+    pub(crate) fn finish(&mut self) {
+        if let Some(node) = self.current_node.take() {
+            self.finish_code(node, self.input_len - self.phrase_start);
+        }
+        if self.streamed {
+            self.emit(self.decoder_dict_size + 1);
         }
         self.universal_coder.pad(&mut self.output_stream);
+        self.output_stream.flush();
+
+        if let Some(diagnostics) = &mut self.diagnostics {
+            diagnostics.finish(self.input_len, self.output_stream.written_bits(), self.decoder_dict_size);
+        }
+    }
+
+    pub(crate) fn output_mut(&mut self) -> &mut O {
+        self.output_stream.get_mut()
+    }
+
+    pub fn config(&self) -> DictConfig {
+        self.config
+    }
+
+    pub fn diagnostics(&self) -> Option<&Diagnostics> {
+        self.diagnostics.as_ref()
+    }
+
+    pub fn dict_len(&self) -> usize {
+        self.tree.len()
     }
 
     pub fn get_stat(&self) -> (usize, usize) {
         (self.input_len, self.output_stream.written_bytes)
     }
 
-    fn code(&mut self) -> Option<usize> {
-        let start = self.read()?;
-        let mut current_node = start as usize;
+    fn code_phrase(&mut self) -> io::Result<Option<usize>> {
+        loop {
+            let Some(phrases) = self.phrases.as_ref() else {
+                return Ok(None);
+            };
+            let rest = &self.data[self.input_len - self.data_start..];
+            if !rest.is_empty() {
+                let (code, len) = phrases.longest_match(rest);
+                // A match up to the end of the buffer may go on in the input
+                if len < rest.len() || self.input_done {
+                    self.phrase_start = self.input_len;
+                    self.input_len += len;
+                    return Ok(Some(code));
+                }
+            } else if self.input_done {
+                return Ok(None);
+            }
+            self.read_chunk()?;
+        }
+    }
+
+    fn finish_code(&mut self, code: usize, phrase_len: usize) {
+        self.emit(code);
+        if let Some(lru) = &mut self.lru {
+            lru.touch(code, self.emitted_codes);
+        }
+        self.mirror_decoder_dict(code);
+
+        if let Some(diagnostics) = &mut self.diagnostics {
+            diagnostics.add_phrase(phrase_len);
+            diagnostics.sample_dict(self.input_len, self.decoder_dict_size);
+        }
+        self.check_full_dict();
+    }
+
+    fn emit(&mut self, code: usize) {
+        // Leaves room for the end code
+        self.universal_coder.set_dict_size(self.decoder_dict_size + self.streamed as usize);
+        let start = self.output_stream.written_bits();
+        self.universal_coder.code_number(&mut self.output_stream, code + 1);
+
+        if let Some(diagnostics) = &mut self.diagnostics {
+            diagnostics.add_code(self.output_stream.written_bits() - start);
+        }
+    }
+
+    fn add_entry(&mut self, from: usize, byte: u8) {
+        if !self.tree.is_full() {
+            let id = self.tree.append(from, byte);
+            if let Some(lru) = &mut self.lru {
+                lru.add(id, from, self.emitted_codes);
+            }
+        } else if let Some(lru) = &mut self.lru {
+            if let Some(victim) = lru.victim(from) {
+                self.tree.replace(victim, from, byte);
+                lru.add(victim, from, self.emitted_codes);
+            }
+        }
+    }
 
-        while let Some(byte) = self.read() {
-            let maybe_next_node = self.tree.travel(current_node, byte);
-            if let Some(next_node) = maybe_next_node {
-                current_node = next_node;
-            } else {
-                self.save_byte(byte);
-                self.tree.append(current_node, byte);
-                break;
+    fn mirror_decoder_dict(&mut self, code: usize) {
+        if let Some(phrases) = &mut self.phrases {
+            // LZMW and LZAP add entries only once the decoder knows the whole phrase
+            if let Some(previous_code) = self.previous_code {
+                phrases.extend(previous_code, &self.data[self.phrase_start - self.data_start..self.input_len - self.data_start]);
             }
+            self.previous_code = Some(code);
+            self.decoder_dict_size = phrases.len();
+        } else if self.emitted_codes > 0 && self.decoder_dict_size < self.config.max_size {
+            self.decoder_dict_size += 1;
         }
+        self.emitted_codes += 1;
+    }
 
-        Some(current_node)
+    fn is_dict_full(&self) -> bool {
+        match &self.phrases {
+            Some(phrases) => phrases.is_full(),
+            None => self.decoder_dict_size >= self.config.max_size,
+        }
     }
 
-    fn save_byte(&mut self, byte: u8) {
-        self.saved_byte = Some(byte)
+    fn check_full_dict(&mut self) {
+        if !self.is_dict_full() {
+            return;
+        }
+        if let Some(diagnostics) = &mut self.diagnostics {
+            diagnostics.dict_full(self.input_len);
+        }
+
+        match self.config.policy {
+            DictPolicy::Reset => self.clear(),
+            DictPolicy::AdaptiveReset if self.input_len >= self.next_ratio_check => {
+                self.next_ratio_check = self.input_len + RATIO_CHECK_GAP;
+
+                let ratio = self.input_len as f64 / self.output_stream.written_bits() as f64;
+                if ratio > self.best_ratio {
+                    self.best_ratio = ratio;
+                } else {
+                    self.best_ratio = 0.0;
+                    self.clear();
+                }
+            }
+            _ => {}
+        }
     }
 
-    fn read(&mut self) -> Option<u8> {
-        if self.saved_byte.is_some() {
-            self.saved_byte.take()
-        } else {
-            self.read_new_byte()
+    fn clear(&mut self) {
+        self.emit(CLEAR_CODE);
+        self.fresh_dict();
+
+        if let Some(diagnostics) = &mut self.diagnostics {
+            diagnostics.reset(self.input_len);
         }
     }
 
-    fn read_new_byte(&mut self) -> Option<u8> {
-        let mut buf = [0];
-        self.input_stream
-            .read_exact(&mut buf)
-            .ok()
-            .map(|_| {
-                self.input_len += 1;
-                buf[0]
-            })
+    fn fresh_dict(&mut self) {
+        self.tree = PrefixTree::with_size(self.config.initial_size(), self.config.max_size);
+        if let Some(preset) = &self.preset {
+            preset.prime_tree(&mut self.tree);
+        }
+        self.decoder_dict_size = self.tree.len();
+
+        if self.phrases.is_some() {
+            let mut phrases = PhraseDict::new(self.config);
+            if let Some(preset) = &self.preset {
+                preset.prime_phrases(&mut phrases);
+            }
+            self.decoder_dict_size = phrases.len();
+            self.phrases = Some(phrases);
+            self.previous_code = None;
+        }
+
+        // Preset entries are never evicted
+        if self.lru.is_some() {
+            self.lru = Some(LruTracker::new(self.tree.len()));
+        }
+        self.emitted_codes = 0;
     }
 }
 
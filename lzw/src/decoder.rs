@@ -1,82 +1,447 @@
-use std::io::{Read, Write};
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    io::{self, Read, Write},
+};
+
+use crate::{
+    dict_policy::{DictConfig, DictPolicy, DictVariant},
+    header::{Coding, StreamHeader, HEADER_LEN, STREAMED_LEN},
+    lru::LruTracker,
+    phrase_dict::PhraseDict,
+    prefix_tree::PrefixTree,
+    preset::PresetDict,
+    words::decode_words,
+    universal_decoder::{
+        AdaptiveRiceDecoder, ArithmeticDecoder, BinaryDecoder, BitInputStream, DeltaDecoder, ExpGolombDecoder, FibonacciDecoder,
+        DecodeError, GammaDecoder, GolombDecoder, OmegaDecoder, RiceDecoder, UniversalDecoder,
+    },
+    CLEAR_CODE,
+};
+
+const NO_PARENT: u32 = u32::MAX;
+
+// Offsets count bits from the start of the codes, right after the header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LzwError {
+    InvalidHeader,
+    CodingMismatch { stream: Coding, decoder: Coding },
+    PresetMismatch { stream: Option<u32>, decoder: Option<u32> },
+    InvalidCode { offset: u64, code: u128 },
+    Truncated { offset: u64 },
+    Overflow { offset: u64 },
+    TooLong { offset: u64 },
+    Chunk { index: usize, error: Box<LzwError> },
+    Io(io::ErrorKind),
+}
+
+impl Display for LzwError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LzwError::InvalidHeader => write!(f, "not an LZW stream"),
+            LzwError::CodingMismatch { stream, decoder } => {
+                write!(f, "stream is coded with {:?}, decoder expects {:?}", stream, decoder)
+            }
+            LzwError::PresetMismatch { stream, decoder } => {
+                write!(f, "stream uses preset {}, decoder has {}", preset_name(*stream), preset_name(*decoder))
+            }
+            LzwError::InvalidCode { offset, code } => write!(f, "invalid code {} at bit {}", code, offset),
+            LzwError::Truncated { offset } => write!(f, "stream ends before all data was decoded, at bit {}", offset),
+            LzwError::Overflow { offset } => write!(f, "code at bit {} does not fit in 128 bits", offset),
+            LzwError::TooLong { offset } => write!(f, "code at bit {} decodes past the original length", offset),
+            LzwError::Chunk { index, error } => write!(f, "chunk {}: {}", index, error),
+            LzwError::Io(kind) => write!(f, "io error: {}", kind),
+        }
+    }
+}
 
-use crate::{universal_decoder::{BitInputStream, UniversalDecoder}, MAX_DICT_SIZE};
+fn preset_name(hash: Option<u32>) -> String {
+    hash.map_or("none".to_string(), |hash| format!("{:08x}", hash))
+}
 
+impl Error for LzwError {}
+
+impl From<io::Error> for LzwError {
+    fn from(err: io::Error) -> Self {
+        LzwError::Io(err.kind())
+    }
+}
+
+impl From<LzwError> for io::Error {
+    fn from(err: LzwError) -> Self {
+        match err {
+            LzwError::Io(kind) => kind.into(),
+            err => io::Error::new(io::ErrorKind::InvalidData, err),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct DictEntry {
+    parent: u32,
+    byte: u8,
+    len: u32,
+}
+
+// The universal decoders peek at the input ahead of the codes, so up to 16
+// bytes past the end of the stream may be read from input_stream
 pub struct LZWDecoder<I, O, D> {
     input_stream: BitInputStream<I>,
     output_stream: O,
     universal_decoder: D,
+    header: Option<StreamHeader>,
+    config: DictConfig,
+    preset: Option<PresetDict>,
+    lru: Option<LruTracker>,
     last_id: Option<usize>,
-    dict: Vec<Vec<u8>>,
+    decoded_codes: usize,
+    dict: Vec<DictEntry>,
+    phrases: Option<PhraseDict>,
+    phrase: Vec<u8>,
     output_len: usize,
+    data_start: u64,
+    finished: bool,
 }
 
 impl<I: Read, O: Write, D: UniversalDecoder> LZWDecoder<I, O, D> {
     pub fn new(input_stream: I, output_stream: O, universal_decoder: D) -> Self {
-        let mut dict = vec![];
-        for i in 0..=255 {
-            dict.push(vec![i]);
-        }
-
         Self {
             input_stream: BitInputStream::new(input_stream),
             output_stream,
             universal_decoder,
+            header: None,
+            config: DictConfig::default(),
+            preset: None,
+            lru: None,
             last_id: None,
-            dict,
+            decoded_codes: 0,
+            dict: vec![],
+            phrases: None,
+            phrase: vec![],
             output_len: 0,
+            data_start: 0,
+            finished: false,
         }
     }
 
-    pub fn decode_to_end(&mut self) {
-        while let Some(new_n) = self.universal_decoder.decode_number(&mut self.input_stream) {
-            self.decode_one(new_n - 1);
+    // For a stream whose header was already read, e.g. by decode_auto
+    pub fn with_header(input_stream: I, output_stream: O, universal_decoder: D, header: StreamHeader) -> Self {
+        Self {
+            header: Some(header),
+            ..Self::new(input_stream, output_stream, universal_decoder)
         }
     }
 
+    pub fn with_preset(mut self, preset: PresetDict) -> Self {
+        self.preset = Some(preset);
+        self
+    }
+
+    pub fn decode_to_end(&mut self) -> Result<(), LzwError> {
+        self.start()?;
+        while self.decode_next()? {}
+
+        self.output_stream.flush()?;
+        Ok(())
+    }
+
+    pub(crate) fn read_header(&mut self) -> Result<StreamHeader, LzwError> {
+        if let Some(header) = self.header {
+            return Ok(header);
+        }
+        let mut fixed = [0; HEADER_LEN];
+        self.input_stream.read_bytes(&mut fixed).ok_or(LzwError::InvalidHeader)?;
+        let mut bytes = fixed.to_vec();
+        bytes.resize(HEADER_LEN + StreamHeader::extra_len(&fixed), 0);
+        self.input_stream.read_bytes(&mut bytes[HEADER_LEN..]).ok_or(LzwError::InvalidHeader)?;
+        let header = StreamHeader::from_bytes(&bytes).ok_or(LzwError::InvalidHeader)?;
+        self.header = Some(header);
+        Ok(header)
+    }
+
+    // Reads and checks the header
+    pub(crate) fn start(&mut self) -> Result<(), LzwError> {
+        let header = self.read_header()?;
+        if header.coding != self.universal_decoder.coding() {
+            return Err(LzwError::CodingMismatch {
+                stream: header.coding,
+                decoder: self.universal_decoder.coding(),
+            });
+        }
+        let preset = self.preset.as_ref().map(PresetDict::hash);
+        if header.preset != preset {
+            return Err(LzwError::PresetMismatch {
+                stream: header.preset,
+                decoder: preset,
+            });
+        }
+
+        self.config = header.config;
+        if self.config.variant == DictVariant::Words {
+            let output_len =
+                decode_words(&mut self.input_stream, &mut self.output_stream, &mut self.universal_decoder, header)?;
+            self.output_len = output_len;
+            // Word streams are decoded in one go
+            self.finished = true;
+            return Ok(());
+        }
+        self.reset_dict();
+
+        self.data_start = self.input_stream.position();
+        Ok(())
+    }
+
+    // Decodes one code, returns false once the stream is over
+    pub(crate) fn decode_next(&mut self) -> Result<bool, LzwError> {
+        let Some(header) = self.header else {
+            unreachable!("The header is read first");
+        };
+        let streamed = header.len == STREAMED_LEN;
+        if self.finished || (!streamed && self.output_len as u64 >= header.len) {
+            return Ok(false);
+        }
+        let offset = self.input_stream.position() - self.data_start;
+
+        let dict_len = self.dict_len();
+        self.universal_decoder.set_dict_size(dict_len + streamed as usize);
+        let number = self
+            .universal_decoder
+            .decode_positive(&mut self.input_stream)
+            .map_err(|err| match (err, self.input_stream.take_error()) {
+                (_, Some(err)) => LzwError::from(err),
+                (DecodeError::EndOfStream | DecodeError::Truncated, None) => LzwError::Truncated { offset },
+                (DecodeError::Overflow, None) => LzwError::Overflow { offset },
+            })?;
+
+        let code = number - 1;
+        if streamed && code == dict_len as u128 + 1 {
+            self.finished = true;
+            return Ok(false);
+        }
+        if self.config.policy.uses_clear_code() && code == CLEAR_CODE as u128 {
+            self.reset_dict();
+        } else if self.phrases.is_some() {
+            self.decode_phrase(code, offset, header.len)?;
+        } else {
+            self.decode_one(code, offset, header.len)?;
+        }
+
+        Ok(true)
+    }
+
+    pub(crate) fn output_mut(&mut self) -> &mut O {
+        &mut self.output_stream
+    }
+
     pub fn get_stat(&self) -> usize {
         self.output_len
     }
 
-    fn decode_one(&mut self, n: usize) {
-        if let Some(entry) = self.dict.get(n) {
-            self.output_stream.write_all(entry).unwrap();
-            self.output_len += entry.len();
+    pub fn dict_len(&self) -> usize {
+        self.phrases.as_ref().map_or(self.dict.len(), PhraseDict::len)
+    }
+
+    pub fn config(&self) -> DictConfig {
+        self.config
+    }
+
+    pub fn header(&self) -> Option<StreamHeader> {
+        self.header
+    }
+
+    fn decode_one(&mut self, code: u128, offset: u64, len: u64) -> Result<(), LzwError> {
+        let slot = self.last_id.and_then(|last_id| self.next_slot(last_id));
+        let is_next_entry = slot.is_some_and(|slot| slot as u128 == code);
+        if !is_next_entry && code >= self.dict.len() as u128 {
+            return Err(LzwError::InvalidCode { offset, code });
+        }
+        let n = code as usize;
+
+        if let Some(last_id) = self.last_id {
+            // The phrase buffer still holds the previous phrase
+            if is_next_entry {
+                self.phrase.push(self.phrase[0]);
+            } else {
+                self.expand(n);
+            }
 
-            if let Some(last_id) = self.last_id {
-                let first_byte = entry[0];
-                self.add_entry(last_id, first_byte);
+            if let Some(slot) = slot {
+                self.add_entry(slot, last_id, self.phrase[0]);
+            }
+
+            if let Some(lru) = &mut self.lru {
+                lru.touch(last_id, self.decoded_codes - 1);
             }
         } else {
-            let last_id = self.last_id.unwrap();
-            let first_byte = self.dict[last_id][0];
-            self.add_entry(last_id, first_byte);
+            self.expand(n);
+        }
 
-            let entry = &self.dict.last().unwrap();
-            
-            self.output_stream.write_all(entry).unwrap();
-            self.output_len += entry.len();
+        if self.output_len as u64 + self.phrase.len() as u64 > len {
+            return Err(LzwError::TooLong { offset });
+        }
+        self.output_stream.write_all(&self.phrase)?;
+        self.output_len += self.phrase.len();
+
+        self.last_id = Some(n);
+        self.decoded_codes += 1;
+
+        Ok(())
+    }
+
+    fn decode_phrase(&mut self, code: u128, offset: u64, len: u64) -> Result<(), LzwError> {
+        let Some(phrases) = &mut self.phrases else {
+            unreachable!("Only used for LZMW and LZAP");
+        };
+        if code >= phrases.len() as u128 {
+            return Err(LzwError::InvalidCode { offset, code });
+        }
+        let n = code as usize;
+
+        phrases.phrase(n, &mut self.phrase);
+        if self.output_len as u64 + self.phrase.len() as u64 > len {
+            return Err(LzwError::TooLong { offset });
+        }
+        self.output_stream.write_all(&self.phrase)?;
+        self.output_len += self.phrase.len();
+
+        if let Some(last_id) = self.last_id {
+            phrases.extend(last_id, &self.phrase);
         }
         self.last_id = Some(n);
+        self.decoded_codes += 1;
+
+        Ok(())
+    }
+
+    fn expand(&mut self, n: usize) {
+        self.phrase.resize(self.dict[n].len as usize, 0);
+
+        let mut id = n;
+        for byte in self.phrase.iter_mut().rev() {
+            *byte = self.dict[id].byte;
+            id = self.dict[id].parent as usize;
+        }
+    }
+
+    fn next_slot(&self, from: usize) -> Option<usize> {
+        if self.dict.len() < self.config.max_size {
+            Some(self.dict.len())
+        } else if let Some(lru) = &self.lru {
+            lru.victim(from)
+        } else {
+            None
+        }
+    }
+
+    fn add_entry(&mut self, slot: usize, from: usize, new_byte: u8) {
+        let new_entry = DictEntry {
+            parent: from as u32,
+            byte: new_byte,
+            len: self.dict[from].len + 1,
+        };
+
+        if slot == self.dict.len() {
+            self.dict.push(new_entry);
+        } else {
+            self.dict[slot] = new_entry;
+        }
+
+        if let Some(lru) = &mut self.lru {
+            lru.add(slot, from, self.decoded_codes - 1);
+        }
     }
 
-    fn add_entry(&mut self, id: usize, new_byte: u8) {
-        if self.dict.len() >= MAX_DICT_SIZE {
-            return;
+    fn reset_dict(&mut self) {
+        self.dict.clear();
+        for i in 0..=255 {
+            self.dict.push(DictEntry {
+                parent: NO_PARENT,
+                byte: i,
+                len: 1,
+            });
+        }
+        if self.config.policy.uses_clear_code() {
+            self.dict.push(DictEntry {
+                parent: NO_PARENT,
+                byte: 0,
+                len: 0,
+            });
+        }
+
+        if let Some(preset) = &self.preset {
+            let mut tree = PrefixTree::with_size(self.config.initial_size(), self.config.max_size);
+            preset.prime_tree(&mut tree);
+            for node in self.dict.len()..tree.len() {
+                let parent = tree.parent(node).unwrap();
+                self.dict.push(DictEntry {
+                    parent: parent as u32,
+                    byte: tree.byte(node),
+                    len: self.dict[parent].len + 1,
+                });
+            }
         }
 
-        let mut new_entry = self.dict[id].clone();
-        new_entry.push(new_byte);
-        self.dict.push(new_entry);
+        self.lru = (self.config.policy == DictPolicy::Lru).then(|| LruTracker::new(self.dict.len()));
+        self.phrases = matches!(self.config.variant, DictVariant::Lzmw | DictVariant::Lzap).then(|| {
+            let mut phrases = PhraseDict::new(self.config);
+            if let Some(preset) = &self.preset {
+                preset.prime_phrases(&mut phrases);
+            }
+            phrases
+        });
+        self.last_id = None;
+        self.decoded_codes = 0;
     }
 }
 
+// Reads the header and decodes with whichever coder it names
+pub fn decode_auto<I: Read, O: Write>(input_stream: I, output_stream: O) -> Result<(StreamHeader, usize), LzwError> {
+    decode_auto_with_preset(input_stream, output_stream, None)
+}
+
+pub fn decode_auto_with_preset<I: Read, O: Write>(
+    mut input_stream: I,
+    output_stream: O,
+    preset: Option<&PresetDict>,
+) -> Result<(StreamHeader, usize), LzwError> {
+    let header = StreamHeader::read(&mut input_stream).ok_or(LzwError::InvalidHeader)?;
+
+    let output_len = match header.coding {
+        Coding::Gamma => decode_with(input_stream, output_stream, GammaDecoder, header, preset),
+        Coding::Delta => decode_with(input_stream, output_stream, DeltaDecoder, header, preset),
+        Coding::Omega => decode_with(input_stream, output_stream, OmegaDecoder, header, preset),
+        Coding::Fib => decode_with(input_stream, output_stream, FibonacciDecoder::new(), header, preset),
+        Coding::Binary => decode_with(input_stream, output_stream, BinaryDecoder::new(), header, preset),
+        Coding::Golomb(m) => decode_with(input_stream, output_stream, GolombDecoder::new(m as usize), header, preset),
+        Coding::Rice(k) => decode_with(input_stream, output_stream, RiceDecoder::new(k), header, preset),
+        Coding::ExpGolomb(k) => decode_with(input_stream, output_stream, ExpGolombDecoder::new(k), header, preset),
+        Coding::AdaptiveRice => decode_with(input_stream, output_stream, AdaptiveRiceDecoder::new(), header, preset),
+        Coding::Arithmetic => decode_with(input_stream, output_stream, ArithmeticDecoder::new(), header, preset),
+    };
+
+    Ok((header, output_len?))
+}
+
+fn decode_with<I: Read, O: Write, D: UniversalDecoder>(
+    input_stream: I,
+    output_stream: O,
+    universal_decoder: D,
+    header: StreamHeader,
+    preset: Option<&PresetDict>,
+) -> Result<usize, LzwError> {
+    let mut decoder = LZWDecoder::with_header(input_stream, output_stream, universal_decoder, header);
+    decoder.preset = preset.cloned();
+    decoder.decode_to_end()?;
+    Ok(decoder.get_stat())
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
         coder::LZWCoder,
-        universal_coder::{DeltaCoder, FibonacciCoder, GammaCoder, OmegaCoder},
-        universal_decoder::{DeltaDecoder, FibonacciDecoder, GammaDecoder, OmegaDecoder},
+        dict_policy::{DictConfig, DictPolicy, DictVariant},
+        preset::PresetDict,
+        universal_coder::{ArithmeticCoder, BinaryCoder, DeltaCoder, ExpGolombCoder, FibonacciCoder, GammaCoder, OmegaCoder, RiceCoder},
     };
 
     use super::*;
@@ -89,14 +454,14 @@ mod tests {
 
         let mut coder = LZWCoder::new(buf.as_slice(), &mut coded_buf, GammaCoder);
 
-        coder.code_to_end();
+        coder.code_to_end().unwrap();
 
         println!("original len: {}", buf.len());
         println!("coded len: {}", coded_buf.len());
 
         let mut decoder = LZWDecoder::new(coded_buf.as_slice(), &mut output_buf, GammaDecoder);
 
-        decoder.decode_to_end();
+        decoder.decode_to_end().unwrap();
 
         assert_eq!(buf.as_slice(), output_buf);
         //eprintln!("{}", String::from_utf8_lossy(&output_buf));
@@ -110,14 +475,14 @@ mod tests {
 
         let mut coder = LZWCoder::new(buf.as_bytes(), &mut coded_buf, DeltaCoder);
 
-        coder.code_to_end();
+        coder.code_to_end().unwrap();
 
         println!("original len: {}", buf.len());
         println!("coded len: {}", coded_buf.len());
 
         let mut decoder = LZWDecoder::new(coded_buf.as_slice(), &mut output_buf, DeltaDecoder);
 
-        decoder.decode_to_end();
+        decoder.decode_to_end().unwrap();
 
         assert_eq!(buf.as_bytes(), output_buf);
         //eprintln!("{}", String::from_utf8_lossy(&output_buf));
@@ -131,14 +496,14 @@ mod tests {
 
         let mut coder = LZWCoder::new(buf.as_bytes(), &mut coded_buf, OmegaCoder);
 
-        coder.code_to_end();
+        coder.code_to_end().unwrap();
 
         println!("original len: {}", buf.len());
         println!("coded len: {}", coded_buf.len());
 
         let mut decoder = LZWDecoder::new(coded_buf.as_slice(), &mut output_buf, OmegaDecoder);
 
-        decoder.decode_to_end();
+        decoder.decode_to_end().unwrap();
 
         assert_eq!(buf.as_bytes(), output_buf);
         //eprintln!("{}", String::from_utf8_lossy(&output_buf));
@@ -152,7 +517,7 @@ mod tests {
 
         let mut coder = LZWCoder::new(buf.as_slice(), &mut coded_buf, FibonacciCoder::new());
 
-        coder.code_to_end();
+        coder.code_to_end().unwrap();
 
         println!("original len: {}", buf.len());
         println!("coded len: {}", coded_buf.len());
@@ -163,9 +528,492 @@ mod tests {
             FibonacciDecoder::new(),
         );
 
-        decoder.decode_to_end();
+        decoder.decode_to_end().unwrap();
 
         assert_eq!(buf.as_slice(), output_buf);
         //eprintln!("{}", String::from_utf8_lossy(&output_buf));
     }
+
+    #[test]
+    fn lzw_binary() {
+        for buf in [
+            include_bytes!("./../../testy/test1.bin").as_slice(),
+            include_bytes!("./../../testy/test3.bin").as_slice(),
+            include_bytes!("./../../testy/pan_tadeusz.txt").as_slice(),
+        ] {
+            let mut coded_buf = vec![];
+            let mut output_buf = vec![];
+
+            let mut coder = LZWCoder::new(buf, &mut coded_buf, BinaryCoder::new());
+
+            coder.code_to_end().unwrap();
+
+            println!("original len: {}", buf.len());
+            println!("coded len: {}", coded_buf.len());
+
+            let mut decoder = LZWDecoder::new(coded_buf.as_slice(), &mut output_buf, BinaryDecoder::new());
+
+            decoder.decode_to_end().unwrap();
+
+            assert_eq!(buf, output_buf);
+        }
+    }
+
+    #[test]
+    fn lzw_policies() {
+        let buf = include_bytes!("./../../testy/pan_tadeusz.txt");
+
+        for policy in [DictPolicy::Freeze, DictPolicy::Reset, DictPolicy::AdaptiveReset, DictPolicy::Lru] {
+            for max_size in [258, 600, 4096] {
+                let config = DictConfig::new(policy, max_size);
+                let mut coded_buf = vec![];
+                let mut output_buf = vec![];
+
+                let mut coder = LZWCoder::with_config(buf.as_slice(), &mut coded_buf, BinaryCoder::new(), config);
+
+                coder.code_to_end().unwrap();
+
+                println!("{:?} {} coded len: {}", policy, max_size, coded_buf.len());
+
+                let mut decoder = LZWDecoder::new(coded_buf.as_slice(), &mut output_buf, BinaryDecoder::new());
+
+                decoder.decode_to_end().unwrap();
+
+                assert_eq!(decoder.config(), config);
+                assert_eq!(buf.as_slice(), output_buf);
+            }
+        }
+    }
+
+    #[test]
+    fn lzw_long_phrases() {
+        let buf = vec![0; 4_000_000];
+        let mut coded_buf = vec![];
+        let mut output_buf = vec![];
+
+        let mut coder = LZWCoder::new(buf.as_slice(), &mut coded_buf, BinaryCoder::new());
+
+        coder.code_to_end().unwrap();
+
+        println!("coded len: {}", coded_buf.len());
+
+        let mut decoder = LZWDecoder::new(coded_buf.as_slice(), &mut output_buf, BinaryDecoder::new());
+
+        decoder.decode_to_end().unwrap();
+
+        // Every entry has a fixed size no matter how long its phrase is
+        println!("longest phrase: {}", decoder.dict.iter().map(|entry| entry.len).max().unwrap());
+        println!("dictionary memory: {} bytes", decoder.dict.len() * std::mem::size_of::<DictEntry>());
+        assert_eq!(std::mem::size_of::<DictEntry>(), 12);
+        assert_eq!(buf, output_buf);
+    }
+
+    #[test]
+    fn lzw_auto_detect() {
+        let buf = include_bytes!("./../../testy/pan_tadeusz.txt");
+
+        let mut coded_buf = vec![];
+        LZWCoder::new(buf.as_slice(), &mut coded_buf, RiceCoder::new(9)).with_input_len(buf.len() as u64).code_to_end().unwrap();
+
+        let mut output_buf = vec![];
+        let (header, output_len) = decode_auto(coded_buf.as_slice(), &mut output_buf).unwrap();
+        assert_eq!(header.coding, Coding::Rice(9));
+        assert_eq!(header.len, buf.len() as u64);
+        assert_eq!(output_len, buf.len());
+        assert_eq!(buf.as_slice(), output_buf);
+
+        // A decoder for another coding refuses the stream
+        let mut output_buf = vec![];
+        let result = LZWDecoder::new(coded_buf.as_slice(), &mut output_buf, GammaDecoder).decode_to_end();
+        assert_eq!(
+            result,
+            Err(LzwError::CodingMismatch {
+                stream: Coding::Rice(9),
+                decoder: Coding::Gamma
+            })
+        );
+    }
+
+    struct FailingReader<'a> {
+        data: &'a [u8],
+    }
+
+    impl Read for FailingReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.data.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::ConnectionReset, "gone"));
+            }
+            let len = buf.len().min(self.data.len());
+            buf[..len].copy_from_slice(&self.data[..len]);
+            self.data = &self.data[len..];
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn lzw_read_error() {
+        let buf = include_bytes!("./../../testy/pan_tadeusz.txt");
+
+        for variant in [DictVariant::Lzw, DictVariant::Lzap, DictVariant::Words] {
+            let config = DictConfig::new(DictPolicy::Freeze, 1 << 16).with_variant(variant);
+            let mut coded_buf = vec![];
+            LZWCoder::with_config(buf.as_slice(), &mut coded_buf, GammaCoder, config).code_to_end().unwrap();
+
+            let input_stream = FailingReader { data: &coded_buf[..coded_buf.len() / 2] };
+            let result = decode_auto(input_stream, &mut vec![]);
+            assert_eq!(result.unwrap_err(), LzwError::Io(io::ErrorKind::ConnectionReset), "{:?}", variant);
+        }
+    }
+
+    #[test]
+    fn lzw_exact_end() {
+        // Padding that decodes as extra codes must not reach the output
+        for len in 0..40 {
+            let buf = (0..len).map(|i| (i % 3) as u8).collect::<Vec<_>>();
+            let mut coded_buf = vec![];
+            LZWCoder::new(buf.as_slice(), &mut coded_buf, ExpGolombCoder::new(3)).code_to_end().unwrap();
+            coded_buf.extend_from_slice(&[0x80; 4]);
+
+            let mut output_buf = vec![];
+            decode_auto(coded_buf.as_slice(), &mut output_buf).unwrap();
+            assert_eq!(buf, output_buf);
+        }
+    }
+
+    #[test]
+    fn lzw_malformed_corpus() {
+        let corpus = concat!(env!("CARGO_MANIFEST_DIR"), "/fuzz_corpus");
+        let mut paths = std::fs::read_dir(corpus)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        let mut state = 0x2545_F491_4F6C_DD1Du64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        let mut failures = 0;
+        for path in paths {
+            let seed = std::fs::read(&path).unwrap();
+            let mut output_buf = vec![];
+            match decode_auto(seed.as_slice(), &mut output_buf) {
+                Ok((header, _)) if header.len != STREAMED_LEN => assert_eq!(output_buf.len() as u64, header.len),
+                Ok(_) => {}
+                Err(err) => println!("{}: {}", path.file_name().unwrap().to_string_lossy(), err),
+            }
+
+            // Bit flips, cuts and random tails must only ever give errors
+            for round in 0..300 {
+                let mut mutated = seed.clone();
+                match round % 3 {
+                    0 if !mutated.is_empty() => {
+                        for _ in 0..1 + next() % 4 {
+                            let bit = next() as usize % (mutated.len() * 8);
+                            mutated[bit / 8] ^= 1 << (bit % 8);
+                        }
+                    }
+                    1 => mutated.truncate(next() as usize % (mutated.len() + 1)),
+                    _ => {
+                        let keep = next() as usize % (mutated.len() + 1);
+                        mutated.truncate(keep);
+                        mutated.extend((0..next() % 64).map(|_| next() as u8));
+                    }
+                }
+
+                let mut output_buf = vec![];
+                match decode_auto(mutated.as_slice(), &mut output_buf) {
+                    Ok((header, output_len)) => {
+                        // Streamed headers leave the length to the end code
+                        if header.len != STREAMED_LEN {
+                            assert_eq!(output_len as u64, header.len);
+                        }
+                        assert_eq!(output_buf.len(), output_len);
+                    }
+                    Err(_) => {
+                        failures += 1;
+                    }
+                }
+            }
+        }
+        println!("rejected mutations: {}", failures);
+    }
+
+    #[test]
+    fn lzw_invalid_codes() {
+        let corpus = concat!(env!("CARGO_MANIFEST_DIR"), "/fuzz_corpus/");
+        let decode = |name: &str| {
+            let data = std::fs::read(format!("{}{}", corpus, name)).unwrap();
+            decode_auto(data.as_slice(), vec![]).map(|(_, len)| len)
+        };
+
+        assert_eq!(decode("first_code_too_big.lzw"), Err(LzwError::InvalidCode { offset: 0, code: 300 }));
+        assert_eq!(decode("next_entry_first.lzw"), Err(LzwError::InvalidCode { offset: 0, code: 256 }));
+        assert_eq!(decode("future_code.lzw"), Err(LzwError::InvalidCode { offset: 13, code: 299 }));
+        assert_eq!(decode("too_long.lzw"), Err(LzwError::TooLong { offset: 13 }));
+        assert_eq!(decode("overflow.lzw"), Err(LzwError::Overflow { offset: 0 }));
+        assert_eq!(decode("bad_magic.lzw"), Err(LzwError::InvalidHeader));
+        assert_eq!(decode("golomb_zero.lzw"), Err(LzwError::InvalidHeader));
+        assert!(matches!(decode("truncated_omega.lzw"), Err(LzwError::Truncated { .. })));
+        assert!(matches!(decode("huge_len.lzw"), Err(LzwError::Truncated { .. })));
+        assert_eq!(decode("kwkwk.lzw"), Ok(3));
+    }
+
+    #[test]
+    fn lzw_arithmetic() {
+        let buf = include_bytes!("./../../testy/pan_tadeusz.txt");
+
+        for config in [
+            DictConfig::default(),
+            DictConfig::new(DictPolicy::Reset, 4096),
+            DictConfig::new(DictPolicy::Lru, 4096),
+        ] {
+            let mut coded_buf = vec![];
+            let mut gamma_buf = vec![];
+            LZWCoder::with_config(buf.as_slice(), &mut coded_buf, ArithmeticCoder::new(), config).code_to_end().unwrap();
+            LZWCoder::with_config(buf.as_slice(), &mut gamma_buf, GammaCoder, config).code_to_end().unwrap();
+
+            println!("{:?}: arithmetic {} bytes, gamma {} bytes", config.policy, coded_buf.len(), gamma_buf.len());
+            assert!(coded_buf.len() < gamma_buf.len());
+
+            let mut output_buf = vec![];
+            let (header, _) = decode_auto(coded_buf.as_slice(), &mut output_buf).unwrap();
+            assert_eq!(header.coding, Coding::Arithmetic);
+            assert_eq!(buf.as_slice(), output_buf);
+
+            let mut output_buf = vec![];
+            let result = decode_auto(&coded_buf[..coded_buf.len() / 2], &mut output_buf);
+            assert!(matches!(result, Err(LzwError::Truncated { .. })));
+        }
+    }
+
+    #[test]
+    fn lzw_variants() {
+        let text = include_bytes!("./../../testy/pan_tadeusz.txt");
+        let repeats = text[..20_000].repeat(20);
+
+        for buf in [text.as_slice(), repeats.as_slice()] {
+            for policy in [DictPolicy::Freeze, DictPolicy::Reset] {
+                let mut sizes = vec![];
+                for variant in [DictVariant::Lzw, DictVariant::Lzmw, DictVariant::Lzap] {
+                    let config = DictConfig::new(policy, 1 << 16).with_variant(variant);
+                    let mut coded_buf = vec![];
+                    LZWCoder::with_config(buf, &mut coded_buf, DeltaCoder, config).code_to_end().unwrap();
+
+                    let mut output_buf = vec![];
+                    let (header, _) = decode_auto(coded_buf.as_slice(), &mut output_buf).unwrap();
+                    assert_eq!(header.config, config);
+                    assert_eq!(buf, output_buf);
+                    sizes.push(coded_buf.len());
+                }
+                println!("{:?}: lzw {} lzmw {} lzap {}", policy, sizes[0], sizes[1], sizes[2]);
+            }
+        }
+
+        // Small dictionaries fill up and reset many times
+        let config = DictConfig::new(DictPolicy::AdaptiveReset, 300).with_variant(DictVariant::Lzmw);
+        let mut coded_buf = vec![];
+        LZWCoder::with_config(repeats.as_slice(), &mut coded_buf, BinaryCoder::new(), config).code_to_end().unwrap();
+        let mut output_buf = vec![];
+        decode_auto(coded_buf.as_slice(), &mut output_buf).unwrap();
+        assert_eq!(repeats, output_buf);
+    }
+
+    #[test]
+    fn lzw_preset() {
+        let record = |i: usize| {
+            format!(
+                "{{\"id\": {}, \"user\": \"user{}\", \"status\": \"{}\", \"tags\": [\"sensor\", \"room{}\"], \"value\": {}.{}}}\n",
+                i,
+                i % 17,
+                ["active", "idle", "offline"][i % 3],
+                i % 5,
+                i * 37 % 100,
+                i % 10
+            )
+            .into_bytes()
+        };
+        let sample = (0..20).flat_map(record).collect::<Vec<_>>();
+        let preset = PresetDict::new(sample);
+
+        for variant in [DictVariant::Lzw, DictVariant::Lzmw, DictVariant::Lzap] {
+            for policy in [DictPolicy::Freeze, DictPolicy::Reset, DictPolicy::Lru] {
+                if variant != DictVariant::Lzw && policy == DictPolicy::Lru {
+                    continue;
+                }
+                let config = DictConfig::new(policy, 4096).with_variant(variant);
+
+                let (mut plain, mut primed) = (0, 0);
+                for i in 100..150 {
+                    let buf = record(i);
+
+                    let mut coded_buf = vec![];
+                    LZWCoder::with_config(buf.as_slice(), &mut coded_buf, GammaCoder, config).code_to_end().unwrap();
+                    plain += coded_buf.len();
+
+                    let mut coded_buf = vec![];
+                    LZWCoder::with_config(buf.as_slice(), &mut coded_buf, GammaCoder, config)
+                        .with_preset(preset.clone())
+                        .code_to_end().unwrap();
+                    primed += coded_buf.len();
+
+                    let mut output_buf = vec![];
+                    let (header, _) = decode_auto_with_preset(coded_buf.as_slice(), &mut output_buf, Some(&preset)).unwrap();
+                    assert_eq!(header.preset, Some(preset.hash()));
+                    assert_eq!(buf, output_buf);
+
+                    let mut output_buf = vec![];
+                    let mut decoder =
+                        LZWDecoder::new(coded_buf.as_slice(), &mut output_buf, GammaDecoder).with_preset(preset.clone());
+                    decoder.decode_to_end().unwrap();
+                    assert_eq!(buf, output_buf);
+                }
+                println!("{:?} {:?}: without preset {} with preset {}", variant, policy, plain, primed);
+                assert!(primed * 3 < plain * 2);
+            }
+        }
+
+        // Longer inputs keep growing and resetting the primed dictionary
+        let buf = (0..2000).flat_map(record).collect::<Vec<_>>();
+        let config = DictConfig::new(DictPolicy::Reset, 1024);
+        let mut coded_buf = vec![];
+        LZWCoder::with_config(buf.as_slice(), &mut coded_buf, ArithmeticCoder::new(), config)
+            .with_preset(preset.clone())
+            .code_to_end().unwrap();
+        let mut output_buf = vec![];
+        decode_auto_with_preset(coded_buf.as_slice(), &mut output_buf, Some(&preset)).unwrap();
+        assert_eq!(buf, output_buf);
+
+        // The decoder has to know the same sample
+        let other = PresetDict::new(record(0));
+        let missing = decode_auto(coded_buf.as_slice(), &mut vec![]);
+        let wrong = decode_auto_with_preset(coded_buf.as_slice(), &mut vec![], Some(&other));
+        println!("{}", missing.clone().unwrap_err());
+        assert_eq!(
+            missing.unwrap_err(),
+            LzwError::PresetMismatch {
+                stream: Some(preset.hash()),
+                decoder: None
+            }
+        );
+        assert!(matches!(wrong, Err(LzwError::PresetMismatch { .. })));
+    }
+
+    #[test]
+    fn lzw_words() {
+        let text = include_bytes!("./../../testy/pride_and_prejudice.txt");
+        let binary = include_bytes!("./../../testy/test1.bin");
+        let config = DictConfig::default().with_variant(DictVariant::Words);
+
+        for buf in [text.as_slice(), binary.as_slice(), b"".as_slice()] {
+            let mut coded_buf = vec![];
+            LZWCoder::with_config(buf, &mut coded_buf, FibonacciCoder::new(), config).code_to_end().unwrap();
+            let mut bytes_buf = vec![];
+            LZWCoder::new(buf, &mut bytes_buf, FibonacciCoder::new()).code_to_end().unwrap();
+            println!(
+                "words {:.3} bits per byte, bytes {:.3} bits per byte",
+                coded_buf.len() as f64 * 8.0 / buf.len() as f64,
+                bytes_buf.len() as f64 * 8.0 / buf.len() as f64
+            );
+
+            let mut output_buf = vec![];
+            let (header, _) = decode_auto(coded_buf.as_slice(), &mut output_buf).unwrap();
+            assert_eq!(header.config, config);
+            assert_eq!(buf, output_buf);
+        }
+
+        // A small dictionary still takes new tokens
+        let config = DictConfig::new(DictPolicy::Freeze, 300).with_variant(DictVariant::Words);
+        let mut coded_buf = vec![];
+        LZWCoder::with_config(binary.as_slice(), &mut coded_buf, ArithmeticCoder::new(), config).code_to_end().unwrap();
+        let mut output_buf = vec![];
+        decode_auto(coded_buf.as_slice(), &mut output_buf).unwrap();
+        assert_eq!(binary.as_slice(), output_buf);
+    }
+
+    #[test]
+    fn preset_larger_than_dict() {
+        // The sample alone would fill the dictionary many times over
+        let text = include_bytes!("./../../testy/pan_tadeusz.txt");
+        let (sample, buf) = text.split_at(100_000);
+        let preset = PresetDict::new(sample.to_vec());
+
+        for variant in [DictVariant::Lzw, DictVariant::Lzmw, DictVariant::Lzap] {
+            for policy in [DictPolicy::Reset, DictPolicy::Lru] {
+                if variant != DictVariant::Lzw && policy == DictPolicy::Lru {
+                    continue;
+                }
+                let config = DictConfig::new(policy, 4096).with_variant(variant);
+
+                let mut plain_buf = vec![];
+                LZWCoder::with_config(buf, &mut plain_buf, GammaCoder, config).code_to_end().unwrap();
+                let mut coded_buf = vec![];
+                LZWCoder::with_config(buf, &mut coded_buf, GammaCoder, config)
+                    .with_preset(preset.clone())
+                    .code_to_end().unwrap();
+                println!("{:?} {:?}: without preset {} with preset {}", variant, policy, plain_buf.len(), coded_buf.len());
+                assert!(coded_buf.len() <= plain_buf.len());
+
+                let mut output_buf = vec![];
+                decode_auto_with_preset(coded_buf.as_slice(), &mut output_buf, Some(&preset)).unwrap();
+                assert!(buf == output_buf);
+            }
+        }
+    }
+
+    #[test]
+    fn lzw_lru_gamma() {
+        let buf = include_bytes!("./../../testy/test2.bin");
+        let config = DictConfig::new(DictPolicy::Lru, 1000);
+        let mut coded_buf = vec![];
+        let mut output_buf = vec![];
+
+        let mut coder = LZWCoder::with_config(buf.as_slice(), &mut coded_buf, GammaCoder, config);
+
+        coder.code_to_end().unwrap();
+
+        let mut decoder = LZWDecoder::new(coded_buf.as_slice(), &mut output_buf, GammaDecoder);
+
+        decoder.decode_to_end().unwrap();
+
+        assert_eq!(buf.as_slice(), output_buf);
+    }
+
+    #[test]
+    fn input_len_in_header() {
+        // Long repeats make LZMW phrases run over the coder's read chunks
+        let text = include_bytes!("./../../testy/pan_tadeusz.txt").repeat(4);
+        println!("input: {}", text.len());
+
+        for variant in [DictVariant::Lzw, DictVariant::Lzmw, DictVariant::Lzap] {
+            let config = DictConfig::new(DictPolicy::Freeze, 1 << 16).with_variant(variant);
+            let mut streamed_buf = vec![];
+            LZWCoder::with_config(text.as_slice(), &mut streamed_buf, GammaCoder, config).code_to_end().unwrap();
+            let mut known_buf = vec![];
+            LZWCoder::with_config(text.as_slice(), &mut known_buf, GammaCoder, config)
+                .with_input_len(text.len() as u64)
+                .code_to_end().unwrap();
+            println!("{:?}: streamed {} known {}", variant, streamed_buf.len(), known_buf.len());
+
+            for (coded_buf, len) in [(&streamed_buf, STREAMED_LEN), (&known_buf, text.len() as u64)] {
+                let mut output_buf = vec![];
+                let (header, output_len) = decode_auto(coded_buf.as_slice(), &mut output_buf).unwrap();
+                assert_eq!(header.len, len);
+                assert_eq!(output_len, text.len());
+                assert!(text == output_buf);
+            }
+        }
+    }
+
+    #[test]
+    fn wrong_input_len() {
+        for len in [5, 7] {
+            let result = LZWCoder::new(b"abcabc".as_slice(), vec![], GammaCoder).with_input_len(len).code_to_end();
+            assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        }
+    }
 }
@@ -0,0 +1,223 @@
+use std::fmt::Write;
+
+use crate::{dict_policy::DictConfig, header::Coding};
+
+// Input bytes between two samples of the dictionary size
+pub const DICT_SAMPLE_GAP: usize = 4096;
+
+// What the coder did, for comparing policies and coders on a dataset.
+// Phrase lengths are in bytes and only count codes that stand for a phrase.
+// Code lengths count all codes, clear and end codes too. The arithmetic
+// coder writes bytes in bursts, so its code lengths only add up on average.
+// Word streams only fill in the summary.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostics {
+    pub coding: Coding,
+    pub config: DictConfig,
+    pub phrase_lengths: Vec<usize>,
+    pub code_bits: Vec<usize>,
+    // (input position, dictionary size)
+    pub dict_sizes: Vec<(usize, usize)>,
+    pub resets: Vec<usize>,
+    pub dict_full_at: Option<usize>,
+    pub input_len: usize,
+    pub output_bits: usize,
+    next_sample: usize,
+}
+
+impl Diagnostics {
+    pub fn new(coding: Coding, config: DictConfig) -> Self {
+        Self {
+            coding,
+            config,
+            phrase_lengths: vec![],
+            code_bits: vec![],
+            dict_sizes: vec![],
+            resets: vec![],
+            dict_full_at: None,
+            input_len: 0,
+            output_bits: 0,
+            next_sample: 0,
+        }
+    }
+
+    pub fn add_phrase(&mut self, len: usize) {
+        add_to(&mut self.phrase_lengths, len);
+    }
+
+    pub fn add_code(&mut self, bits: usize) {
+        add_to(&mut self.code_bits, bits);
+    }
+
+    pub fn sample_dict(&mut self, input_pos: usize, dict_size: usize) {
+        if input_pos >= self.next_sample {
+            self.dict_sizes.push((input_pos, dict_size));
+            self.next_sample = input_pos + DICT_SAMPLE_GAP;
+        }
+    }
+
+    pub fn reset(&mut self, input_pos: usize) {
+        self.resets.push(input_pos);
+    }
+
+    pub fn dict_full(&mut self, input_pos: usize) {
+        self.dict_full_at.get_or_insert(input_pos);
+    }
+
+    pub fn finish(&mut self, input_len: usize, output_bits: usize, dict_size: usize) {
+        self.input_len = input_len;
+        self.output_bits = output_bits;
+        if self.dict_sizes.last().map(|&(pos, _)| pos) != Some(input_len) {
+            self.dict_sizes.push((input_len, dict_size));
+        }
+    }
+
+    pub fn phrase_count(&self) -> usize {
+        self.phrase_lengths.iter().sum()
+    }
+
+    pub fn mean_phrase_len(&self) -> f64 {
+        let total = self.phrase_lengths.iter().enumerate().map(|(len, count)| len * count).sum::<usize>();
+        total as f64 / self.phrase_count().max(1) as f64
+    }
+
+    // One row per value in long format: section,key,value
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("section,key,value\n");
+        let mut row = |section: &str, key: &dyn ToString, value: &dyn ToString| {
+            writeln!(csv, "{},{},{}", section, key.to_string(), value.to_string()).unwrap();
+        };
+
+        row("summary", &"coding", &format!("{:?}", self.coding));
+        row("summary", &"policy", &format!("{:?}", self.config.policy));
+        row("summary", &"variant", &format!("{:?}", self.config.variant));
+        row("summary", &"max_size", &self.config.max_size);
+        row("summary", &"input_len", &self.input_len);
+        row("summary", &"output_bits", &self.output_bits);
+        row("summary", &"dict_full_at", &self.dict_full_at.map_or(String::new(), |pos| pos.to_string()));
+        for (len, &count) in self.phrase_lengths.iter().enumerate().filter(|(_, &count)| count > 0) {
+            row("phrase_length", &len, &count);
+        }
+        for (bits, &count) in self.code_bits.iter().enumerate().filter(|(_, &count)| count > 0) {
+            row("code_bits", &bits, &count);
+        }
+        for &(pos, size) in &self.dict_sizes {
+            row("dict_size", &pos, &size);
+        }
+        for &pos in &self.resets {
+            row("reset", &pos, &"");
+        }
+
+        csv
+    }
+
+    pub fn to_json(&self) -> String {
+        let histogram = |counts: &[usize]| {
+            let entries = counts
+                .iter()
+                .enumerate()
+                .filter(|(_, &count)| count > 0)
+                .map(|(key, count)| format!("\"{}\": {}", key, count))
+                .collect::<Vec<_>>();
+            format!("{{{}}}", entries.join(", "))
+        };
+        let dict_sizes = self
+            .dict_sizes
+            .iter()
+            .map(|(pos, size)| format!("[{}, {}]", pos, size))
+            .collect::<Vec<_>>();
+        let resets = self.resets.iter().map(|pos| pos.to_string()).collect::<Vec<_>>();
+
+        let mut json = String::from("{\n");
+        writeln!(json, "  \"coding\": \"{:?}\",", self.coding).unwrap();
+        writeln!(json, "  \"policy\": \"{:?}\",", self.config.policy).unwrap();
+        writeln!(json, "  \"variant\": \"{:?}\",", self.config.variant).unwrap();
+        writeln!(json, "  \"max_size\": {},", self.config.max_size).unwrap();
+        writeln!(json, "  \"input_len\": {},", self.input_len).unwrap();
+        writeln!(json, "  \"output_bits\": {},", self.output_bits).unwrap();
+        let dict_full_at = self.dict_full_at.map_or("null".to_string(), |pos| pos.to_string());
+        writeln!(json, "  \"dict_full_at\": {},", dict_full_at).unwrap();
+        writeln!(json, "  \"phrase_lengths\": {},", histogram(&self.phrase_lengths)).unwrap();
+        writeln!(json, "  \"code_bits\": {},", histogram(&self.code_bits)).unwrap();
+        writeln!(json, "  \"dict_sizes\": [{}],", dict_sizes.join(", ")).unwrap();
+        writeln!(json, "  \"resets\": [{}]", resets.join(", ")).unwrap();
+        json.push_str("}\n");
+
+        json
+    }
+}
+
+fn add_to(histogram: &mut Vec<usize>, value: usize) {
+    if histogram.len() <= value {
+        histogram.resize(value + 1, 0);
+    }
+    histogram[value] += 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        coder::LZWCoder,
+        dict_policy::{DictPolicy, DictVariant},
+        header::HEADER_LEN,
+        universal_coder::{BinaryCoder, FibonacciCoder, GammaCoder},
+    };
+
+    use super::*;
+
+    #[test]
+    fn diagnostics_add_up() {
+        let text = include_bytes!("./../../testy/pan_tadeusz.txt");
+
+        for (policy, variant) in [
+            (DictPolicy::Freeze, DictVariant::Lzw),
+            (DictPolicy::Reset, DictVariant::Lzw),
+            (DictPolicy::Lru, DictVariant::Lzw),
+            (DictPolicy::AdaptiveReset, DictVariant::Lzap),
+        ] {
+            let config = DictConfig::new(policy, 4096).with_variant(variant);
+            let mut coded_buf = vec![];
+            let mut coder = LZWCoder::with_config(text.as_slice(), &mut coded_buf, FibonacciCoder::new(), config).with_diagnostics();
+            coder.code_to_end().unwrap();
+            let diagnostics = coder.diagnostics().unwrap().clone();
+            println!("{}", diagnostics.to_json());
+
+            // Every input byte is in one phrase, every output bit in one code
+            let bytes = diagnostics.phrase_lengths.iter().enumerate().map(|(len, count)| len * count).sum::<usize>();
+            assert_eq!(bytes, text.len());
+            let bits = diagnostics.code_bits.iter().enumerate().map(|(bits, count)| bits * count).sum::<usize>();
+            assert_eq!(bits.div_ceil(8) + HEADER_LEN, coded_buf.len());
+            assert_eq!(diagnostics.output_bits, coded_buf.len() * 8);
+
+            assert!(diagnostics.dict_full_at.is_some());
+            assert_eq!(diagnostics.dict_sizes.last().unwrap().0, text.len());
+            assert_eq!(diagnostics.resets.is_empty(), !policy.uses_clear_code());
+            assert!(diagnostics.dict_sizes.iter().all(|&(_, size)| size <= 4096));
+        }
+    }
+
+    #[test]
+    fn diagnostics_export() {
+        let text = b"TOBEORNOTTOBEORTOBEORNOT".repeat(10);
+        let mut coder = LZWCoder::new(text.as_slice(), vec![], BinaryCoder::new()).with_diagnostics();
+        coder.code_to_end().unwrap();
+        let diagnostics = coder.diagnostics().unwrap();
+        assert_eq!(diagnostics.dict_full_at, None);
+
+        let csv = diagnostics.to_csv();
+        println!("{}", csv);
+        assert!(csv.starts_with("section,key,value\nsummary,coding,Binary\n"));
+        assert!(csv.lines().all(|line| line.split(',').count() == 3));
+        assert!(csv.contains("\nphrase_length,1,"));
+        assert!(csv.contains(&format!("\ndict_size,{},", text.len())));
+
+        let json = diagnostics.to_json();
+        assert!(json.contains("\"dict_full_at\": null,"));
+        assert!(json.contains("\"resets\": []"));
+
+        // Without the option nothing is collected
+        let mut coder = LZWCoder::new(text.as_slice(), vec![], GammaCoder);
+        coder.code_to_end().unwrap();
+        assert!(coder.diagnostics().is_none());
+    }
+}
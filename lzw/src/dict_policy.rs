@@ -0,0 +1,186 @@
+use crate::MAX_DICT_SIZE;
+
+pub const RATIO_CHECK_GAP: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DictPolicy {
+    Freeze,
+    Reset,
+    AdaptiveReset,
+    Lru,
+}
+
+impl DictPolicy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "freeze" => Some(DictPolicy::Freeze),
+            "reset" => Some(DictPolicy::Reset),
+            "adaptive" => Some(DictPolicy::AdaptiveReset),
+            "lru" => Some(DictPolicy::Lru),
+            _ => None,
+        }
+    }
+
+    pub fn id(self) -> u8 {
+        match self {
+            DictPolicy::Freeze => 0,
+            DictPolicy::Reset => 1,
+            DictPolicy::AdaptiveReset => 2,
+            DictPolicy::Lru => 3,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(DictPolicy::Freeze),
+            1 => Some(DictPolicy::Reset),
+            2 => Some(DictPolicy::AdaptiveReset),
+            3 => Some(DictPolicy::Lru),
+            _ => None,
+        }
+    }
+
+    pub fn uses_clear_code(self) -> bool {
+        matches!(self, DictPolicy::Reset | DictPolicy::AdaptiveReset)
+    }
+}
+
+// How new entries are built from the phrases just coded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DictVariant {
+    // Previous phrase plus the next byte
+    Lzw,
+    // Previous phrase plus the current phrase
+    Lzmw,
+    // Previous phrase plus every prefix of the current phrase
+    Lzap,
+    // LZW over words and separators instead of bytes
+    Words,
+}
+
+impl DictVariant {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "lzw" => Some(DictVariant::Lzw),
+            "lzmw" => Some(DictVariant::Lzmw),
+            "lzap" => Some(DictVariant::Lzap),
+            "words" => Some(DictVariant::Words),
+            _ => None,
+        }
+    }
+
+    pub fn id(self) -> u8 {
+        match self {
+            DictVariant::Lzw => 0,
+            DictVariant::Lzmw => 1,
+            DictVariant::Lzap => 2,
+            DictVariant::Words => 3,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(DictVariant::Lzw),
+            1 => Some(DictVariant::Lzmw),
+            2 => Some(DictVariant::Lzap),
+            3 => Some(DictVariant::Words),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DictConfig {
+    pub policy: DictPolicy,
+    pub max_size: usize,
+    pub variant: DictVariant,
+}
+
+impl DictConfig {
+    pub fn new(policy: DictPolicy, max_size: usize) -> Self {
+        let config = Self {
+            policy,
+            max_size,
+            variant: DictVariant::Lzw,
+        };
+        assert!(
+            config.max_size > config.initial_size() && config.max_size <= MAX_DICT_SIZE,
+            "Dictionary size {} outside of the supported range",
+            max_size
+        );
+        config
+    }
+
+    // LRU replacement only works with entries that extend one other entry by a
+    // byte, and word dictionaries are never cleared
+    pub fn with_variant(self, variant: DictVariant) -> Self {
+        let config = Self { variant, ..self };
+        assert!(config.is_valid(), "{:?} cannot be used with the {:?} policy", variant, config.policy);
+        config
+    }
+
+    fn is_valid(&self) -> bool {
+        self.max_size > self.initial_size()
+            && self.max_size <= MAX_DICT_SIZE
+            && match self.variant {
+                DictVariant::Lzw => true,
+                DictVariant::Lzmw | DictVariant::Lzap => self.policy != DictPolicy::Lru,
+                DictVariant::Words => self.policy == DictPolicy::Freeze,
+            }
+    }
+
+    pub fn initial_size(&self) -> usize {
+        if self.policy.uses_clear_code() {
+            257
+        } else {
+            256
+        }
+    }
+
+    // The variant shares the policy byte, so plain LZW keeps the old layout
+    pub fn to_bytes(&self) -> [u8; 5] {
+        let mut bytes = [0; 5];
+        bytes[0] = self.variant.id() << 4 | self.policy.id();
+        bytes[1..].copy_from_slice(&(self.max_size as u32).to_le_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: [u8; 5]) -> Option<Self> {
+        let policy = DictPolicy::from_id(bytes[0] & 0x0F)?;
+        let variant = DictVariant::from_id(bytes[0] >> 4)?;
+        let max_size = u32::from_le_bytes(bytes[1..].try_into().unwrap()) as usize;
+
+        let config = Self {
+            policy,
+            max_size,
+            variant,
+        };
+        config.is_valid().then_some(config)
+    }
+}
+
+impl Default for DictConfig {
+    fn default() -> Self {
+        Self::new(DictPolicy::Freeze, MAX_DICT_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_round_trip() {
+        for policy in [DictPolicy::Freeze, DictPolicy::Reset, DictPolicy::AdaptiveReset, DictPolicy::Lru] {
+            let config = DictConfig::new(policy, 4096);
+            assert_eq!(DictConfig::from_bytes(config.to_bytes()), Some(config));
+        }
+
+        let config = DictConfig::new(DictPolicy::Reset, 4096).with_variant(DictVariant::Lzap);
+        assert_eq!(config.to_bytes()[0], 0x21);
+        assert_eq!(DictConfig::from_bytes(config.to_bytes()), Some(config));
+
+        // LRU with LZMW
+        assert_eq!(DictConfig::from_bytes([0x13, 0, 16, 0, 0]), None);
+    }
+}
@@ -0,0 +1,261 @@
+use std::fmt;
+
+use crate::prefix_tree::PrefixTree;
+
+pub const MIN_CODE_SIZE: u8 = 2;
+
+pub const MAX_CODE_SIZE: u32 = 12;
+
+const MAX_CODES: usize = 1 << MAX_CODE_SIZE;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GifLzwError {
+    InvalidCodeSize(u8),
+    MissingClear,
+    InvalidCode(usize),
+}
+
+impl fmt::Display for GifLzwError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GifLzwError::InvalidCodeSize(size) => write!(f, "invalid minimum code size {}", size),
+            GifLzwError::MissingClear => write!(f, "data does not start with a literal or clear code"),
+            GifLzwError::InvalidCode(code) => write!(f, "invalid code {}", code),
+        }
+    }
+}
+
+impl std::error::Error for GifLzwError {}
+
+fn check_code_size(min_code_size: u8) -> Result<(), GifLzwError> {
+    if (MIN_CODE_SIZE..=8).contains(&min_code_size) {
+        Ok(())
+    } else {
+        Err(GifLzwError::InvalidCodeSize(min_code_size))
+    }
+}
+
+struct LsbWriter {
+    output: Vec<u8>,
+    buffer: u32,
+    n: u32,
+}
+
+impl LsbWriter {
+    fn write_code(&mut self, code: usize, size: u32) {
+        self.buffer |= (code as u32) << self.n;
+        self.n += size;
+        while self.n >= 8 {
+            self.output.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.n -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.n > 0 {
+            self.output.push(self.buffer as u8);
+        }
+        self.output
+    }
+}
+
+pub struct GifLzwCoder {
+    min_code_size: u8,
+    clear_code: usize,
+    code_size: u32,
+    tree: PrefixTree,
+    writer: LsbWriter,
+}
+
+impl GifLzwCoder {
+    pub fn new(min_code_size: u8) -> Self {
+        check_code_size(min_code_size).unwrap();
+        let clear_code = 1 << min_code_size;
+
+        Self {
+            min_code_size,
+            clear_code,
+            code_size: min_code_size as u32 + 1,
+            tree: PrefixTree::with_size(clear_code + 2, MAX_CODES),
+            writer: LsbWriter {
+                output: vec![],
+                buffer: 0,
+                n: 0,
+            },
+        }
+    }
+
+    pub fn code_all(mut self, indices: &[u8]) -> Vec<u8> {
+        self.writer.write_code(self.clear_code, self.code_size);
+
+        let mut iter = indices.iter().copied();
+        if let Some(first) = iter.next() {
+            let mut current_node = self.literal(first);
+
+            for index in iter {
+                let index = self.literal(index) as u8;
+                if let Some(next_node) = self.tree.travel(current_node, index) {
+                    current_node = next_node;
+                    continue;
+                }
+
+                self.emit(current_node);
+                self.tree.append(current_node, index);
+                if self.tree.is_full() {
+                    self.writer.write_code(self.clear_code, self.code_size);
+                    self.reset();
+                }
+                current_node = index as usize;
+            }
+
+            self.emit(current_node);
+        }
+
+        self.writer.write_code(self.clear_code + 1, self.code_size);
+        self.writer.finish()
+    }
+
+    fn literal(&self, index: u8) -> usize {
+        assert!(
+            (index as usize) < self.clear_code,
+            "Index {} does not fit in {} bits",
+            index,
+            self.min_code_size
+        );
+        index as usize
+    }
+
+    // The decoder adds its entry one code later, so the width is checked
+    // against the table before this step's entry is appended.
+    fn emit(&mut self, code: usize) {
+        self.writer.write_code(code, self.code_size);
+        if self.tree.len() >= 1 << self.code_size && self.code_size < MAX_CODE_SIZE {
+            self.code_size += 1;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.code_size = self.min_code_size as u32 + 1;
+        self.tree = PrefixTree::with_size(self.clear_code + 2, MAX_CODES);
+    }
+}
+
+pub fn encode(indices: &[u8], min_code_size: u8) -> Vec<u8> {
+    GifLzwCoder::new(min_code_size).code_all(indices)
+}
+
+pub fn decode(data: &[u8], min_code_size: u8) -> Result<Vec<u8>, GifLzwError> {
+    check_code_size(min_code_size)?;
+
+    let clear_code = 1usize << min_code_size;
+    let end_code = clear_code + 1;
+
+    let mut prefix: Vec<usize> = (0..clear_code + 2).collect();
+    let mut suffix: Vec<u8> = (0..clear_code + 2).map(|i| i as u8).collect();
+    let mut lengths: Vec<usize> = vec![1; clear_code + 2];
+    let mut code_size = min_code_size as u32 + 1;
+    let mut last_code: Option<usize> = None;
+
+    let mut output = vec![];
+    let mut buffer = 0u32;
+    let mut n = 0;
+    let mut bytes = data.iter();
+
+    loop {
+        while n < code_size {
+            let Some(&byte) = bytes.next() else {
+                return Ok(output);
+            };
+            buffer |= (byte as u32) << n;
+            n += 8;
+        }
+        let code = (buffer & ((1 << code_size) - 1)) as usize;
+        buffer >>= code_size;
+        n -= code_size;
+
+        if code == clear_code {
+            prefix.truncate(clear_code + 2);
+            suffix.truncate(clear_code + 2);
+            lengths.truncate(clear_code + 2);
+            code_size = min_code_size as u32 + 1;
+            last_code = None;
+            continue;
+        }
+        if code == end_code {
+            return Ok(output);
+        }
+
+        let Some(last) = last_code else {
+            if code >= clear_code {
+                return Err(GifLzwError::MissingClear);
+            }
+            output.push(code as u8);
+            last_code = Some(code);
+            continue;
+        };
+
+        let free_code = prefix.len();
+        if code > free_code {
+            return Err(GifLzwError::InvalidCode(code));
+        }
+
+        let start = output.len();
+        let entry = if code == free_code { last } else { code };
+        output.resize(start + lengths[entry], 0);
+        let mut current = entry;
+        for pos in (start..start + lengths[entry]).rev() {
+            output[pos] = suffix[current];
+            current = prefix[current];
+        }
+        let first_byte = output[start];
+        if code == free_code {
+            output.push(first_byte);
+        }
+
+        if free_code < MAX_CODES {
+            prefix.push(last);
+            suffix.push(first_byte);
+            lengths.push(lengths[last] + 1);
+            if prefix.len() >= 1 << code_size && code_size < MAX_CODE_SIZE {
+                code_size += 1;
+            }
+        }
+        last_code = Some(code);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let buf = include_bytes!("./../../testy/pan_tadeusz.txt");
+
+        for min_code_size in [2, 5, 8] {
+            let mask = (1u16 << min_code_size) - 1;
+            let indices = buf.iter().map(|&b| (b as u16 & mask) as u8).collect::<Vec<_>>();
+
+            let coded = encode(&indices, min_code_size);
+            println!("min code size {} coded len: {}", min_code_size, coded.len());
+
+            assert_eq!(decode(&coded, min_code_size).unwrap(), indices);
+        }
+    }
+
+    #[test]
+    fn fixture() {
+        // 4x1 image 0, 1, 1, 1 with the smallest code size
+        let coded = encode(&[0, 1, 1, 1], 2);
+        assert_eq!(coded, [0x44, 0x5E]);
+        assert_eq!(decode(&coded, 2).unwrap(), [0, 1, 1, 1]);
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(decode(&[0x00], 9), Err(GifLzwError::InvalidCodeSize(9)));
+        assert_eq!(decode(&[0x07], 2), Err(GifLzwError::MissingClear));
+        assert_eq!(decode(&[0xC4, 0x01], 2), Err(GifLzwError::InvalidCode(7)));
+    }
+}
@@ -0,0 +1,201 @@
+use std::io::{self, Read, Write};
+
+use crate::dict_policy::{DictConfig, DictVariant};
+
+pub const LZW_MAGIC: &[u8; 4] = b"LZW1";
+
+// Length without the optional preset hash
+pub const HEADER_LEN: usize = 26;
+
+// Length of streams coded before their end was known, which finish with an end code
+pub const STREAMED_LEN: u64 = u64::MAX;
+
+pub const PRESET_HASH_LEN: usize = 4;
+
+// Set in the dictionary config byte when a preset hash follows the header
+const PRESET_FLAG: u8 = 0x80;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Coding {
+    Gamma,
+    Delta,
+    Omega,
+    Fib,
+    Binary,
+    Golomb(u64),
+    Rice(u32),
+    ExpGolomb(u32),
+    AdaptiveRice,
+    Arithmetic,
+}
+
+impl Coding {
+    // Names as accepted by the binaries, parameters after a colon (golomb:10)
+    pub fn from_name(name: &str) -> Option<Self> {
+        let (name, parameter) = name.split_once(':').unwrap_or((name, ""));
+
+        match name {
+            "gamma" => Some(Coding::Gamma),
+            "delta" => Some(Coding::Delta),
+            "omega" => Some(Coding::Omega),
+            "fib" => Some(Coding::Fib),
+            "binary" => Some(Coding::Binary),
+            "golomb" => parameter.parse().ok().filter(|&m| m > 0).map(Coding::Golomb),
+            "rice" => parameter.parse().ok().map(Coding::Rice),
+            "expgolomb" => parameter.parse().ok().map(Coding::ExpGolomb),
+            "adaptive-rice" => Some(Coding::AdaptiveRice),
+            "arithmetic" => Some(Coding::Arithmetic),
+            _ => None,
+        }
+    }
+
+    pub fn id(self) -> u8 {
+        match self {
+            Coding::Gamma => 0,
+            Coding::Delta => 1,
+            Coding::Omega => 2,
+            Coding::Fib => 3,
+            Coding::Binary => 4,
+            Coding::Golomb(_) => 5,
+            Coding::Rice(_) => 6,
+            Coding::ExpGolomb(_) => 7,
+            Coding::AdaptiveRice => 8,
+            Coding::Arithmetic => 9,
+        }
+    }
+
+    pub fn parameter(self) -> u64 {
+        match self {
+            Coding::Golomb(m) => m,
+            Coding::Rice(k) | Coding::ExpGolomb(k) => k as u64,
+            _ => 0,
+        }
+    }
+
+    pub fn from_id(id: u8, parameter: u64) -> Option<Self> {
+        match id {
+            0 => Some(Coding::Gamma),
+            1 => Some(Coding::Delta),
+            2 => Some(Coding::Omega),
+            3 => Some(Coding::Fib),
+            4 => Some(Coding::Binary),
+            5 if parameter > 0 => Some(Coding::Golomb(parameter)),
+            6 if parameter < u128::BITS as u64 => Some(Coding::Rice(parameter as u32)),
+            7 if parameter < u128::BITS as u64 => Some(Coding::ExpGolomb(parameter as u32)),
+            8 => Some(Coding::AdaptiveRice),
+            9 => Some(Coding::Arithmetic),
+            _ => None,
+        }
+    }
+}
+
+// Magic, coding id and parameter, dictionary config and the original length,
+// so a stream can be decoded without knowing how it was made. Streams coded
+// with a preset dictionary add the hash of its sample.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamHeader {
+    pub coding: Coding,
+    pub config: DictConfig,
+    pub len: u64,
+    pub preset: Option<u32>,
+}
+
+impl StreamHeader {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut header = vec![0; HEADER_LEN];
+        header[..4].copy_from_slice(LZW_MAGIC);
+        header[4] = self.coding.id();
+        header[5..13].copy_from_slice(&self.coding.parameter().to_le_bytes());
+        header[13..18].copy_from_slice(&self.config.to_bytes());
+        header[18..].copy_from_slice(&self.len.to_le_bytes());
+
+        if let Some(hash) = self.preset {
+            header[13] |= PRESET_FLAG;
+            header.extend_from_slice(&hash.to_le_bytes());
+        }
+        header
+    }
+
+    // How many bytes follow the fixed part of the header
+    pub fn extra_len(header: &[u8; HEADER_LEN]) -> usize {
+        if header[13] & PRESET_FLAG != 0 {
+            PRESET_HASH_LEN
+        } else {
+            0
+        }
+    }
+
+    pub fn from_bytes(header: &[u8]) -> Option<Self> {
+        let fixed: &[u8; HEADER_LEN] = header.get(..HEADER_LEN)?.try_into().unwrap();
+        if &fixed[..4] != LZW_MAGIC || header.len() != HEADER_LEN + Self::extra_len(fixed) {
+            return None;
+        }
+
+        let coding = Coding::from_id(fixed[4], u64::from_le_bytes(fixed[5..13].try_into().unwrap()))?;
+        let mut config_bytes: [u8; 5] = fixed[13..18].try_into().unwrap();
+        config_bytes[0] &= !PRESET_FLAG;
+        let config = DictConfig::from_bytes(config_bytes)?;
+        let len = u64::from_le_bytes(fixed[18..].try_into().unwrap());
+        let preset = header
+            .get(HEADER_LEN..)
+            .filter(|hash| !hash.is_empty())
+            .map(|hash| u32::from_le_bytes(hash.try_into().unwrap()));
+        if preset.is_some() && config.variant == DictVariant::Words {
+            return None;
+        }
+
+        Some(Self {
+            coding,
+            config,
+            len,
+            preset,
+        })
+    }
+
+    pub fn write<O: Write>(&self, output_stream: &mut O) -> io::Result<()> {
+        output_stream.write_all(&self.to_bytes())
+    }
+
+    pub fn read<I: Read>(input_stream: &mut I) -> Option<Self> {
+        let mut fixed = [0; HEADER_LEN];
+        input_stream.read_exact(&mut fixed).ok()?;
+
+        let mut header = fixed.to_vec();
+        header.resize(HEADER_LEN + Self::extra_len(&fixed), 0);
+        input_stream.read_exact(&mut header[HEADER_LEN..]).ok()?;
+        Self::from_bytes(&header)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dict_policy::DictPolicy;
+
+    use super::*;
+
+    #[test]
+    fn header_round_trip() {
+        for coding in ["gamma", "fib", "golomb:10", "rice:4", "expgolomb:2", "adaptive-rice"] {
+            let header = StreamHeader {
+                coding: Coding::from_name(coding).unwrap(),
+                config: DictConfig::new(DictPolicy::Lru, 4096),
+                len: 123_456_789_012,
+                preset: None,
+            };
+            assert_eq!(StreamHeader::from_bytes(&header.to_bytes()), Some(header));
+
+            let header = StreamHeader {
+                preset: Some(0xDEAD_BEEF),
+                ..header
+            };
+            let bytes = header.to_bytes();
+            assert_eq!(bytes.len(), HEADER_LEN + PRESET_HASH_LEN);
+            assert_eq!(StreamHeader::read(&mut bytes.as_slice()), Some(header));
+            assert_eq!(StreamHeader::from_bytes(&bytes[..HEADER_LEN]), None);
+        }
+
+        assert_eq!(Coding::from_name("golomb:0"), None);
+        assert_eq!(Coding::from_name("rice"), None);
+        assert_eq!(StreamHeader::from_bytes(&[0; HEADER_LEN]), None);
+    }
+}
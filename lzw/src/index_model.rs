@@ -0,0 +1,179 @@
+use arithmetic::model::{PRange, SymbolModel};
+
+// Larger steps adapt faster but make the many indices used once more expensive
+const INCREMENT: u32 = 1;
+
+// Has to stay well below the 2^30 the arithmetic coder can resolve and above MAX_DICT_SIZE
+const MAX_TOTAL: u32 = 1 << 24;
+
+// Adaptive frequencies of dictionary indices. The alphabet grows with the
+// dictionary and every new index starts with a count of one. Counts live in a
+// Fenwick tree, so coding a symbol is logarithmic in the dictionary size.
+#[derive(Debug, Clone)]
+pub struct IndexModel {
+    counts: Vec<u32>,
+    tree: Vec<u32>,
+    size: usize,
+    total: u32,
+}
+
+impl IndexModel {
+    pub fn new() -> Self {
+        Self {
+            counts: vec![],
+            tree: vec![0],
+            size: 0,
+            total: 0,
+        }
+    }
+
+    pub fn alphabet_size(&self) -> usize {
+        self.size
+    }
+
+    // Symbols at or above the size are kept, but cannot be coded until it grows again
+    pub fn set_alphabet_size(&mut self, size: usize) {
+        while self.counts.len() < size {
+            self.push(1);
+        }
+        if size != self.size {
+            self.size = size;
+            self.total = self.prefix(size);
+        }
+    }
+
+    fn push(&mut self, count: u32) {
+        let i = self.counts.len() + 1;
+        let lowest_bit = i & i.wrapping_neg();
+        let node = count + self.prefix(i - 1) - self.prefix(i - lowest_bit);
+
+        self.counts.push(count);
+        self.tree.push(node);
+    }
+
+    // Sum of the counts of symbols below end
+    fn prefix(&self, mut end: usize) -> u32 {
+        let mut sum = 0;
+        while end > 0 {
+            sum += self.tree[end];
+            end &= end - 1;
+        }
+        sum
+    }
+
+    fn add(&mut self, symbol: usize, count: u32) {
+        self.counts[symbol] += count;
+        let mut i = symbol + 1;
+        while i < self.tree.len() {
+            self.tree[i] += count;
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    fn rescale(&mut self) {
+        let counts = std::mem::take(&mut self.counts);
+        self.tree.truncate(1);
+        for count in counts {
+            self.push(count.div_ceil(2));
+        }
+        self.total = self.prefix(self.size);
+    }
+}
+
+impl SymbolModel for IndexModel {
+    fn total(&self) -> u32 {
+        self.total
+    }
+
+    fn get_p_range(&self, symbol: u32) -> PRange {
+        assert!((symbol as usize) < self.size, "Index {} outside of the dictionary", symbol);
+
+        let lower = self.prefix(symbol as usize);
+        PRange {
+            upper: lower + self.counts[symbol as usize],
+            lower,
+            denom: self.total,
+        }
+    }
+
+    fn get_symbol(&self, value: u32) -> Option<u32> {
+        if value >= self.total {
+            return None;
+        }
+
+        // Walks down the tree to the last position whose prefix is at most value
+        let mut pos = 0;
+        let mut rest = value;
+        let mut step = (self.tree.len() - 1).checked_ilog2().map_or(0, |bits| 1 << bits);
+        while step > 0 {
+            if pos + step < self.tree.len() && self.tree[pos + step] <= rest {
+                pos += step;
+                rest -= self.tree[pos];
+            }
+            step >>= 1;
+        }
+
+        Some(pos as u32)
+    }
+
+    fn save_symbol(&mut self, symbol: u32) {
+        self.add(symbol as usize, INCREMENT);
+        self.total += INCREMENT;
+        if self.total > MAX_TOTAL {
+            self.rescale();
+        }
+    }
+}
+
+impl Default for IndexModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges_and_lookup() {
+        let mut model = IndexModel::new();
+        model.set_alphabet_size(300);
+        for symbol in [5, 5, 7, 299, 0] {
+            model.save_symbol(symbol);
+        }
+        model.set_alphabet_size(1000);
+        model.save_symbol(999);
+
+        let mut lower = 0;
+        for symbol in 0..1000 {
+            let p_range = model.get_p_range(symbol);
+            assert_eq!(p_range.lower, lower);
+            assert!(p_range.upper > p_range.lower);
+            assert_eq!(model.get_symbol(p_range.lower), Some(symbol));
+            assert_eq!(model.get_symbol(p_range.upper - 1), Some(symbol));
+            lower = p_range.upper;
+        }
+        assert_eq!(lower, model.total());
+        assert_eq!(model.get_symbol(model.total()), None);
+
+        // A smaller alphabet only covers the first symbols
+        model.set_alphabet_size(256);
+        assert_eq!(model.total(), model.get_p_range(255).upper);
+    }
+
+    #[test]
+    fn rescale_keeps_symbols() {
+        let mut model = IndexModel::new();
+        model.set_alphabet_size(100);
+        for i in 0..20_000_000u32 {
+            model.save_symbol(i % 3);
+        }
+
+        assert!(model.total() <= MAX_TOTAL);
+        for symbol in 0..100 {
+            let p_range = model.get_p_range(symbol);
+            assert!(p_range.upper > p_range.lower);
+        }
+    }
+}
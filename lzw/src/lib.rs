@@ -3,5 +3,19 @@ pub mod coder;
 pub mod decoder;
 pub mod universal_coder;
 pub mod universal_decoder;
+pub mod dict_policy;
+pub mod header;
+pub mod lru;
+pub mod index_model;
+pub mod phrase_dict;
+pub mod words;
+pub mod preset;
+pub mod stream;
+pub mod diagnostics;
+pub mod chunked;
+pub mod unix_compress;
+pub mod gif_lzw;
 
-pub const MAX_DICT_SIZE : usize = 1_000_000;
\ No newline at end of file
+pub const MAX_DICT_SIZE : usize = 1_000_000;
+
+pub const CLEAR_CODE: usize = 256;
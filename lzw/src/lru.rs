@@ -0,0 +1,97 @@
+use std::collections::BTreeSet;
+
+pub struct LruTracker {
+    first_evictable: usize,
+    last_used: Vec<usize>,
+    parent: Vec<usize>,
+    children: Vec<usize>,
+    leaves: BTreeSet<(usize, usize)>,
+}
+
+impl LruTracker {
+    pub fn new(first_evictable: usize) -> Self {
+        Self {
+            first_evictable,
+            last_used: vec![0; first_evictable],
+            parent: vec![0; first_evictable],
+            children: vec![0; first_evictable],
+            leaves: BTreeSet::new(),
+        }
+    }
+
+    pub fn touch(&mut self, id: usize, time: usize) {
+        if id < self.first_evictable || id >= self.last_used.len() {
+            return;
+        }
+
+        if self.children[id] == 0 {
+            self.leaves.remove(&(self.last_used[id], id));
+            self.leaves.insert((time, id));
+        }
+        self.last_used[id] = time;
+    }
+
+    pub fn victim(&self, exclude: usize) -> Option<usize> {
+        self.leaves
+            .iter()
+            .map(|&(_, id)| id)
+            .find(|&id| id != exclude)
+    }
+
+    pub fn add(&mut self, id: usize, parent: usize, time: usize) {
+        if id == self.last_used.len() {
+            self.last_used.push(time);
+            self.parent.push(parent);
+            self.children.push(0);
+        } else {
+            self.evict(id);
+            self.last_used[id] = time;
+            self.parent[id] = parent;
+        }
+
+        self.leaves.insert((time, id));
+
+        if parent >= self.first_evictable {
+            if self.children[parent] == 0 {
+                self.leaves.remove(&(self.last_used[parent], parent));
+            }
+            self.children[parent] += 1;
+        }
+    }
+
+    fn evict(&mut self, id: usize) {
+        assert_eq!(self.children[id], 0, "Only leaves can be evicted");
+        self.leaves.remove(&(self.last_used[id], id));
+
+        let parent = self.parent[id];
+        if parent >= self.first_evictable {
+            self.children[parent] -= 1;
+            if self.children[parent] == 0 {
+                self.leaves.insert((self.last_used[parent], parent));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lru_leaves() {
+        let mut lru = LruTracker::new(256);
+
+        lru.add(256, 97, 0);
+        lru.add(257, 256, 1);
+        lru.add(258, 98, 2);
+
+        assert_eq!(lru.victim(usize::MAX), Some(257));
+
+        lru.touch(257, 3);
+        assert_eq!(lru.victim(usize::MAX), Some(258));
+        assert_eq!(lru.victim(258), Some(257));
+
+        lru.add(257, 99, 4);
+        assert_eq!(lru.victim(usize::MAX), Some(256));
+    }
+}
@@ -0,0 +1,168 @@
+use crate::{
+    dict_policy::{DictConfig, DictVariant},
+    prefix_tree::{PrefixTree, MAX_NODES},
+    preset::prime_limit,
+};
+
+const NO_CODE: u32 = u32::MAX;
+
+// LZMW entries are not prefix closed, so the tree keeps extra nodes for the
+// missing prefixes. This bounds them relative to the number of codes.
+const LZMW_NODES_PER_CODE: usize = 16;
+
+// Dictionary for LZMW and LZAP shared by the coder and the decoder. Both build
+// the same tree in the same order, so node ids and codes agree on both sides.
+pub struct PhraseDict {
+    variant: DictVariant,
+    initial_size: usize,
+    max_size: usize,
+    tree: PrefixTree,
+    code_of_node: Vec<u32>,
+    node_of_code: Vec<u32>,
+}
+
+impl PhraseDict {
+    pub fn new(config: DictConfig) -> Self {
+        let max_nodes = match config.variant {
+            DictVariant::Lzmw => (config.max_size * LZMW_NODES_PER_CODE).min(MAX_NODES),
+            _ => config.max_size,
+        };
+        let initial_size = config.initial_size();
+
+        Self {
+            variant: config.variant,
+            initial_size,
+            max_size: config.max_size,
+            tree: PrefixTree::with_size(initial_size, max_nodes),
+            code_of_node: (0..initial_size as u32).collect(),
+            node_of_code: (0..initial_size as u32).collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.node_of_code.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.node_of_code.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() >= self.max_size || self.tree.is_full()
+    }
+
+    // Presets stop priming here, so the data still gets entries of its own
+    pub fn is_primed(&self) -> bool {
+        self.len() >= prime_limit(self.initial_size, self.max_size)
+            || self.tree.len() >= prime_limit(self.initial_size, self.tree.max_size())
+    }
+
+    pub fn node(&self, code: usize) -> usize {
+        self.node_of_code[code] as usize
+    }
+
+    pub fn code(&self, node: usize) -> Option<usize> {
+        let code = self.code_of_node[node];
+        (code != NO_CODE).then_some(code as usize)
+    }
+
+    pub fn phrase(&self, code: usize, buf: &mut Vec<u8>) {
+        self.tree.phrase(self.node(code), buf);
+    }
+
+    // Longest entry at the start of data, as (code, length)
+    pub fn longest_match(&self, data: &[u8]) -> (usize, usize) {
+        let mut node = data[0] as usize;
+        let mut best = (node, 1);
+
+        for (i, &byte) in data.iter().enumerate().skip(1) {
+            let Some(next) = self.tree.travel(node, byte) else {
+                break;
+            };
+            node = next;
+            if let Some(code) = self.code(node) {
+                best = (code, i + 1);
+            }
+        }
+
+        best
+    }
+
+    // Adds entries built from the previous phrase and the one just coded
+    pub fn extend(&mut self, previous: usize, phrase: &[u8]) {
+        let mut node = self.node(previous);
+
+        for (i, &byte) in phrase.iter().enumerate() {
+            node = match self.tree.travel(node, byte) {
+                Some(next) => next,
+                None if self.tree.is_full() => return,
+                None => {
+                    self.code_of_node.push(NO_CODE);
+                    self.tree.append(node, byte)
+                }
+            };
+
+            let is_entry = self.variant == DictVariant::Lzap || i + 1 == phrase.len();
+            if is_entry && self.code_of_node[node] == NO_CODE && self.len() < self.max_size {
+                self.code_of_node[node] = self.len() as u32;
+                self.node_of_code.push(node as u32);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dict_policy::DictPolicy;
+
+    use super::*;
+
+    fn parse(variant: DictVariant, data: &[u8]) -> Vec<usize> {
+        let mut dict = PhraseDict::new(DictConfig::new(DictPolicy::Freeze, 4096).with_variant(variant));
+        let mut codes = vec![];
+        let mut start = 0;
+        let mut previous = None;
+
+        while start < data.len() {
+            let (code, len) = dict.longest_match(&data[start..]);
+            if let Some(previous) = previous {
+                dict.extend(previous, &data[start..start + len]);
+            }
+            codes.push(code);
+            previous = Some(code);
+            start += len;
+        }
+
+        codes
+    }
+
+    #[test]
+    fn variants_grow_differently() {
+        let data = b"abcabcabcabcabcabcabcabc";
+
+        // LZMW adds "ab", "bc", "cab", "abcab", "cababc", ... doubling on repeats
+        let lzmw = parse(DictVariant::Lzmw, data);
+        let lzap = parse(DictVariant::Lzap, data);
+        println!("lzmw: {:?}", lzmw);
+        println!("lzap: {:?}", lzap);
+        assert_eq!(lzmw[..3], [b'a' as usize, b'b' as usize, b'c' as usize]);
+        assert!(lzmw.len() < data.len() / 2);
+        assert!(lzap.len() < data.len() / 2);
+
+        let mut dict = PhraseDict::new(DictConfig::new(DictPolicy::Freeze, 4096).with_variant(DictVariant::Lzap));
+        dict.extend(b'x' as usize, b"yz");
+        let mut phrase = vec![];
+        dict.phrase(256, &mut phrase);
+        assert_eq!(phrase, b"xy");
+        dict.phrase(257, &mut phrase);
+        assert_eq!(phrase, b"xyz");
+
+        // LZMW only gets the whole concatenation
+        let mut dict = PhraseDict::new(DictConfig::new(DictPolicy::Freeze, 4096).with_variant(DictVariant::Lzmw));
+        dict.extend(b'x' as usize, b"yz");
+        assert_eq!(dict.len(), 257);
+        dict.phrase(256, &mut phrase);
+        assert_eq!(phrase, b"xyz");
+        assert_eq!(dict.longest_match(b"xyw"), (b'x' as usize, 1));
+    }
+}
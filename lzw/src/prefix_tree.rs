@@ -1,50 +1,212 @@
-use std::collections::HashMap;
-
 use crate::MAX_DICT_SIZE;
 
-#[derive(Debug, Clone)]
-pub struct PrefixNode {
-    children: HashMap<u8, usize>,
+const EMPTY: u32 = 0;
+
+const MIN_TABLE_BITS: u32 = 10;
+
+// Keys pack (node, byte) into 32 bits plus one, so node ids stay below
+// 2^24 - 1 and the last key does not wrap around to EMPTY
+pub const MAX_NODES: usize = (1 << 24) - 1;
+
+#[derive(Debug, Clone, Copy)]
+struct PrefixNode {
+    parent: u32,
+    byte: u8,
+    children: u32,
 }
 
-impl PrefixNode {
-    pub fn new() -> Self {
-        Self { children: HashMap::new() }
-    }
+// Edge from a node to its child for one byte. The key is (node << 8 | byte) + 1
+// so that zero marks an empty slot.
+#[derive(Debug, Clone, Copy)]
+struct Slot {
+    key: u32,
+    child: u32,
 }
 
+const EMPTY_SLOT: Slot = Slot { key: EMPTY, child: 0 };
+
+// All edges of the trie live in one open-addressing table with linear probing,
+// which keeps lookups to a hash and a few adjacent reads.
 #[derive(Debug)]
 pub struct PrefixTree {
-    buffer: Vec<PrefixNode>,
+    nodes: Vec<PrefixNode>,
+    slots: Vec<Slot>,
+    table_bits: u32,
+    edges: usize,
+    max_size: usize,
 }
 
 impl PrefixTree {
     pub fn new() -> Self {
-        let buf = vec![PrefixNode::new(); 256];
+        Self::with_size(256, MAX_DICT_SIZE)
+    }
+
+    pub fn with_size(initial_size: usize, max_size: usize) -> Self {
+        assert!(max_size <= MAX_NODES, "Prefix tree can hold at most {} nodes", MAX_NODES);
+
+        let root = PrefixNode {
+            parent: u32::MAX,
+            byte: 0,
+            children: 0,
+        };
+
+        Self {
+            nodes: vec![root; initial_size],
+            slots: vec![EMPTY_SLOT; 1 << MIN_TABLE_BITS],
+            table_bits: MIN_TABLE_BITS,
+            edges: 0,
+            max_size,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
 
-        Self { buffer: buf }
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.nodes.len() >= self.max_size
     }
 
     pub fn travel(&self, from: usize, char: u8) -> Option<usize> {
-        let node = &self.buffer[from];
-        let dest = node.children.get(&char).copied();
-        dest
+        let key = Self::key(from, char);
+        let mask = self.slots.len() - 1;
+        let mut i = self.home(key);
+
+        loop {
+            let slot = self.slots[i];
+            if slot.key == key {
+                return Some(slot.child as usize);
+            }
+            if slot.key == EMPTY {
+                return None;
+            }
+            i = (i + 1) & mask;
+        }
     }
 
     pub fn append(&mut self, from: usize, char: u8) -> usize {
-        if self.buffer.len() >= MAX_DICT_SIZE {
+        if self.is_full() {
             return from;
         }
 
-        let new_node = PrefixNode::new();
-        let id = self.buffer.len();
-        self.buffer.push(new_node);
-
-        let src_node = &mut self.buffer[from];
-        src_node.children.insert(char, id);
+        let id = self.nodes.len();
+        self.nodes.push(PrefixNode {
+            parent: from as u32,
+            byte: char,
+            children: 0,
+        });
+        self.link(from, char, id);
 
         id
     }
+
+    pub fn replace(&mut self, victim: usize, from: usize, char: u8) -> usize {
+        let old_node = self.nodes[victim];
+        assert_eq!(old_node.children, 0, "Only leaves can be replaced");
+        self.unlink(old_node.parent as usize, old_node.byte);
+
+        let node = &mut self.nodes[victim];
+        node.parent = from as u32;
+        node.byte = char;
+        self.link(from, char, victim);
+
+        victim
+    }
+
+    pub fn parent(&self, node: usize) -> Option<usize> {
+        let parent = self.nodes[node].parent;
+        (parent != u32::MAX).then_some(parent as usize)
+    }
+
+    pub fn byte(&self, node: usize) -> u8 {
+        self.nodes[node].byte
+    }
+
+    // Bytes on the path to node; nodes without a parent stand for their own byte
+    pub fn phrase(&self, mut node: usize, buf: &mut Vec<u8>) {
+        buf.clear();
+        while self.nodes[node].parent != u32::MAX {
+            buf.push(self.nodes[node].byte);
+            node = self.nodes[node].parent as usize;
+        }
+        buf.push(node as u8);
+        buf.reverse();
+    }
+
+    fn key(from: usize, char: u8) -> u32 {
+        ((from as u32) << 8 | char as u32) + 1
+    }
+
+    fn home(&self, key: u32) -> usize {
+        (key.wrapping_mul(0x9E37_79B1) >> (32 - self.table_bits)) as usize
+    }
+
+    fn link(&mut self, from: usize, char: u8, child: usize) {
+        // Keep the load factor at or below one half
+        if (self.edges + 1) * 2 > self.slots.len() {
+            self.grow();
+        }
+
+        self.insert(Slot {
+            key: Self::key(from, char),
+            child: child as u32,
+        });
+        self.nodes[from].children += 1;
+        self.edges += 1;
+    }
+
+    fn unlink(&mut self, from: usize, char: u8) {
+        let key = Self::key(from, char);
+        let mask = self.slots.len() - 1;
+        let mut hole = self.home(key);
+        while self.slots[hole].key != key {
+            hole = (hole + 1) & mask;
+        }
+        self.nodes[from].children -= 1;
+        self.edges -= 1;
+
+        // Backward shift deletion, so probe sequences never need tombstones
+        let mut i = hole;
+        loop {
+            i = (i + 1) & mask;
+            let slot = self.slots[i];
+            if slot.key == EMPTY {
+                break;
+            }
+
+            let home = self.home(slot.key);
+            if (i.wrapping_sub(home) & mask) >= (i.wrapping_sub(hole) & mask) {
+                self.slots[hole] = slot;
+                hole = i;
+            }
+        }
+        self.slots[hole] = EMPTY_SLOT;
+    }
+
+    fn insert(&mut self, slot: Slot) {
+        let mask = self.slots.len() - 1;
+        let mut i = self.home(slot.key);
+        while self.slots[i].key != EMPTY {
+            i = (i + 1) & mask;
+        }
+        self.slots[i] = slot;
+    }
+
+    fn grow(&mut self) {
+        self.table_bits += 1;
+        let old_slots = std::mem::replace(&mut self.slots, vec![EMPTY_SLOT; 1 << self.table_bits]);
+        for slot in old_slots.into_iter().filter(|slot| slot.key != EMPTY) {
+            self.insert(slot);
+        }
+    }
 }
 
 impl Default for PrefixTree {
@@ -53,8 +215,44 @@ impl Default for PrefixTree {
     }
 }
 
-impl Default for PrefixNode {
-    fn default() -> Self {
-        Self::new()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn travel_append_replace() {
+        let mut tree = PrefixTree::with_size(256, 10_000);
+
+        let mut node = b'a' as usize;
+        for i in 0..5000 {
+            node = tree.append(node, (i % 251) as u8);
+        }
+        assert_eq!(tree.len(), 5256);
+        assert_eq!(tree.travel(b'a' as usize, 0), Some(256));
+        assert_eq!(tree.travel(256, 1), Some(257));
+        assert_eq!(tree.travel(256, 2), None);
+
+        let leaf = tree.len() - 1;
+        let parent = leaf - 1;
+        assert_eq!(tree.replace(leaf, b'b' as usize, 7), leaf);
+        assert_eq!(tree.travel(parent, (4999 % 251) as u8), None);
+        assert_eq!(tree.travel(b'b' as usize, 7), Some(leaf));
+
+        let mut phrase = vec![];
+        tree.phrase(258, &mut phrase);
+        assert_eq!(phrase, [b'a', 0, 1, 2]);
+
+        // Every edge stays reachable after a removal shifts its probe chain
+        for i in 256..leaf - 1 {
+            let node = tree.nodes[i + 1];
+            assert_eq!(tree.travel(node.parent as usize, node.byte), Some(i + 1));
+        }
+    }
+
+    #[test]
+    fn last_node_key() {
+        let last = MAX_NODES - 1;
+        assert_ne!(PrefixTree::key(last, u8::MAX), EMPTY);
+        assert!(PrefixTree::key(last, u8::MAX) > PrefixTree::key(last - 1, u8::MAX));
     }
-}
\ No newline at end of file
+}
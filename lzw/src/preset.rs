@@ -0,0 +1,78 @@
+use std::{fs, io, path::Path};
+
+use arithmetic::checksum::Crc32;
+
+use crate::{phrase_dict::PhraseDict, prefix_tree::PrefixTree};
+
+// Priming stops halfway to a full dictionary. A full primed dictionary would
+// be reset after every phrase, or pinned whole by LRU.
+pub fn prime_limit(initial_size: usize, max_size: usize) -> usize {
+    initial_size + max_size.saturating_sub(initial_size) / 2
+}
+
+// Sample data whose phrases prefill the dictionary of the coder and the
+// decoder. Streams name their preset by the CRC-32 of the sample.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PresetDict {
+    sample: Vec<u8>,
+    hash: u32,
+}
+
+impl PresetDict {
+    pub fn new(sample: Vec<u8>) -> Self {
+        let mut checksum = Crc32::new();
+        checksum.update(&sample);
+
+        Self {
+            hash: checksum.finish(),
+            sample,
+        }
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(fs::read(path)?))
+    }
+
+    pub fn hash(&self) -> u32 {
+        self.hash
+    }
+
+    pub fn sample(&self) -> &[u8] {
+        &self.sample
+    }
+
+    // Adds the entries LZW creates while coding the sample
+    pub fn prime_tree(&self, tree: &mut PrefixTree) {
+        let Some((&first, rest)) = self.sample.split_first() else {
+            return;
+        };
+
+        let limit = prime_limit(tree.len(), tree.max_size());
+        let mut node = first as usize;
+        for &byte in rest {
+            match tree.travel(node, byte) {
+                Some(next) => node = next,
+                None if tree.len() >= limit => return,
+                None => {
+                    tree.append(node, byte);
+                    node = byte as usize;
+                }
+            }
+        }
+    }
+
+    // Same for LZMW and LZAP, which share their parse between both sides
+    pub fn prime_phrases(&self, phrases: &mut PhraseDict) {
+        let mut previous = None;
+        let mut start = 0;
+
+        while start < self.sample.len() && !phrases.is_primed() {
+            let (code, len) = phrases.longest_match(&self.sample[start..]);
+            if let Some(previous) = previous {
+                phrases.extend(previous, &self.sample[start..start + len]);
+            }
+            previous = Some(code);
+            start += len;
+        }
+    }
+}
@@ -0,0 +1,301 @@
+use std::io::{self, Empty, Read, Write};
+
+use crate::{
+    coder::LZWCoder,
+    decoder::LZWDecoder,
+    dict_policy::{DictConfig, DictVariant},
+    header::StreamHeader,
+    preset::PresetDict,
+    universal_coder::UniversalCoder,
+    universal_decoder::UniversalDecoder,
+};
+
+// The reader decodes codes until it has at least this many bytes buffered
+const READ_CHUNK: usize = 1 << 14;
+
+// LZW coder for data that arrives in pieces. The length is not known up front,
+// so the stream ends with an end code written by finish. Codes are passed on
+// to the inner writer after every write, flush only cannot push out the bits
+// of a code that does not fill its last byte.
+pub struct LzwWriter<W, C> {
+    coder: LZWCoder<Empty, Vec<u8>, C>,
+    inner: W,
+    started: bool,
+}
+
+impl<W: Write, C: UniversalCoder> LzwWriter<W, C> {
+    pub fn new(inner: W, universal_coder: C) -> Self {
+        Self::with_config(inner, universal_coder, DictConfig::default())
+    }
+
+    // LZMW, LZAP and words need to see more than the current phrase
+    pub fn with_config(inner: W, universal_coder: C, config: DictConfig) -> Self {
+        assert!(config.variant == DictVariant::Lzw, "{:?} cannot be coded as a stream", config.variant);
+
+        Self {
+            coder: LZWCoder::with_config(io::empty(), vec![], universal_coder, config),
+            inner,
+            started: false,
+        }
+    }
+
+    pub fn with_preset(mut self, preset: PresetDict) -> Self {
+        assert!(!self.started, "The preset has to be set before writing");
+        self.coder = self.coder.with_preset(preset);
+        self
+    }
+
+    // Input bytes taken and bytes written so far
+    pub fn get_stat(&self) -> (usize, usize) {
+        self.coder.get_stat()
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.start();
+        self.coder.finish();
+        self.pass_on()?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    fn start(&mut self) {
+        if !self.started {
+            self.coder.write_header(None);
+            self.started = true;
+        }
+    }
+
+    fn pass_on(&mut self) -> io::Result<()> {
+        let output = self.coder.output_mut();
+        self.inner.write_all(output)?;
+        output.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write, C: UniversalCoder> Write for LzwWriter<W, C> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.start();
+        for &byte in buf {
+            self.coder.push(byte);
+        }
+        self.pass_on()?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.pass_on()?;
+        self.inner.flush()
+    }
+}
+
+// Decodes lazily, holding at most one chunk plus a phrase. Reads both streams
+// of known length and streams ended with an end code, but not word streams.
+// Like LZWDecoder it reads a few bytes past the stream, so data that follows
+// it in inner is lost. Wrap inner with Read::take if the length is known.
+pub struct LzwReader<R, D> {
+    decoder: LZWDecoder<R, Vec<u8>, D>,
+    pos: usize,
+    started: bool,
+}
+
+impl<R: Read, D: UniversalDecoder> LzwReader<R, D> {
+    pub fn new(inner: R, universal_decoder: D) -> Self {
+        Self {
+            decoder: LZWDecoder::new(inner, vec![], universal_decoder),
+            pos: 0,
+            started: false,
+        }
+    }
+
+    pub fn with_preset(mut self, preset: PresetDict) -> Self {
+        self.decoder = self.decoder.with_preset(preset);
+        self
+    }
+
+    pub fn header(&self) -> Option<StreamHeader> {
+        self.decoder.header()
+    }
+
+    // Decodes the next chunk, false once everything was read
+    fn fill(&mut self) -> io::Result<bool> {
+        self.decoder.output_mut().clear();
+        self.pos = 0;
+
+        if !self.started {
+            // Word streams are decoded in one go, so they would not stay in one chunk
+            let variant = self.decoder.read_header()?.config.variant;
+            if variant == DictVariant::Words {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!("{:?} cannot be decoded as a stream", variant),
+                ));
+            }
+            self.decoder.start()?;
+            self.started = true;
+        }
+        while self.decoder.output_mut().len() < READ_CHUNK && self.decoder.decode_next()? {}
+
+        Ok(!self.decoder.output_mut().is_empty())
+    }
+}
+
+impl<R: Read, D: UniversalDecoder> Read for LzwReader<R, D> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.pos == self.decoder.output_mut().len() && !self.fill()? {
+            return Ok(0);
+        }
+
+        let pos = self.pos;
+        let output = self.decoder.output_mut();
+        let len = buf.len().min(output.len() - pos);
+        buf[..len].copy_from_slice(&output[pos..pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        coder::LZWCoder,
+        decoder::decode_auto,
+        dict_policy::DictPolicy,
+        header::STREAMED_LEN,
+        universal_coder::{ArithmeticCoder, BinaryCoder, GammaCoder},
+        universal_decoder::{ArithmeticDecoder, BinaryDecoder, GammaDecoder},
+    };
+
+    use super::*;
+
+    fn read_in_pieces<R: Read>(mut reader: R, piece: usize) -> io::Result<Vec<u8>> {
+        let mut output = vec![];
+        let mut buf = vec![0; piece];
+        loop {
+            let len = reader.read(&mut buf)?;
+            if len == 0 {
+                return Ok(output);
+            }
+            output.extend_from_slice(&buf[..len]);
+        }
+    }
+
+    #[test]
+    fn stream_round_trip() {
+        let text = include_bytes!("./../../testy/pan_tadeusz.txt");
+
+        for policy in [DictPolicy::Freeze, DictPolicy::Reset, DictPolicy::AdaptiveReset, DictPolicy::Lru] {
+            let config = DictConfig::new(policy, 4096);
+
+            // Pieces of uneven sizes, so phrases span many writes
+            let mut writer = LzwWriter::with_config(vec![], BinaryCoder::new(), config);
+            let mut start = 0;
+            for piece in (1..).cycle() {
+                let end = (start + piece * 7).min(text.len());
+                writer.write_all(&text[start..end]).unwrap();
+                start = end;
+                if start == text.len() {
+                    break;
+                }
+            }
+            let (input_len, _) = writer.get_stat();
+            let coded_buf = writer.finish().unwrap();
+            assert_eq!(input_len, text.len());
+
+            let mut whole_buf = vec![];
+            LZWCoder::with_config(text.as_slice(), &mut whole_buf, BinaryCoder::new(), config)
+                .with_input_len(text.len() as u64)
+                .code_to_end().unwrap();
+            println!("{:?}: streamed {} whole {}", policy, coded_buf.len(), whole_buf.len());
+
+            let mut reader = LzwReader::new(coded_buf.as_slice(), BinaryDecoder::new());
+            assert_eq!(read_in_pieces(&mut reader, 1000).unwrap(), text);
+            assert_eq!(reader.header().unwrap().len, STREAMED_LEN);
+
+            let mut output_buf = vec![];
+            decode_auto(coded_buf.as_slice(), &mut output_buf).unwrap();
+            assert_eq!(text.as_slice(), output_buf);
+
+            // Streams of known length read the same way
+            let mut reader = LzwReader::new(whole_buf.as_slice(), BinaryDecoder::new());
+            assert_eq!(read_in_pieces(&mut reader, 333).unwrap(), text);
+            assert_eq!(reader.header().unwrap().len, text.len() as u64);
+        }
+    }
+
+    #[test]
+    fn stream_buffers_stay_small() {
+        let text = include_bytes!("./../../testy/pride_and_prejudice.txt").repeat(5);
+        let config = DictConfig::new(DictPolicy::Reset, 4096);
+
+        let mut writer = LzwWriter::with_config(vec![], ArithmeticCoder::new(), config);
+        for chunk in text.chunks(4096) {
+            writer.write_all(chunk).unwrap();
+            assert!(writer.coder.dict_len() <= config.max_size);
+            assert!(writer.coder.output_mut().capacity() <= 2 * 4096);
+        }
+        let coded_buf = writer.finish().unwrap();
+        println!("input {} coded {}", text.len(), coded_buf.len());
+
+        let mut reader = LzwReader::new(coded_buf.as_slice(), ArithmeticDecoder::new());
+        let mut output = vec![];
+        let mut buf = [0; 100];
+        loop {
+            let len = reader.read(&mut buf).unwrap();
+            if len == 0 {
+                break;
+            }
+            output.extend_from_slice(&buf[..len]);
+            assert!(reader.decoder.dict_len() <= config.max_size);
+            assert!(reader.decoder.output_mut().capacity() <= 2 * READ_CHUNK);
+        }
+        assert_eq!(output, text);
+    }
+
+    #[test]
+    fn stream_rejects_words() {
+        let text = include_bytes!("./../../testy/pan_tadeusz.txt");
+        let config = DictConfig::default().with_variant(DictVariant::Words);
+        let mut coded_buf = vec![];
+        LZWCoder::with_config(text.as_slice(), &mut coded_buf, GammaCoder, config).code_to_end().unwrap();
+
+        let mut reader = LzwReader::new(coded_buf.as_slice(), GammaDecoder);
+        let err = reader.read(&mut [0; 100]).unwrap_err();
+        println!("{}", err);
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+        assert!(reader.decoder.output_mut().is_empty());
+    }
+
+    #[test]
+    fn stream_preset_and_errors() {
+        let text = include_bytes!("./../../testy/pan_tadeusz.txt");
+        let preset = PresetDict::new(text[..10_000].to_vec());
+
+        let mut writer = LzwWriter::new(vec![], GammaCoder).with_preset(preset.clone());
+        writer.write_all(&text[10_000..20_000]).unwrap();
+        let coded_buf = writer.finish().unwrap();
+
+        let reader = LzwReader::new(coded_buf.as_slice(), GammaDecoder).with_preset(preset);
+        assert_eq!(read_in_pieces(reader, 4096).unwrap(), text[10_000..20_000]);
+
+        let reader = LzwReader::new(coded_buf.as_slice(), GammaDecoder);
+        assert_eq!(read_in_pieces(reader, 4096).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        // Without the end code the reader cannot tell where the stream stops
+        let mut writer = LzwWriter::new(vec![], GammaCoder);
+        writer.write_all(&text[..5000]).unwrap();
+        writer.flush().unwrap();
+        let partial = writer.inner.clone();
+        let err = read_in_pieces(LzwReader::new(partial.as_slice(), GammaDecoder), 4096).unwrap_err();
+        println!("{}", err);
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // Empty streams hold only the header and the end code
+        let coded_buf = LzwWriter::new(vec![], GammaCoder).finish().unwrap();
+        let reader = LzwReader::new(coded_buf.as_slice(), GammaDecoder);
+        assert!(read_in_pieces(reader, 10).unwrap().is_empty());
+    }
+}
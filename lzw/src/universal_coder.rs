@@ -1,132 +1,275 @@
-use std::io::Write;
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    io::Write,
+};
+
+use arithmetic::coder::SymbolCoder;
+
+use crate::{header::Coding, index_model::IndexModel};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeError {
+    Zero,
+    OutOfRange,
+}
+
+impl Display for CodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodeError::Zero => write!(f, "universal codes start at 1"),
+            CodeError::OutOfRange => write!(f, "number does not fit in the code"),
+        }
+    }
+}
+
+impl Error for CodeError {}
 
 pub trait UniversalCoder {
-    fn code_number<O: Write>(&mut self, output_stream: &mut BitOutputStream<O>, number: usize);
+    // Codes a number >= 1. Nothing is written when an error is returned.
+    fn code_positive<O: Write>(&mut self, output_stream: &mut BitOutputStream<O>, number: u128) -> Result<(), CodeError>;
     fn pad<O: Write>(&mut self, output_stream: &mut BitOutputStream<O>);
+    fn coding(&self) -> Coding;
+    fn set_dict_size(&mut self, _dict_size: usize) {}
+
+    fn code_number<O: Write>(&mut self, output_stream: &mut BitOutputStream<O>, number: usize) {
+        if let Err(err) = self.code_positive(output_stream, number as u128) {
+            panic!("Cannot code {}: {}", number, err);
+        }
+    }
+
+    // Zero-inclusive variant, codes value + 1
+    fn code_u64<O: Write>(&mut self, output_stream: &mut BitOutputStream<O>, value: u64) -> Result<(), CodeError> {
+        self.code_positive(output_stream, value as u128 + 1)
+    }
+}
+
+fn bit_len(number: u128) -> u32 {
+    u128::BITS - number.leading_zeros()
+}
+
+fn write_binary<O: Write>(output_stream: &mut BitOutputStream<O>, value: u128, width: u32) {
+    if width > u64::BITS {
+        output_stream.write_bits((value >> u64::BITS) as u64, width - u64::BITS);
+        output_stream.write_bits(value as u64, u64::BITS);
+    } else {
+        output_stream.write_bits(value as u64, width);
+    }
+}
+
+fn write_unary<O: Write>(output_stream: &mut BitOutputStream<O>, mut quotient: u128) {
+    while quotient >= u64::BITS as u128 {
+        output_stream.write_bits(u64::MAX, u64::BITS);
+        quotient -= u64::BITS as u128;
+    }
+    let quotient = quotient as u32;
+    output_stream.write_bits(((1 << quotient) - 1) << 1, quotient + 1);
+}
+
+// Longest unary part Golomb and Rice codes write. LZW codes of the largest
+// dictionary still fit with m = 1 or k = 0.
+pub const MAX_UNARY: u128 = 1 << 20;
+
+fn check_unary(quotient: u128) -> Result<u128, CodeError> {
+    if quotient > MAX_UNARY {
+        Err(CodeError::OutOfRange)
+    } else {
+        Ok(quotient)
+    }
+}
+
+fn check_positive(number: u128) -> Result<(), CodeError> {
+    if number == 0 {
+        Err(CodeError::Zero)
+    } else {
+        Ok(())
+    }
 }
 
 pub struct GammaCoder;
 
 impl UniversalCoder for GammaCoder {
-    fn code_number<O: Write>(&mut self, output_stream: &mut BitOutputStream<O>, number: usize) {
-        let num_size = number.ilog2() + 1;
+    fn code_positive<O: Write>(&mut self, output_stream: &mut BitOutputStream<O>, number: u128) -> Result<(), CodeError> {
+        check_positive(number)?;
+        let num_size = bit_len(number);
 
-        for _ in 0..(num_size - 1) {
-            output_stream.write_bit(false);
-        }
+        write_binary(output_stream, 0, num_size - 1);
+        write_binary(output_stream, number, num_size);
 
-        let mut mask = 1 << (num_size - 1);
-        while mask != 0 {
-            output_stream.write_bit((mask & number) > 0);
-            mask >>= 1;
-        }
+        Ok(())
     }
 
     fn pad<O: Write>(&mut self, output_stream: &mut BitOutputStream<O>) {
         output_stream.pad_with_zeros();
     }
-    
+
+    fn coding(&self) -> Coding {
+        Coding::Gamma
+    }
 }
 
-pub struct DeltaCoder; 
+pub struct DeltaCoder;
 
 impl UniversalCoder for DeltaCoder {
-    fn code_number<O: Write>(&mut self, output_stream: &mut BitOutputStream<O>, number: usize) {
-        let n = number.ilog2() + 1;
-        GammaCoder.code_number(output_stream, n as usize); 
+    fn code_positive<O: Write>(&mut self, output_stream: &mut BitOutputStream<O>, number: u128) -> Result<(), CodeError> {
+        check_positive(number)?;
+        let n = bit_len(number);
+        GammaCoder.code_positive(output_stream, n as u128)?;
+        write_binary(output_stream, number, n - 1);
 
-        let mut mask = if n > 1 { 1 << (n - 2) } else { 0 };
-        while mask != 0 {
-            output_stream.write_bit((mask & number) > 0);
-            mask >>= 1;
-        }
+        Ok(())
     }
 
     fn pad<O: Write>(&mut self, output_stream: &mut BitOutputStream<O>) {
         output_stream.pad_with_zeros();
     }
-} 
+
+    fn coding(&self) -> Coding {
+        Coding::Delta
+    }
+}
 
 pub struct OmegaCoder;
 
 impl UniversalCoder for OmegaCoder {
-    fn code_number<O: Write>(&mut self, output_stream: &mut BitOutputStream<O>, number: usize) {
-        let mut bits = vec![];
-        bits.push(false);
+    fn code_positive<O: Write>(&mut self, output_stream: &mut BitOutputStream<O>, number: u128) -> Result<(), CodeError> {
+        check_positive(number)?;
 
+        // A u128 has at most 4 groups, of 128, 7, 3 and 2 bits
+        let mut groups = [0; 4];
+        let mut count = 0;
         let mut rec_number = number;
-
         while rec_number != 1 {
-            let mut n = rec_number;
-            while n != 0 {
-                bits.push(n % 2 == 1);
-                n /= 2;
-            }
-
-            let n_size = rec_number.ilog2();
-            rec_number = n_size as usize;
+            groups[count] = rec_number;
+            count += 1;
+            rec_number = (bit_len(rec_number) - 1) as u128;
         }
 
-        for bit in bits.into_iter().rev() {
-            output_stream.write_bit(bit);
+        for &group in groups[..count].iter().rev() {
+            write_binary(output_stream, group, bit_len(group));
         }
+        output_stream.write_bit(false);
+
+        Ok(())
     }
 
     fn pad<O: Write>(&mut self, output_stream: &mut BitOutputStream<O>) {
         output_stream.pad_with_ones()
     }
+
+    fn coding(&self) -> Coding {
+        Coding::Omega
+    }
 }
 
+// Numbers below F(LOW_TOP + 1) are coded from a table
+const LOW_TOP: usize = 18;
+
 pub struct FibonacciCoder {
-    fib_numbers:  Vec<usize>,
+    fib_numbers: Vec<u128>,
+    // For each remainder, the bits for F(2)..=F(LOW_TOP) in writing order
+    low_codes: Vec<u32>,
 }
 
 impl FibonacciCoder {
     pub fn new() -> Self {
-        Self { fib_numbers: vec![0, 1] }
-    }
-
-    pub fn find_or_resize(&mut self, to: usize) -> usize {
-        let mut i = 2;
-        loop {
-            if i == self.fib_numbers.len() {
-                self.fib_numbers.push(self.fib_numbers[i - 1] + self.fib_numbers[i - 2])
-            }
+        // Every Fibonacci number that fits in a u128, a bit under 190 of them
+        let mut fib_numbers = vec![0u128, 1];
+        while let Some(next) = fib_numbers[fib_numbers.len() - 1].checked_add(fib_numbers[fib_numbers.len() - 2]) {
+            fib_numbers.push(next);
+        }
 
-            if to < self.fib_numbers[i] {
-                break;
-            }
+        let low_codes = (0..fib_numbers[LOW_TOP + 1])
+            .map(|mut n| {
+                let mut bits = 0;
+                for i in (2..=LOW_TOP).rev() {
+                    if n >= fib_numbers[i] {
+                        n -= fib_numbers[i];
+                        bits |= 1 << (LOW_TOP - i);
+                    }
+                }
+                bits
+            })
+            .collect();
+
+        Self { fib_numbers, low_codes }
+    }
 
+    // Index of the largest Fibonacci number not greater than to. F(i) grows
+    // by about 1.44 indices per bit, so the guess is at most a step or two off.
+    pub fn find_or_resize(&mut self, to: u128) -> usize {
+        let mut i = ((bit_len(to).max(1) - 1) as usize * 1440 / 1000 + 1).min(self.fib_numbers.len() - 1);
+        while i + 1 < self.fib_numbers.len() && self.fib_numbers[i + 1] <= to {
             i += 1;
         }
-        i - 1
+        while i > 1 && self.fib_numbers[i] > to {
+            i -= 1;
+        }
+        i
     }
 }
 
 impl UniversalCoder for FibonacciCoder {
-    fn code_number<O: Write>(&mut self, output_stream: &mut BitOutputStream<O>, number: usize) {
-        let mut bits = vec![];
-        bits.push(true);
+    fn code_positive<O: Write>(&mut self, output_stream: &mut BitOutputStream<O>, number: u128) -> Result<(), CodeError> {
+        check_positive(number)?;
 
-        let mut i = self.find_or_resize(number);
+        let top = self.find_or_resize(number);
         let mut n = number;
 
-        while i >= 2 {
-            bits.push(n >= self.fib_numbers[i]);
+        // The code has a bit for each of F(2)..=F(top) and the final 1, so it
+        // is top bits long, with the bit for F(i) at top + 1 - i counting
+        // from the right. It is put together in two words as the largest
+        // numbers need more than 128 bits.
+        let len = top as u32;
+        let mut low = 1;
+        let mut high = 0;
+        let mut set = |i: usize| {
+            let pos = len + 1 - i as u32;
+            if pos < u128::BITS {
+                low |= 1 << pos;
+            } else {
+                high |= 1 << (pos - u128::BITS);
+            }
+        };
+
+        for i in (LOW_TOP + 1..=top).rev() {
             if n >= self.fib_numbers[i] {
                 n -= self.fib_numbers[i];
+                set(i);
             }
-            i -= 1;
+        }
+        // What is left is below F(LOW_TOP + 1)
+        let low_bits = self.low_codes[n as usize];
+        if len > u128::BITS {
+            for i in 2..=LOW_TOP {
+                if low_bits >> (LOW_TOP - i) & 1 == 1 {
+                    set(i);
+                }
+            }
+        } else if top >= LOW_TOP - 1 {
+            low |= (low_bits as u128) << (top + 1 - LOW_TOP);
+        } else {
+            // Bits above F(top) are all zero
+            low |= (low_bits >> (LOW_TOP - 1 - top)) as u128;
         }
 
-        for bit in bits.into_iter().rev() {
-            output_stream.write_bit(bit);
-        }        
+        if len > u128::BITS {
+            write_binary(output_stream, high, len - u128::BITS);
+            write_binary(output_stream, low, u128::BITS);
+        } else {
+            write_binary(output_stream, low, len);
+        }
+
+        Ok(())
     }
 
     fn pad<O: Write>(&mut self, output_stream: &mut BitOutputStream<O>) {
         output_stream.pad_with_zeros();
     }
+
+    fn coding(&self) -> Coding {
+        Coding::Fib
+    }
 }
 
 impl Default for FibonacciCoder {
@@ -135,9 +278,295 @@ impl Default for FibonacciCoder {
     }
 }
 
+pub struct BinaryCoder {
+    width: u32,
+}
+
+impl BinaryCoder {
+    pub fn new() -> Self {
+        Self { width: 256usize.ilog2() + 1 }
+    }
+}
+
+impl UniversalCoder for BinaryCoder {
+    fn code_positive<O: Write>(&mut self, output_stream: &mut BitOutputStream<O>, number: u128) -> Result<(), CodeError> {
+        check_positive(number)?;
+        let value = number - 1;
+        if bit_len(value) > self.width {
+            return Err(CodeError::OutOfRange);
+        }
+
+        write_binary(output_stream, value, self.width);
+
+        Ok(())
+    }
+
+    fn pad<O: Write>(&mut self, output_stream: &mut BitOutputStream<O>) {
+        output_stream.pad_with_zeros();
+    }
+
+    fn coding(&self) -> Coding {
+        Coding::Binary
+    }
+
+    fn set_dict_size(&mut self, dict_size: usize) {
+        self.width = dict_size.ilog2() + 1;
+    }
+}
+
+impl Default for BinaryCoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct GolombCoder {
+    m: u128,
+    width: u32,
+    cutoff: u128,
+}
+
+impl GolombCoder {
+    pub fn new(m: usize) -> Self {
+        assert!(m > 0, "Golomb parameter has to be positive");
+
+        let m = m as u128;
+        let width = m.next_power_of_two().ilog2();
+        let cutoff = (1 << width) - m;
+        Self { m, width, cutoff }
+    }
+}
+
+impl UniversalCoder for GolombCoder {
+    fn code_positive<O: Write>(&mut self, output_stream: &mut BitOutputStream<O>, number: u128) -> Result<(), CodeError> {
+        check_positive(number)?;
+        let value = number - 1;
+        let quotient = check_unary(value / self.m)?;
+        write_unary(output_stream, quotient);
+
+        // Truncated binary remainder
+        let remainder = value % self.m;
+        if remainder < self.cutoff {
+            write_binary(output_stream, remainder, self.width - 1);
+        } else {
+            write_binary(output_stream, remainder + self.cutoff, self.width);
+        }
+
+        Ok(())
+    }
+
+    fn pad<O: Write>(&mut self, output_stream: &mut BitOutputStream<O>) {
+        output_stream.pad_with_ones();
+    }
+
+    fn coding(&self) -> Coding {
+        Coding::Golomb(self.m as u64)
+    }
+}
+
+pub const MAX_RICE_K: u32 = u128::BITS - 1;
+
+pub struct RiceCoder {
+    k: u32,
+}
+
+impl RiceCoder {
+    pub fn new(k: u32) -> Self {
+        assert!(k <= MAX_RICE_K, "Rice parameter has to be at most {}", MAX_RICE_K);
+        Self { k }
+    }
+}
+
+impl UniversalCoder for RiceCoder {
+    fn code_positive<O: Write>(&mut self, output_stream: &mut BitOutputStream<O>, number: u128) -> Result<(), CodeError> {
+        check_positive(number)?;
+        let value = number - 1;
+        let quotient = check_unary(value >> self.k)?;
+        write_unary(output_stream, quotient);
+        write_binary(output_stream, value, self.k);
+
+        Ok(())
+    }
+
+    fn pad<O: Write>(&mut self, output_stream: &mut BitOutputStream<O>) {
+        output_stream.pad_with_ones();
+    }
+
+    fn coding(&self) -> Coding {
+        Coding::Rice(self.k)
+    }
+}
+
+pub struct ExpGolombCoder {
+    k: u32,
+}
+
+impl ExpGolombCoder {
+    pub fn new(k: u32) -> Self {
+        assert!(k <= MAX_RICE_K, "Exp-Golomb order has to be at most {}", MAX_RICE_K);
+        Self { k }
+    }
+}
+
+impl UniversalCoder for ExpGolombCoder {
+    fn code_positive<O: Write>(&mut self, output_stream: &mut BitOutputStream<O>, number: u128) -> Result<(), CodeError> {
+        check_positive(number)?;
+        let value = (number - 1).checked_add(1 << self.k).ok_or(CodeError::OutOfRange)?;
+        let width = bit_len(value);
+
+        write_binary(output_stream, 0, width - 1 - self.k);
+        write_binary(output_stream, value, width);
+
+        Ok(())
+    }
+
+    fn pad<O: Write>(&mut self, output_stream: &mut BitOutputStream<O>) {
+        output_stream.pad_with_zeros();
+    }
+
+    fn coding(&self) -> Coding {
+        Coding::ExpGolomb(self.k)
+    }
+}
+
+const RICE_RESET: u128 = 64;
+
+// Running mean of the coded values, kept the JPEG-LS way: k is the smallest
+// parameter with count * 2^k >= sum, and both halve every RICE_RESET values.
+#[derive(Debug, Clone, Copy)]
+pub struct RiceStats {
+    sum: u128,
+    count: u128,
+}
+
+impl RiceStats {
+    pub fn new() -> Self {
+        Self { sum: 1, count: 1 }
+    }
+
+    pub fn k(&self) -> u32 {
+        let mut k = 0;
+        while k < MAX_RICE_K && self.count << k < self.sum {
+            k += 1;
+        }
+        k
+    }
+
+    pub fn update(&mut self, value: u128) {
+        self.sum = self.sum.saturating_add(value);
+        self.count += 1;
+
+        if self.count == RICE_RESET {
+            self.sum = self.sum.div_ceil(2);
+            self.count /= 2;
+        }
+    }
+}
+
+impl Default for RiceStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct AdaptiveRiceCoder {
+    stats: RiceStats,
+}
+
+impl AdaptiveRiceCoder {
+    pub fn new() -> Self {
+        Self { stats: RiceStats::new() }
+    }
+}
+
+impl UniversalCoder for AdaptiveRiceCoder {
+    fn code_positive<O: Write>(&mut self, output_stream: &mut BitOutputStream<O>, number: u128) -> Result<(), CodeError> {
+        RiceCoder::new(self.stats.k()).code_positive(output_stream, number)?;
+        self.stats.update(number - 1);
+
+        Ok(())
+    }
+
+    fn pad<O: Write>(&mut self, output_stream: &mut BitOutputStream<O>) {
+        output_stream.pad_with_ones();
+    }
+
+    fn coding(&self) -> Coding {
+        Coding::AdaptiveRice
+    }
+}
+
+impl Default for AdaptiveRiceCoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Codes indices with an adaptive arithmetic model over the current dictionary
+pub struct ArithmeticCoder {
+    symbol_coder: SymbolCoder<Vec<u8>>,
+    model: IndexModel,
+}
+
+impl ArithmeticCoder {
+    pub fn new() -> Self {
+        Self {
+            symbol_coder: SymbolCoder::new(vec![], 1),
+            model: IndexModel::new(),
+        }
+    }
+
+    fn move_bytes<O: Write>(&mut self, output_stream: &mut BitOutputStream<O>) {
+        for byte in self.symbol_coder.get_mut().drain(..) {
+            output_stream.write_byte(byte);
+        }
+    }
+}
+
+impl UniversalCoder for ArithmeticCoder {
+    fn code_positive<O: Write>(&mut self, output_stream: &mut BitOutputStream<O>, number: u128) -> Result<(), CodeError> {
+        check_positive(number)?;
+        if number > self.model.alphabet_size() as u128 {
+            return Err(CodeError::OutOfRange);
+        }
+
+        self.symbol_coder.code_symbol_with(&mut self.model, (number - 1) as u32);
+        self.move_bytes(output_stream);
+
+        Ok(())
+    }
+
+    fn pad<O: Write>(&mut self, output_stream: &mut BitOutputStream<O>) {
+        self.symbol_coder.terminate();
+        self.move_bytes(output_stream);
+        output_stream.pad_with_zeros();
+    }
+
+    fn coding(&self) -> Coding {
+        Coding::Arithmetic
+    }
+
+    // The next entry can be coded before the decoder has added it
+    fn set_dict_size(&mut self, dict_size: usize) {
+        self.model.set_alphabet_size(dict_size + 1);
+    }
+}
+
+impl Default for ArithmeticCoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Bits added at once stay below this, so that with fewer than 8 pending bits
+// they fit in the 64-bit buffer
+const MAX_CHUNK: u32 = 56;
+
 pub struct BitOutputStream<O> {
-    buf: u8,
-    n: u8,
+    // Fewer than 8 bits are pending between calls, MSB-first writes keep them
+    // right-aligned and LSB-first writes from bit 0 up
+    buf: u64,
+    n: u32,
     output_stream: O,
     pub written_bytes: usize,
 }
@@ -152,37 +581,101 @@ impl<O: Write> BitOutputStream<O> {
         }
     }
 
+    fn emit(&mut self, bytes: &[u8]) {
+        self.output_stream.write_all(bytes).unwrap();
+        self.written_bytes += bytes.len();
+    }
+
+    // Writes the low count bits of value, MSB-first
+    pub fn write_bits(&mut self, value: u64, count: u32) {
+        assert!(count <= u64::BITS, "At most 64 bits can be written at once");
+        if count > MAX_CHUNK {
+            self.write_bits(value >> 32, count - 32);
+            self.write_bits(value, 32);
+            return;
+        }
+        if count == 0 {
+            return;
+        }
+
+        self.buf = self.buf << count | value & (u64::MAX >> (u64::BITS - count));
+        self.n += count;
+
+        let whole = self.n / 8;
+        if whole > 0 {
+            self.n %= 8;
+            let bytes = (self.buf >> self.n).to_be_bytes();
+            self.emit(&bytes[8 - whole as usize..]);
+            self.buf &= (1 << self.n) - 1;
+        }
+    }
+
     pub fn write_bit(&mut self, bit: bool) {
-        self.buf <<= 1;
-        self.buf |= if bit { 1 } else { 0 };
-        self.n += 1;
+        self.write_bits(bit as u64, 1);
+    }
 
-        if self.n >= 8 {
-            self.output_stream.write_all(&[self.buf]).unwrap();
+    pub fn write_bit_lsb(&mut self, bit: bool) {
+        self.write_bits_lsb(bit as u32, 1);
+    }
+
+    pub fn write_bits_lsb(&mut self, value: u32, count: u32) {
+        assert!(count <= u32::BITS, "At most 32 bits can be written at once");
+        self.buf |= (value as u64 & ((1 << count) - 1)) << self.n;
+        self.n += count;
+
+        let whole = self.n / 8;
+        if whole > 0 {
+            let bytes = self.buf.to_le_bytes();
+            self.emit(&bytes[..whole as usize]);
+            self.buf >>= whole * 8;
+            self.n %= 8;
+        }
+    }
+
+    pub fn align_lsb(&mut self) {
+        if self.n != 0 {
+            self.emit(&[self.buf as u8]);
             self.buf = 0;
             self.n = 0;
-            self.written_bytes += 1;
         }
     }
 
+    pub fn write_byte(&mut self, byte: u8) {
+        self.write_bits(byte as u64, 8);
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        assert_eq!(self.n, 0, "Bytes can only be written on a byte boundary");
+        self.emit(bytes);
+    }
+
+    pub fn flush(&mut self) {
+        self.output_stream.flush().unwrap();
+    }
+
+    pub fn get_mut(&mut self) -> &mut O {
+        &mut self.output_stream
+    }
+
+    pub fn written_bits(&self) -> usize {
+        self.written_bytes * 8 + self.n as usize
+    }
+
     pub fn pad_with_ones(&mut self) {
         if self.n != 0 {
-            self.buf <<= 8 - self.n;
-            self.buf |= !0 >> self.n;
-            self.output_stream.write_all(&[self.buf]).unwrap();
+            let byte = (self.buf << (8 - self.n)) as u8 | 0xFF >> self.n;
+            self.emit(&[byte]);
             self.buf = 0;
             self.n = 0;
-            self.written_bytes += 1;
         }
     }
 
     pub fn pad_with_zeros(&mut self) {
         if self.n != 0 {
-            self.buf <<= 8 - self.n;
-            self.output_stream.write_all(&[self.buf]).unwrap();
+            let byte = (self.buf << (8 - self.n)) as u8;
+            self.emit(&[byte]);
             self.buf = 0;
             self.n = 0;
-            self.written_bytes += 1;
         }
     }
 }
@@ -242,6 +735,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn binary() {
+        let mut stream = vec![];
+        let mut coder = BinaryCoder::new();
+
+        let mut bitoutput = BitOutputStream::new(&mut stream);
+
+        for i in 1..10 {
+            coder.set_dict_size(255 + i);
+            coder.code_number(&mut bitoutput, i);
+        }
+        coder.pad(&mut bitoutput);
+
+        for num in stream {
+            eprintln!("{:#010b}", num)
+        }
+    }
+
     #[test]
     fn fib() {
         let mut stream = vec![];
@@ -1,124 +1,323 @@
-use std::io::Read;
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt::{self, Display},
+    io::{self, ErrorKind, Read},
+};
+
+use arithmetic::decoder::SymbolDecoder;
+
+use crate::{
+    header::Coding,
+    index_model::IndexModel,
+    universal_coder::{RiceStats, MAX_RICE_K, MAX_UNARY},
+};
+
+// Bytes kept ahead of the arithmetic decoder, more than one symbol can take
+const ARITHMETIC_LOOKAHEAD: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    EndOfStream,
+    Truncated,
+    Overflow,
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::EndOfStream => write!(f, "end of stream"),
+            DecodeError::Truncated => write!(f, "stream ends in the middle of a code"),
+            DecodeError::Overflow => write!(f, "decoded number does not fit in 128 bits"),
+        }
+    }
+}
+
+impl Error for DecodeError {}
 
 pub trait UniversalDecoder {
-    fn decode_number<I: Read>(&mut self, input_stream: &mut BitInputStream<I>) -> Option<usize>;
+    // Reads one number >= 1, returning Truncated on any end of input
+    fn read_code<I: Read>(&mut self, input_stream: &mut BitInputStream<I>) -> Result<u128, DecodeError>;
+    fn coding(&self) -> Coding;
+    fn set_dict_size(&mut self, _dict_size: usize) {}
+
+    // Value of the bits the matching coder pads with
+    fn padding(&self) -> bool {
+        false
+    }
+
+    fn decode_positive<I: Read>(&mut self, input_stream: &mut BitInputStream<I>) -> Result<u128, DecodeError> {
+        let start = input_stream.position();
+        match self.read_code(input_stream) {
+            Err(DecodeError::Truncated) if input_stream.is_padding_from(start, self.padding()) => {
+                Err(DecodeError::EndOfStream)
+            }
+            result => result,
+        }
+    }
+
+    // Zero-inclusive variant, inverse of code_u64
+    fn decode_u64<I: Read>(&mut self, input_stream: &mut BitInputStream<I>) -> Result<u64, DecodeError> {
+        let number = self.decode_positive(input_stream)?;
+        u64::try_from(number - 1).map_err(|_| DecodeError::Overflow)
+    }
+
+    fn decode_number<I: Read>(&mut self, input_stream: &mut BitInputStream<I>) -> Option<usize> {
+        let number = self.decode_positive(input_stream).ok()?;
+        usize::try_from(number).ok()
+    }
+}
+
+fn next_bit<I: Read>(input_stream: &mut BitInputStream<I>) -> Result<bool, DecodeError> {
+    input_stream.read_bit().ok_or(DecodeError::Truncated)
+}
+
+fn read_binary<I: Read>(input_stream: &mut BitInputStream<I>, width: u32) -> Result<u128, DecodeError> {
+    let mut read_number: u128 = 0;
+    let mut left = width;
+    while left > 0 {
+        let count = left.min(u64::BITS);
+        let bits = input_stream.read_bits(count).ok_or(DecodeError::Truncated)?;
+        read_number = read_number << count | bits as u128;
+        left -= count;
+    }
+    Ok(read_number)
+}
+
+// Runs of ones are counted a buffer at a time
+fn read_unary<I: Read>(input_stream: &mut BitInputStream<I>) -> Result<u128, DecodeError> {
+    let mut quotient = 0;
+    loop {
+        let (bits, available) = input_stream.peek();
+        if available == 0 {
+            return Err(DecodeError::Truncated);
+        }
+        let ones = bits.leading_ones().min(available);
+        quotient += ones as u128;
+        if quotient > MAX_UNARY {
+            return Err(DecodeError::Overflow);
+        }
+        if ones < available {
+            input_stream.consume(ones + 1);
+            return Ok(quotient);
+        }
+        input_stream.consume(ones);
+    }
+}
+
+fn count_zeros<I: Read>(input_stream: &mut BitInputStream<I>, limit: u32) -> Result<u32, DecodeError> {
+    let mut zeros = 0;
+    loop {
+        let (bits, available) = input_stream.peek();
+        if available == 0 {
+            return Err(DecodeError::Truncated);
+        }
+        // Bits past the available ones read as zeros too
+        let run = bits.leading_zeros().min(available);
+        zeros += run;
+        if zeros > limit {
+            return Err(DecodeError::Overflow);
+        }
+        if run < available {
+            input_stream.consume(run + 1);
+            return Ok(zeros);
+        }
+        input_stream.consume(run);
+    }
 }
 
 pub struct GammaDecoder;
 
 impl UniversalDecoder for GammaDecoder {
-    fn decode_number<I: Read>(&mut self, input_stream: &mut BitInputStream<I>) -> Option<usize> {
-        let mut zeros = 0;
-        loop { 
-            let Some(bit) = input_stream.read_bit() else { return None; };
-            if !bit {
-                zeros += 1;
-            } else {
-                break;
-            }
+    fn read_code<I: Read>(&mut self, input_stream: &mut BitInputStream<I>) -> Result<u128, DecodeError> {
+        // Gamma needs no table, the leading zeros of the next bits give the
+        // length. Codes longer than the buffer are read bit by bit.
+        let (bits, available) = input_stream.peek();
+        let zeros = bits.leading_zeros();
+        if 2 * zeros < available {
+            input_stream.consume(2 * zeros + 1);
+            return Ok((bits >> (63 - 2 * zeros)) as u128);
         }
-        
-        let mut read_number = 1;
 
-        while zeros != 0 {
-            let Some(bit) = input_stream.read_bit() else { return None; };
-            read_number *= 2;
-            read_number += if bit { 1 } else { 0 }; 
-            zeros -= 1;
-        }
+        let zeros = count_zeros(input_stream, u128::BITS - 1)?;
+        Ok(1 << zeros | read_binary(input_stream, zeros)?)
+    }
 
-        Some(read_number)
+    fn coding(&self) -> Coding {
+        Coding::Gamma
     }
 }
 
 pub struct DeltaDecoder;
 
 impl UniversalDecoder for DeltaDecoder {
-    fn decode_number<I: Read>(&mut self, input_stream: &mut BitInputStream<I>) -> Option<usize> {
-        let mut n_size = GammaDecoder.decode_number(input_stream)? - 1;
-        let mut read_number = 1;
-
-        while n_size != 0 {
-            let Some(bit) = input_stream.read_bit() else { return None; };
-            read_number *= 2;
-            read_number += if bit { 1 } else { 0 }; 
-            n_size -= 1;
+    fn read_code<I: Read>(&mut self, input_stream: &mut BitInputStream<I>) -> Result<u128, DecodeError> {
+        let n_size = GammaDecoder.read_code(input_stream)? - 1;
+        if n_size >= u128::BITS as u128 {
+            return Err(DecodeError::Overflow);
         }
 
-        Some(read_number)
+        Ok(1 << n_size | read_binary(input_stream, n_size as u32)?)
+    }
+
+    fn coding(&self) -> Coding {
+        Coding::Delta
     }
 }
 
 pub struct OmegaDecoder;
 
 impl UniversalDecoder for OmegaDecoder {
-    fn decode_number<I: Read>(&mut self, input_stream: &mut BitInputStream<I>) -> Option<usize> {
+    fn read_code<I: Read>(&mut self, input_stream: &mut BitInputStream<I>) -> Result<u128, DecodeError> {
         let mut n = 1;
 
-        loop {
-            let Some(bit) = input_stream.read_bit() else { return None; };
-
-            if !bit {
+        // Groups that are buffered whole are taken from one peek
+        let (bits, available) = input_stream.peek();
+        let mut used = 0;
+        while used < available {
+            if bits << used >> 63 == 0 {
+                input_stream.consume(used + 1);
+                return Ok(n);
+            }
+            if used as u128 + 1 + n > available as u128 {
                 break;
             }
+            let group = bits << (used + 1) >> (u64::BITS - n as u32);
+            used += 1 + n as u32;
+            n = 1 << n | group as u128;
+        }
+        input_stream.consume(used);
 
-            let mut read_number = 1;
-            let mut to_read = n;
-
-            while to_read > 0 {
-                let Some(bit) = input_stream.read_bit() else { return None; };
-                read_number *= 2;
-                read_number += if bit { 1 } else { 0 }; 
-                to_read -= 1;
+        while next_bit(input_stream)? {
+            if n >= u128::BITS as u128 {
+                return Err(DecodeError::Overflow);
             }
-
-            n = read_number;
+            n = 1 << n | read_binary(input_stream, n as u32)?;
         }
 
-        Some(n)
+        Ok(n)
+    }
+
+    fn coding(&self) -> Coding {
+        Coding::Omega
+    }
+
+    fn padding(&self) -> bool {
+        true
     }
 }
 
+// Bit k of a byte starting at index i weighs F(i + k) = F(k) F(i + 1) +
+// F(k - 1) F(i), so a table keeps the sums of F(k) and F(k - 1) over the set
+// bits before the closing 1. Indexed by the last bit of the previous byte.
+#[derive(Debug, Clone, Copy)]
+struct FibStep {
+    next: u8,
+    current: u8,
+    end: Option<u32>,
+    last_bit: bool,
+}
+
+static FIB_STEPS: [[FibStep; 256]; 2] = fib_steps();
+
+const fn fib_steps() -> [[FibStep; 256]; 2] {
+    // F(k - 1) for k = 0..=8
+    const SMALL_FIB: [u8; 9] = [1, 0, 1, 1, 2, 3, 5, 8, 13];
+
+    let empty = FibStep { next: 0, current: 0, end: None, last_bit: false };
+    let mut steps = [[empty; 256]; 2];
+    let mut last = 0;
+    while last < 2 {
+        let mut byte = 0;
+        while byte < 256 {
+            let mut step = empty;
+            let mut last_bit = last == 1;
+            let mut k = 0;
+            while k < 8 {
+                let bit = (byte >> (7 - k)) & 1 == 1;
+                if last_bit && bit {
+                    step.end = Some(k as u32);
+                    break;
+                }
+                if bit {
+                    step.next += SMALL_FIB[k + 1];
+                    step.current += SMALL_FIB[k];
+                }
+                last_bit = bit;
+                k += 1;
+            }
+            step.last_bit = last_bit;
+            steps[last][byte] = step;
+            byte += 1;
+        }
+        last += 1;
+    }
+    steps
+}
+
 pub struct FibonacciDecoder {
-    fib_numbers:  Vec<usize>,
+    fib_numbers: Vec<u128>,
 }
 
 impl FibonacciDecoder {
     pub fn new() -> Self {
-        Self { fib_numbers: vec![0, 1] }
-    }
-
-    pub fn fib(&mut self, i: usize) -> usize {
-        while i >= self.fib_numbers.len() {
-            let len = self.fib_numbers.len();
-            self.fib_numbers.push(self.fib_numbers[len - 1] + self.fib_numbers[len - 2])
+        // Every Fibonacci number that fits in a u128
+        let mut fib_numbers = vec![0u128, 1];
+        while let Some(next) = fib_numbers[fib_numbers.len() - 1].checked_add(fib_numbers[fib_numbers.len() - 2]) {
+            fib_numbers.push(next);
         }
+        Self { fib_numbers }
+    }
 
-        self.fib_numbers[i]
+    pub fn fib(&self, i: usize) -> Option<u128> {
+        self.fib_numbers.get(i).copied()
     }
 }
 
 impl UniversalDecoder for FibonacciDecoder {
-    fn decode_number<I: Read>(&mut self, input_stream: &mut BitInputStream<I>) -> Option<usize> {
-        let mut read_number = 0;
+    fn read_code<I: Read>(&mut self, input_stream: &mut BitInputStream<I>) -> Result<u128, DecodeError> {
+        // A byte at a time while the code is in the buffer
+        let (bits, available) = input_stream.peek();
+        let mut read_number: u128 = 0;
         let mut i = 2;
         let mut last_bit = false;
+        let mut used = 0;
+        while used + 8 <= available {
+            let step = FIB_STEPS[last_bit as usize][(bits << used >> 56) as usize];
+            // At most 64 bits are buffered, far from the end of the table
+            read_number += step.next as u128 * self.fib_numbers[i + 1] + step.current as u128 * self.fib_numbers[i];
+
+            if let Some(end) = step.end {
+                input_stream.consume(used + end + 1);
+                return Ok(read_number);
+            }
+            last_bit = step.last_bit;
+            i += 8;
+            used += 8;
+        }
 
-        while let Some(bit) = input_stream.read_bit() {
+        let mut read_number: u128 = 0;
+        let mut i = 2;
+        let mut last_bit = false;
+
+        loop {
+            let bit = next_bit(input_stream)?;
             if last_bit && bit {
-                return Some(read_number);
+                return Ok(read_number);
             }
 
             if bit {
-                read_number += self.fib(i);
+                let fib = self.fib(i).ok_or(DecodeError::Overflow)?;
+                read_number = read_number.checked_add(fib).ok_or(DecodeError::Overflow)?;
             }
-            
 
             last_bit = bit;
             i += 1;
         }
+    }
 
-        None
+    fn coding(&self) -> Coding {
+        Coding::Fib
     }
 }
 
@@ -128,43 +327,444 @@ impl Default for FibonacciDecoder {
     }
 }
 
+pub struct BinaryDecoder {
+    width: u32,
+}
+
+impl BinaryDecoder {
+    pub fn new() -> Self {
+        Self { width: 256usize.ilog2() + 1 }
+    }
+}
+
+impl UniversalDecoder for BinaryDecoder {
+    fn read_code<I: Read>(&mut self, input_stream: &mut BitInputStream<I>) -> Result<u128, DecodeError> {
+        Ok(read_binary(input_stream, self.width)? + 1)
+    }
+
+    fn coding(&self) -> Coding {
+        Coding::Binary
+    }
+
+    fn set_dict_size(&mut self, dict_size: usize) {
+        self.width = dict_size.ilog2() + 1;
+    }
+}
+
+impl Default for BinaryDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct GolombDecoder {
+    m: u128,
+    width: u32,
+    cutoff: u128,
+}
+
+impl GolombDecoder {
+    pub fn new(m: usize) -> Self {
+        assert!(m > 0, "Golomb parameter has to be positive");
+
+        let m = m as u128;
+        let width = m.next_power_of_two().ilog2();
+        let cutoff = (1 << width) - m;
+        Self { m, width, cutoff }
+    }
+}
+
+impl UniversalDecoder for GolombDecoder {
+    fn read_code<I: Read>(&mut self, input_stream: &mut BitInputStream<I>) -> Result<u128, DecodeError> {
+        let quotient = read_unary(input_stream)?;
+
+        let mut remainder = 0;
+        if self.m > 1 {
+            remainder = read_binary(input_stream, self.width - 1)?;
+            if remainder >= self.cutoff {
+                remainder = (remainder << 1 | next_bit(input_stream)? as u128) - self.cutoff;
+            }
+        }
+
+        quotient
+            .checked_mul(self.m)
+            .and_then(|value| value.checked_add(remainder + 1))
+            .ok_or(DecodeError::Overflow)
+    }
+
+    fn coding(&self) -> Coding {
+        Coding::Golomb(self.m as u64)
+    }
+
+    fn padding(&self) -> bool {
+        true
+    }
+}
+
+pub struct RiceDecoder {
+    k: u32,
+}
+
+impl RiceDecoder {
+    pub fn new(k: u32) -> Self {
+        assert!(k <= MAX_RICE_K, "Rice parameter has to be at most {}", MAX_RICE_K);
+        Self { k }
+    }
+}
+
+impl UniversalDecoder for RiceDecoder {
+    fn read_code<I: Read>(&mut self, input_stream: &mut BitInputStream<I>) -> Result<u128, DecodeError> {
+        // Codes that are buffered whole are read from one peek
+        let (bits, available) = input_stream.peek();
+        let ones = bits.leading_ones();
+        if ones + 1 + self.k <= available {
+            let remainder = if self.k == 0 { 0 } else { bits << (ones + 1) >> (u64::BITS - self.k) };
+            input_stream.consume(ones + 1 + self.k);
+            return Ok(((ones as u128) << self.k | remainder as u128) + 1);
+        }
+
+        let quotient = read_unary(input_stream)?;
+        let remainder = read_binary(input_stream, self.k)?;
+
+        quotient
+            .checked_mul(1 << self.k)
+            .and_then(|value| value.checked_add(remainder + 1))
+            .ok_or(DecodeError::Overflow)
+    }
+
+    fn coding(&self) -> Coding {
+        Coding::Rice(self.k)
+    }
+
+    fn padding(&self) -> bool {
+        true
+    }
+}
+
+pub struct ExpGolombDecoder {
+    k: u32,
+}
+
+impl ExpGolombDecoder {
+    pub fn new(k: u32) -> Self {
+        assert!(k <= MAX_RICE_K, "Exp-Golomb order has to be at most {}", MAX_RICE_K);
+        Self { k }
+    }
+}
+
+impl UniversalDecoder for ExpGolombDecoder {
+    fn read_code<I: Read>(&mut self, input_stream: &mut BitInputStream<I>) -> Result<u128, DecodeError> {
+        let zeros = count_zeros(input_stream, u128::BITS - 1 - self.k)?;
+        let width = zeros + self.k;
+        let value = 1 << width | read_binary(input_stream, width)?;
+
+        Ok(value - (1 << self.k) + 1)
+    }
+
+    fn coding(&self) -> Coding {
+        Coding::ExpGolomb(self.k)
+    }
+}
+
+pub struct AdaptiveRiceDecoder {
+    stats: RiceStats,
+}
+
+impl AdaptiveRiceDecoder {
+    pub fn new() -> Self {
+        Self { stats: RiceStats::new() }
+    }
+}
+
+impl UniversalDecoder for AdaptiveRiceDecoder {
+    fn read_code<I: Read>(&mut self, input_stream: &mut BitInputStream<I>) -> Result<u128, DecodeError> {
+        let number = RiceDecoder::new(self.stats.k()).read_code(input_stream)?;
+        self.stats.update(number - 1);
+        Ok(number)
+    }
+
+    fn coding(&self) -> Coding {
+        Coding::AdaptiveRice
+    }
+
+    fn padding(&self) -> bool {
+        true
+    }
+}
+
+impl Default for AdaptiveRiceDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct ArithmeticDecoder {
+    symbol_decoder: SymbolDecoder<VecDeque<u8>>,
+    model: IndexModel,
+}
+
+impl ArithmeticDecoder {
+    pub fn new() -> Self {
+        Self {
+            symbol_decoder: SymbolDecoder::new(VecDeque::new(), 1),
+            model: IndexModel::new(),
+        }
+    }
+}
+
+impl UniversalDecoder for ArithmeticDecoder {
+    fn read_code<I: Read>(&mut self, input_stream: &mut BitInputStream<I>) -> Result<u128, DecodeError> {
+        let lookahead = self.symbol_decoder.get_mut();
+        while lookahead.len() < ARITHMETIC_LOOKAHEAD {
+            let Some(byte) = input_stream.read_byte() else {
+                break;
+            };
+            lookahead.push_back(byte);
+        }
+
+        match self.symbol_decoder.decode_symbol_with(&mut self.model) {
+            Ok(Some(symbol)) => Ok(symbol as u128 + 1),
+            _ => Err(DecodeError::Truncated),
+        }
+    }
+
+    fn coding(&self) -> Coding {
+        Coding::Arithmetic
+    }
+
+    fn set_dict_size(&mut self, dict_size: usize) {
+        self.model.set_alphabet_size(dict_size + 1);
+    }
+}
+
+impl Default for ArithmeticDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Bits that can be asked for at once, the buffer then still has room for
+// the rest of a partly read byte
+const MAX_FILL: u32 = 57;
+
+// Bits peek keeps buffered while the input lasts, enough for the codes of
+// 16-bit numbers in one go
+const PEEK_BITS: u32 = 33;
+
 pub struct BitInputStream<I> {
-    buf: u8,
-    n: u8,
+    // Fetched bytes that are not used up yet, the first one in the top bits
+    // with n of its bits already read
+    buf: u64,
+    len: u32,
+    n: u32,
+    fetched: u64,
+    last_byte: u8,
     input_stream: I,
+    // A failed read ends the input like EOF, the error waits here for the decoder
+    last_error: Option<io::Error>,
 }
 
 impl<I: Read> BitInputStream<I> {
     pub fn new(input_stream: I) -> Self {
         Self {
             buf: 0,
+            len: 0,
             n: 0,
+            fetched: 0,
+            last_byte: 0,
             input_stream,
+            last_error: None,
         }
     }
 
-    pub fn read_bit(&mut self) -> Option<bool> {
-        if self.n == 0 {
-            let mut tmp_buf = [0];
-            let read = self.input_stream.read_exact(&mut tmp_buf);
-            if read.is_err() {
+    fn available(&self) -> u32 {
+        self.len - self.n
+    }
+
+    // Reads up to max bytes into the buffer, 0 at the end of input
+    fn fetch(&mut self, max: usize) -> usize {
+        if self.last_error.is_some() {
+            return 0;
+        }
+        let mut bytes = [0; 8];
+        let read = loop {
+            match self.input_stream.read(&mut bytes[..max]) {
+                Ok(read) => break read,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(err) => {
+                    self.last_error = Some(err);
+                    break 0;
+                }
+            }
+        };
+
+        // Bytes past the ones read are still zero
+        self.buf |= u64::from_be_bytes(bytes) >> self.len;
+        self.len += 8 * read as u32;
+        if read > 0 {
+            self.fetched += read as u64;
+            self.last_byte = bytes[read - 1];
+        }
+        read
+    }
+
+    // Fetches only the bytes that are missing, so nothing past the last
+    // byte used is taken from the input
+    fn fill(&mut self, count: u32) -> Option<()> {
+        while self.available() < count {
+            let missing = (count - self.available()).div_ceil(8);
+            if self.fetch(missing as usize) == 0 {
                 return None;
             }
-            self.buf = tmp_buf[0];
         }
+        Some(())
+    }
+
+    // Refills the buffer once fewer than PEEK_BITS are left. This may take up
+    // to 8 bytes past the code being decoded from the input, only the exact
+    // reads of read_bits and read_bit stop at the code.
+    fn refill(&mut self) {
+        if self.available() < PEEK_BITS {
+            self.fetch(((64 - self.len) / 8) as usize);
+        }
+    }
+
+    // The next bits MSB-first in the top of the word and how many of them
+    // there are, the rest are zeros
+    pub fn peek(&mut self) -> (u64, u32) {
+        self.refill();
+        (self.buf << self.n, self.available())
+    }
 
-        let bit = self.buf & 0x80 == 0x80;
-        self.buf <<= 1;
+    // Same for LSB-first codes, the next bit is the lowest one
+    pub fn peek_lsb(&mut self) -> (u64, u32) {
+        self.refill();
+        (self.buf.swap_bytes() >> self.n, self.available())
+    }
+
+    // Drops bits that were looked at with peek
+    pub fn consume(&mut self, count: u32) {
+        assert!(count <= self.available(), "Cannot consume bits that were not peeked");
+        self.n += count;
+        let whole = self.n / 8 * 8;
+        self.buf = self.buf.checked_shl(whole).unwrap_or(0);
+        self.len -= whole;
+        self.n -= whole;
+    }
+
+    // The read error that ended the input, if it did not end by itself
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.last_error.take()
+    }
+
+    // Number of bits consumed so far
+    pub fn position(&self) -> u64 {
+        self.fetched * 8 - self.available() as u64
+    }
+
+    // After hitting the end of input: whether everything read since start is
+    // MSB-first padding made of pad bits, i.e. the stream ended cleanly.
+    pub fn is_padding_from(&self, start: u64, pad: bool) -> bool {
+        let end = self.fetched * 8;
+        if start == end {
+            return true;
+        }
+        if end - start >= 8 {
+            return false;
+        }
+
+        let mask = 0xFF >> (start % 8);
+        self.last_byte & mask == if pad { mask } else { 0 }
+    }
+
+    // Reads count bits MSB-first
+    pub fn read_bits(&mut self, count: u32) -> Option<u64> {
+        assert!(count <= u64::BITS, "At most 64 bits can be read at once");
+        if count > MAX_FILL {
+            let high = self.read_bits(count - 32)?;
+            return Some(high << 32 | self.read_bits(32)?);
+        }
+        if count == 0 {
+            return Some(0);
+        }
+
+        self.fill(count)?;
+        let value = (self.buf << self.n) >> (u64::BITS - count);
+        self.consume(count);
+        Some(value)
+    }
+
+    pub fn read_bit(&mut self) -> Option<bool> {
+        if self.len == 0 {
+            self.fill(1)?;
+        }
+        let bit = self.buf << self.n >> 63 == 1;
+        self.skip_bit();
+        Some(bit)
+    }
+
+    // consume(1) without the checks, the buffer holds at least one bit
+    fn skip_bit(&mut self) {
         self.n += 1;
-        self.n %= 8;
+        if self.n == 8 {
+            self.buf <<= 8;
+            self.len -= 8;
+            self.n = 0;
+        }
+    }
+
+    pub fn read_byte(&mut self) -> Option<u8> {
+        self.read_bits(8).map(|byte| byte as u8)
+    }
 
+    pub fn read_bit_lsb(&mut self) -> Option<bool> {
+        if self.len == 0 {
+            self.fill(1)?;
+        }
+        let bit = self.buf >> (56 + self.n) & 1 == 1;
+        self.skip_bit();
         Some(bit)
     }
+
+    pub fn read_bits_lsb(&mut self, count: u32) -> Option<u32> {
+        assert!(count <= u32::BITS, "At most 32 bits can be read at once");
+        self.fill(count)?;
+
+        // The buffered bytes in reading order, the first one lowest
+        let bytes = u64::from_le_bytes(self.buf.to_be_bytes());
+        let value = (bytes >> self.n) & ((1 << count) - 1);
+        self.consume(count);
+        Some(value as u32)
+    }
+
+    pub fn align(&mut self) {
+        if self.n != 0 {
+            self.consume(8 - self.n);
+        }
+    }
+
+    pub fn read_bytes(&mut self, buf: &mut [u8]) -> Option<()> {
+        assert_eq!(self.n, 0, "Bytes can only be read on a byte boundary");
+        let buffered = buf.len().min(self.len as usize / 8);
+        let (head, rest) = buf.split_at_mut(buffered);
+        head.copy_from_slice(&self.buf.to_be_bytes()[..buffered]);
+        self.consume(buffered as u32 * 8);
+
+        self.input_stream.read_exact(rest).ok()?;
+        self.fetched += rest.len() as u64;
+        if let Some(&byte) = rest.last() {
+            self.last_byte = byte;
+        }
+        Some(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::universal_coder::{BitOutputStream, GammaCoder, UniversalCoder, DeltaCoder, OmegaCoder, FibonacciCoder};
+    use crate::universal_coder::{
+        AdaptiveRiceCoder, BinaryCoder, BitOutputStream, DeltaCoder, ExpGolombCoder, FibonacciCoder, GammaCoder, GolombCoder,
+        OmegaCoder, RiceCoder, UniversalCoder, CodeError,
+    };
 
     use super::*;
 
@@ -263,4 +863,371 @@ mod tests {
             eprintln!("{}", num);
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn binary() {
+        let mut stream = vec![];
+        let mut coder = BinaryCoder::new();
+
+        let mut bitoutput = BitOutputStream::new(&mut stream);
+
+        for i in 1..=300 {
+            coder.set_dict_size(255 + i);
+            coder.code_number(&mut bitoutput, i);
+        }
+        coder.pad(&mut bitoutput);
+
+        let mut decoder = BinaryDecoder::new();
+        let mut bitinput = BitInputStream::new(stream.as_slice());
+
+        for i in 1..=300 {
+            decoder.set_dict_size(255 + i);
+            assert_eq!(decoder.decode_number(&mut bitinput), Some(i));
+        }
+        decoder.set_dict_size(556);
+        assert_eq!(decoder.decode_number(&mut bitinput), None);
+    }
+
+    fn round_trip(mut coder: impl UniversalCoder, mut decoder: impl UniversalDecoder, numbers: &[usize]) -> Vec<u8> {
+        let mut stream = vec![];
+        let mut bitoutput = BitOutputStream::new(&mut stream);
+
+        for &number in numbers {
+            coder.code_number(&mut bitoutput, number);
+        }
+        coder.pad(&mut bitoutput);
+
+        let mut bitinput = BitInputStream::new(stream.as_slice());
+        for &number in numbers {
+            assert_eq!(decoder.decode_number(&mut bitinput), Some(number));
+        }
+        assert_eq!(decoder.decode_number(&mut bitinput), None);
+
+        stream
+    }
+
+    #[test]
+    fn golomb_family() {
+        let numbers = (1..=200).chain([1000, 5000, 1]).collect::<Vec<_>>();
+
+        for m in [1, 3, 5, 10, 16] {
+            let stream = round_trip(GolombCoder::new(m), GolombDecoder::new(m), &numbers);
+            println!("golomb {} coded len: {}", m, stream.len());
+        }
+        for k in [0, 2, 5] {
+            let stream = round_trip(RiceCoder::new(k), RiceDecoder::new(k), &numbers);
+            println!("rice {} coded len: {}", k, stream.len());
+            let stream = round_trip(ExpGolombCoder::new(k), ExpGolombDecoder::new(k), &numbers);
+            println!("exp-golomb {} coded len: {}", k, stream.len());
+        }
+        let stream = round_trip(AdaptiveRiceCoder::new(), AdaptiveRiceDecoder::new(), &numbers);
+        println!("adaptive rice coded len: {}", stream.len());
+
+        // Golomb m = 3: 0 -> 0|0, 1 -> 0|10, 2 -> 0|11, 3 -> 10|0
+        assert_eq!(round_trip(GolombCoder::new(3), GolombDecoder::new(3), &[1, 2, 3, 4]), [0b00010011, 0b10011111]);
+        // Exp-Golomb order 0 is gamma of number
+        assert_eq!(round_trip(ExpGolombCoder::new(0), ExpGolombDecoder::new(0), &[1, 2, 3]), [0b10100110]);
+    }
+
+    fn random_values(count: usize) -> Vec<u64> {
+        let mut state = 0x2545_F491_4F6C_DD1Du64;
+        let mut values = vec![0, 1, 2, u64::MAX - 1, u64::MAX];
+
+        for _ in 0..count {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            // Spread the values over every bit length
+            values.push(state >> (state % 64));
+        }
+        values
+    }
+
+    fn round_trip_u64(mut coder: impl UniversalCoder, mut decoder: impl UniversalDecoder, values: &[u64]) -> usize {
+        let mut stream = vec![];
+        let mut bitoutput = BitOutputStream::new(&mut stream);
+
+        for &value in values {
+            coder.code_u64(&mut bitoutput, value).unwrap();
+        }
+        coder.pad(&mut bitoutput);
+
+        let mut bitinput = BitInputStream::new(stream.as_slice());
+        for &value in values {
+            assert_eq!(decoder.decode_u64(&mut bitinput), Ok(value));
+        }
+        assert_eq!(decoder.decode_u64(&mut bitinput), Err(DecodeError::EndOfStream));
+
+        stream.len()
+    }
+
+    #[test]
+    fn full_range() {
+        let values = random_values(2000);
+
+        println!("gamma: {}", round_trip_u64(GammaCoder, GammaDecoder, &values));
+        println!("delta: {}", round_trip_u64(DeltaCoder, DeltaDecoder, &values));
+        println!("omega: {}", round_trip_u64(OmegaCoder, OmegaDecoder, &values));
+        println!("fib: {}", round_trip_u64(FibonacciCoder::new(), FibonacciDecoder::new(), &values));
+        println!("exp-golomb: {}", round_trip_u64(ExpGolombCoder::new(3), ExpGolombDecoder::new(3), &values));
+        // Unary quotients stay short only when the parameter is close to the range
+        println!("rice: {}", round_trip_u64(RiceCoder::new(60), RiceDecoder::new(60), &values));
+        let m = (1 << 62) + 12345;
+        println!("golomb: {}", round_trip_u64(GolombCoder::new(m), GolombDecoder::new(m), &values));
+
+        let mut coder = BinaryCoder::new();
+        coder.set_dict_size(usize::MAX);
+        let mut decoder = BinaryDecoder::new();
+        decoder.set_dict_size(usize::MAX);
+        println!("binary: {}", round_trip_u64(coder, decoder, &values));
+    }
+
+    #[test]
+    fn u128_extremes() {
+        for number in [1, u64::MAX as u128 + 1, u128::MAX / 3, u128::MAX] {
+            let mut stream = vec![];
+            let mut bitoutput = BitOutputStream::new(&mut stream);
+            GammaCoder.code_positive(&mut bitoutput, number).unwrap();
+            OmegaCoder.code_positive(&mut bitoutput, number).unwrap();
+            FibonacciCoder::new().code_positive(&mut bitoutput, number).unwrap();
+            ExpGolombCoder::new(0).code_positive(&mut bitoutput, number).unwrap();
+            bitoutput.pad_with_zeros();
+
+            let mut bitinput = BitInputStream::new(stream.as_slice());
+            assert_eq!(GammaDecoder.decode_positive(&mut bitinput), Ok(number));
+            assert_eq!(OmegaDecoder.decode_positive(&mut bitinput), Ok(number));
+            assert_eq!(FibonacciDecoder::new().decode_positive(&mut bitinput), Ok(number));
+            assert_eq!(ExpGolombDecoder::new(0).decode_positive(&mut bitinput), Ok(number));
+        }
+
+        let mut stream = vec![];
+        let mut bitoutput = BitOutputStream::new(&mut stream);
+        GammaCoder.code_positive(&mut bitoutput, u128::MAX).unwrap();
+        bitoutput.pad_with_zeros();
+        let mut bitinput = BitInputStream::new(stream.as_slice());
+        assert_eq!(GammaDecoder.decode_u64(&mut bitinput), Err(DecodeError::Overflow));
+    }
+
+    #[test]
+    fn errors() {
+        let mut stream = vec![];
+        let mut bitoutput = BitOutputStream::new(&mut stream);
+        assert_eq!(GammaCoder.code_positive(&mut bitoutput, 0), Err(CodeError::Zero));
+        assert_eq!(BinaryCoder::new().code_positive(&mut bitoutput, 1000), Err(CodeError::OutOfRange));
+        assert_eq!(ExpGolombCoder::new(4).code_positive(&mut bitoutput, u128::MAX), Err(CodeError::OutOfRange));
+        // Unary parts stay bounded with small parameters
+        assert_eq!(RiceCoder::new(0).code_u64(&mut bitoutput, u64::MAX), Err(CodeError::OutOfRange));
+        assert_eq!(RiceCoder::new(3).code_u64(&mut bitoutput, (MAX_UNARY as u64 + 1) << 3), Err(CodeError::OutOfRange));
+        assert_eq!(GolombCoder::new(1).code_u64(&mut bitoutput, u64::MAX), Err(CodeError::OutOfRange));
+        assert_eq!(GolombCoder::new(5).code_positive(&mut bitoutput, u128::MAX), Err(CodeError::OutOfRange));
+        assert_eq!(AdaptiveRiceCoder::new().code_u64(&mut bitoutput, u64::MAX), Err(CodeError::OutOfRange));
+        assert_eq!(bitoutput.written_bits(), 0);
+
+        // The largest quotient still codes, one more is rejected by the decoder
+        let mut unary = vec![];
+        let mut unary_output = BitOutputStream::new(&mut unary);
+        RiceCoder::new(0).code_u64(&mut unary_output, MAX_UNARY as u64).unwrap();
+        RiceCoder::new(0).pad(&mut unary_output);
+        let mut bitinput = BitInputStream::new(unary.as_slice());
+        assert_eq!(RiceDecoder::new(0).decode_u64(&mut bitinput), Ok(MAX_UNARY as u64));
+        let ones = vec![0xFF; MAX_UNARY as usize / 8 + 2];
+        let mut bitinput = BitInputStream::new(ones.as_slice());
+        assert_eq!(GolombDecoder::new(1).decode_positive(&mut bitinput), Err(DecodeError::Overflow));
+
+        GammaCoder.code_positive(&mut bitoutput, 1000).unwrap();
+        GammaCoder.pad(&mut bitoutput);
+        let mut bitinput = BitInputStream::new(&stream[..2]);
+        assert_eq!(GammaDecoder.decode_positive(&mut bitinput), Err(DecodeError::Truncated));
+
+        let zeros = [0; 17];
+        let mut bitinput = BitInputStream::new(zeros.as_slice());
+        assert_eq!(GammaDecoder.decode_positive(&mut bitinput), Err(DecodeError::Overflow));
+
+        let ones = [0xFF; 40];
+        let mut bitinput = BitInputStream::new(ones.as_slice());
+        assert_eq!(FibonacciDecoder::new().decode_positive(&mut bitinput), Ok(1));
+        let mut bitinput = BitInputStream::new(ones.as_slice());
+        assert_eq!(OmegaDecoder.decode_positive(&mut bitinput), Err(DecodeError::Overflow));
+
+        let empty: [u8; 0] = [];
+        let mut bitinput = BitInputStream::new(empty.as_slice());
+        assert_eq!(DeltaDecoder.decode_positive(&mut bitinput), Err(DecodeError::EndOfStream));
+    }
+
+    #[test]
+    fn bit_io_words() {
+        let widths = (0..=64).chain([1, 7, 57, 3, 64, 0, 13]).collect::<Vec<u32>>();
+        let value = |i: usize| 0x9E37_79B9_7F4A_7C15u64.rotate_left(i as u32 * 7);
+        let mask = |width: u32| if width == 0 { 0 } else { u64::MAX >> (64 - width) };
+
+        let mut stream = vec![];
+        let mut bitoutput = BitOutputStream::new(&mut stream);
+        for (i, &width) in widths.iter().enumerate() {
+            bitoutput.write_bits(value(i), width);
+        }
+        bitoutput.pad_with_zeros();
+
+        // Same bits one at a time
+        let mut by_bit = vec![];
+        let mut bitoutput = BitOutputStream::new(&mut by_bit);
+        for (i, &width) in widths.iter().enumerate() {
+            for bit in (0..width).rev() {
+                bitoutput.write_bit(value(i) >> bit & 1 == 1);
+            }
+        }
+        bitoutput.pad_with_zeros();
+        assert_eq!(stream, by_bit);
+
+        let mut bitinput = BitInputStream::new(stream.as_slice());
+        let mut position = 0;
+        for (i, &width) in widths.iter().enumerate() {
+            let (bits, available) = bitinput.peek();
+            if width <= available {
+                assert_eq!(bits.checked_shr(64 - width).unwrap_or(0), value(i) & mask(width));
+            }
+            assert_eq!(bitinput.read_bits(width), Some(value(i) & mask(width)));
+            position += width as u64;
+            assert_eq!(bitinput.position(), position);
+        }
+        let padding = 8 - position % 8;
+        assert_eq!(bitinput.read_bits(padding as u32), Some(0));
+        assert_eq!(bitinput.read_bits(1), None);
+
+        // Plain reads take nothing past the last byte used, gzip and zlib
+        // read their trailers from the same input
+        let mut input = [0b1011_0000, 0xAA, 0xBB, 0xCC].as_slice();
+        let mut bitinput = BitInputStream::new(&mut input);
+        assert_eq!(bitinput.read_bits(4), Some(0b1011));
+        assert_eq!(bitinput.read_bits(8), Some(0x0A));
+        bitinput.align();
+        assert_eq!(input, [0xBB, 0xCC]);
+
+        let mut input = [0b1011_0000, 0xAA, 0xBB, 0xCC].as_slice();
+        let mut bitinput = BitInputStream::new(&mut input);
+        assert_eq!(bitinput.read_bits_lsb(12), Some(0xAB0));
+        bitinput.align();
+        assert_eq!(input, [0xBB, 0xCC]);
+    }
+
+    #[test]
+    fn short_codes() {
+        // Fibonacci 1 -> 11, 2 -> 011, 3 -> 0011, 4 -> 1011
+        assert_eq!(round_trip(FibonacciCoder::new(), FibonacciDecoder::new(), &[1, 2, 3, 4]), [0b11011001, 0b11011000]);
+        // Gamma 1 -> 1, 2 -> 010, 5 -> 00101
+        assert_eq!(round_trip(GammaCoder, GammaDecoder, &[1, 2, 5]), [0b10100010, 0b10000000]);
+
+        // Every number coded from the tables, across byte boundaries
+        let numbers = (1..=10_000).collect::<Vec<_>>();
+        round_trip(FibonacciCoder::new(), FibonacciDecoder::new(), &numbers);
+        round_trip(GammaCoder, GammaDecoder, &numbers);
+        for offset in 1..8 {
+            let mut shifted = vec![1; offset];
+            shifted.extend_from_slice(&numbers[..500]);
+            round_trip(FibonacciCoder::new(), FibonacciDecoder::new(), &shifted);
+        }
+    }
+
+    fn time_codes(name: &str, mut coder: impl UniversalCoder, mut decoder: impl UniversalDecoder, numbers: &[usize]) {
+        let start = std::time::Instant::now();
+        let mut stream = vec![];
+        let mut bitoutput = BitOutputStream::new(&mut stream);
+        for &number in numbers {
+            coder.code_number(&mut bitoutput, number);
+        }
+        coder.pad(&mut bitoutput);
+        let coding = start.elapsed().as_secs_f64();
+
+        let start = std::time::Instant::now();
+        let mut bitinput = BitInputStream::new(stream.as_slice());
+        for &number in numbers {
+            assert_eq!(decoder.decode_number(&mut bitinput), Some(number));
+        }
+        let decoding = start.elapsed().as_secs_f64();
+
+        let millions = numbers.len() as f64 / 1_000_000.0;
+        println!("{}: coding {:.1} M/s, decoding {:.1} M/s", name, millions / coding, millions / decoding);
+    }
+
+    #[test]
+    fn universal_speed() {
+        // Dictionary indices as LZW codes them
+        let mut state = 0x2545_F491_4F6C_DD1Du64;
+        let numbers = (0..200_000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state % 65536) as usize + 1
+            })
+            .collect::<Vec<_>>();
+
+        time_codes("gamma", GammaCoder, GammaDecoder, &numbers);
+        time_codes("delta", DeltaCoder, DeltaDecoder, &numbers);
+        time_codes("omega", OmegaCoder, OmegaDecoder, &numbers);
+        time_codes("fib", FibonacciCoder::new(), FibonacciDecoder::new(), &numbers);
+        time_codes("rice:12", RiceCoder::new(12), RiceDecoder::new(12), &numbers);
+        time_codes("rice:6", RiceCoder::new(6), RiceDecoder::new(6), &numbers[..20_000]);
+        time_codes("expgolomb:3", ExpGolombCoder::new(3), ExpGolombDecoder::new(3), &numbers);
+        let mut coder = BinaryCoder::new();
+        coder.set_dict_size(65536);
+        let mut decoder = BinaryDecoder::new();
+        decoder.set_dict_size(65536);
+        time_codes("binary", coder, decoder, &numbers);
+    }
+
+    #[test]
+    fn peek_reads_ahead() {
+        // Gamma of 5 in the first byte, then whatever follows the stream
+        let stream = [0b00101000, 1, 2, 3, 4, 5, 6, 7, 8, 9];
+
+        let mut rest = stream.as_slice();
+        let mut bitinput = BitInputStream::new(&mut rest);
+        assert_eq!(bitinput.read_bits(5), Some(0b00101));
+        assert_eq!(bitinput.read_bit(), Some(false));
+        assert_eq!(bitinput.position(), 6);
+        assert_eq!(rest.len(), stream.len() - 1);
+
+        let mut rest = stream.as_slice();
+        let mut bitinput = BitInputStream::new(&mut rest);
+        assert_eq!(GammaDecoder.decode_positive(&mut bitinput), Ok(5));
+        bitinput.align();
+        assert_eq!(bitinput.position(), 8);
+        let mut next = [0];
+        bitinput.read_bytes(&mut next).unwrap();
+        assert_eq!(next, [1]);
+        assert!(rest.len() < stream.len() - 2);
+
+        // Single bits come from the buffer in both orders
+        let mut bitinput = BitInputStream::new(stream.as_slice());
+        let msb = (0..16).map(|_| bitinput.read_bit().unwrap() as u64).fold(0, |value, bit| value << 1 | bit);
+        assert_eq!(msb, 0b00101000_00000001);
+        let mut bitinput = BitInputStream::new(stream.as_slice());
+        let lsb = (0..16).map(|i| (bitinput.read_bit_lsb().unwrap() as u32) << i).sum::<u32>();
+        assert_eq!(lsb, 0x0128);
+        assert_eq!(bitinput.read_bits_lsb(8), Some(2));
+    }
+
+    #[test]
+    fn lsb_first() {
+        let mut stream = vec![];
+        let mut bitoutput = BitOutputStream::new(&mut stream);
+
+        bitoutput.write_bits_lsb(0b101, 3);
+        bitoutput.write_bits_lsb(0x1234, 16);
+        bitoutput.align_lsb();
+        bitoutput.write_bytes(&[0xAB]);
+        bitoutput.write_bits_lsb(1, 1);
+        bitoutput.align_lsb();
+
+        assert_eq!(stream, [0xA5, 0x91, 0x00, 0xAB, 0x01]);
+
+        let mut bitinput = BitInputStream::new(stream.as_slice());
+        assert_eq!(bitinput.read_bits_lsb(3), Some(0b101));
+        assert_eq!(bitinput.read_bits_lsb(16), Some(0x1234));
+        bitinput.align();
+        let mut byte = [0];
+        bitinput.read_bytes(&mut byte).unwrap();
+        assert_eq!(byte, [0xAB]);
+        assert_eq!(bitinput.read_bit_lsb(), Some(true));
+    }
+}
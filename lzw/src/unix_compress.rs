@@ -0,0 +1,511 @@
+use std::{
+    fmt,
+    io::{self, Read, Write},
+};
+
+use crate::{dict_policy::RATIO_CHECK_GAP, prefix_tree::PrefixTree, CLEAR_CODE};
+
+pub const Z_MAGIC: [u8; 2] = [0x1F, 0x9D];
+
+pub const BLOCK_MODE: u8 = 0x80;
+
+pub const INIT_BITS: u32 = 9;
+
+pub const MAX_BITS: u32 = 16;
+
+const BITS_MASK: u8 = 0x1F;
+
+const FIRST_CODE: usize = 257;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZError {
+    InvalidMagic,
+    UnsupportedBits(u32),
+    InvalidCode(usize),
+    Io(io::ErrorKind),
+}
+
+impl fmt::Display for ZError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ZError::InvalidMagic => write!(f, "not in compress (.Z) format"),
+            ZError::UnsupportedBits(bits) => write!(f, "unsupported max bits {}", bits),
+            ZError::InvalidCode(code) => write!(f, "invalid code {}", code),
+            ZError::Io(kind) => write!(f, "io error: {}", kind),
+        }
+    }
+}
+
+impl std::error::Error for ZError {}
+
+impl From<io::Error> for ZError {
+    fn from(err: io::Error) -> Self {
+        ZError::Io(err.kind())
+    }
+}
+
+fn max_code(n_bits: u32) -> usize {
+    (1 << n_bits) - 1
+}
+
+// Mirrors compress: the limit only switches to the full table size after a
+// width bump, so with 9 max bits the codes still grow to 10 bits.
+fn grown_max_code(n_bits: u32, max_bits: u32) -> usize {
+    if n_bits == max_bits {
+        1 << max_bits
+    } else {
+        max_code(n_bits)
+    }
+}
+
+// compress writes codes in groups of n_bits bytes (8 codes) and pads the rest
+// of a group whenever the code width changes or the table is cleared.
+struct CodeWriter<O> {
+    output_stream: O,
+    buf: [u8; MAX_BITS as usize],
+    offset: u32,
+    n_bits: u32,
+    max_code: usize,
+    written_bytes: usize,
+}
+
+impl<O: Write> CodeWriter<O> {
+    fn new(output_stream: O) -> Self {
+        Self {
+            output_stream,
+            buf: [0; MAX_BITS as usize],
+            offset: 0,
+            n_bits: INIT_BITS,
+            max_code: max_code(INIT_BITS),
+            written_bytes: 0,
+        }
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.output_stream.write_all(bytes).unwrap();
+        self.written_bytes += bytes.len();
+    }
+
+    fn write_code(&mut self, code: usize) {
+        for i in 0..self.n_bits {
+            let pos = self.offset + i;
+            let bit = ((code >> i) & 1) as u8;
+            self.buf[(pos / 8) as usize] |= bit << (pos % 8);
+        }
+        self.offset += self.n_bits;
+
+        if self.offset == self.n_bits * 8 {
+            self.flush_group();
+        }
+    }
+
+    fn flush_group(&mut self) {
+        if self.offset > 0 {
+            let group = self.buf;
+            self.write_bytes(&group[..self.n_bits as usize]);
+            self.buf = [0; MAX_BITS as usize];
+            self.offset = 0;
+        }
+    }
+
+    fn set_bits(&mut self, n_bits: u32, max_code: usize) {
+        self.flush_group();
+        self.n_bits = n_bits;
+        self.max_code = max_code;
+    }
+
+    fn finish(&mut self) {
+        let group = self.buf;
+        self.write_bytes(&group[..self.offset.div_ceil(8) as usize]);
+        self.buf = [0; MAX_BITS as usize];
+        self.offset = 0;
+        self.output_stream.flush().unwrap();
+    }
+}
+
+pub struct ZCoder<I, O> {
+    input_stream: I,
+    output_stream: CodeWriter<O>,
+    saved_byte: Option<u8>,
+    pending_entry: Option<(usize, u8)>,
+    tree: PrefixTree,
+    max_bits: u32,
+    best_ratio: f64,
+    next_ratio_check: usize,
+
+    input_len: usize,
+}
+
+impl<I: Read, O: Write> ZCoder<I, O> {
+    pub fn new(input_stream: I, output_stream: O) -> Self {
+        Self::with_max_bits(input_stream, output_stream, MAX_BITS)
+    }
+
+    pub fn with_max_bits(input_stream: I, output_stream: O, max_bits: u32) -> Self {
+        assert!(
+            (INIT_BITS..=MAX_BITS).contains(&max_bits),
+            "Max bits has to be between {} and {}",
+            INIT_BITS,
+            MAX_BITS
+        );
+
+        Self {
+            input_stream,
+            output_stream: CodeWriter::new(output_stream),
+            saved_byte: None,
+            pending_entry: None,
+            tree: PrefixTree::with_size(FIRST_CODE, 1 << max_bits),
+            max_bits,
+            best_ratio: 0.0,
+            next_ratio_check: RATIO_CHECK_GAP,
+            input_len: 0,
+        }
+    }
+
+    pub fn code_to_end(&mut self) {
+        self.output_stream.write_bytes(&Z_MAGIC);
+        self.output_stream.write_bytes(&[self.max_bits as u8 | BLOCK_MODE]);
+
+        while let Some(code) = self.code() {
+            // The new entry only becomes visible to the decoder after this code,
+            // so the width check in emit has to see the table without it.
+            self.emit(code);
+            if let Some((from, byte)) = self.pending_entry.take() {
+                self.tree.append(from, byte);
+            }
+
+            if self.tree.is_full() && self.input_len >= self.next_ratio_check {
+                self.check_ratio();
+            }
+        }
+        self.output_stream.finish();
+    }
+
+    pub fn get_stat(&self) -> (usize, usize) {
+        (self.input_len, self.output_stream.written_bytes)
+    }
+
+    fn code(&mut self) -> Option<usize> {
+        let start = self.read()?;
+        let mut current_node = start as usize;
+
+        while let Some(byte) = self.read() {
+            if let Some(next_node) = self.tree.travel(current_node, byte) {
+                current_node = next_node;
+            } else {
+                self.save_byte(byte);
+                self.pending_entry = Some((current_node, byte));
+                break;
+            }
+        }
+
+        Some(current_node)
+    }
+
+    fn emit(&mut self, code: usize) {
+        self.output_stream.write_code(code);
+
+        if self.tree.len() > self.output_stream.max_code {
+            let n_bits = self.output_stream.n_bits + 1;
+            self.output_stream.set_bits(n_bits, grown_max_code(n_bits, self.max_bits));
+        }
+    }
+
+    fn check_ratio(&mut self) {
+        self.next_ratio_check = self.input_len + RATIO_CHECK_GAP;
+
+        let ratio = self.input_len as f64 / self.output_stream.written_bytes.max(1) as f64;
+        if ratio > self.best_ratio {
+            self.best_ratio = ratio;
+        } else {
+            self.best_ratio = 0.0;
+            self.output_stream.write_code(CLEAR_CODE);
+            self.output_stream.set_bits(INIT_BITS, max_code(INIT_BITS));
+            self.tree = PrefixTree::with_size(FIRST_CODE, 1 << self.max_bits);
+        }
+    }
+
+    fn save_byte(&mut self, byte: u8) {
+        self.saved_byte = Some(byte)
+    }
+
+    fn read(&mut self) -> Option<u8> {
+        if self.saved_byte.is_some() {
+            self.saved_byte.take()
+        } else {
+            self.read_new_byte()
+        }
+    }
+
+    fn read_new_byte(&mut self) -> Option<u8> {
+        let mut buf = [0];
+        self.input_stream
+            .read_exact(&mut buf)
+            .ok()
+            .map(|_| {
+                self.input_len += 1;
+                buf[0]
+            })
+    }
+}
+
+struct CodeReader<I> {
+    input_stream: I,
+    buf: [u8; MAX_BITS as usize],
+    offset: u32,
+    size: u32,
+    n_bits: u32,
+    max_code: usize,
+    read_bytes: usize,
+}
+
+impl<I: Read> CodeReader<I> {
+    fn new(input_stream: I) -> Self {
+        Self {
+            input_stream,
+            buf: [0; MAX_BITS as usize],
+            offset: 0,
+            size: 0,
+            n_bits: INIT_BITS,
+            max_code: max_code(INIT_BITS),
+            read_bytes: 0,
+        }
+    }
+
+    fn read_bytes(&mut self, buf: &mut [u8]) -> Result<usize, ZError> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            match self.input_stream.read(&mut buf[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+        self.read_bytes += filled;
+        Ok(filled)
+    }
+
+    fn set_bits(&mut self, n_bits: u32, max_code: usize) {
+        self.n_bits = n_bits;
+        self.max_code = max_code;
+        self.size = 0;
+        self.offset = 0;
+    }
+
+    fn read_code(&mut self) -> Result<Option<usize>, ZError> {
+        if self.offset + self.n_bits > self.size {
+            let mut group = [0; MAX_BITS as usize];
+            let len = self.read_bytes(&mut group[..self.n_bits as usize])?;
+            self.buf = group;
+            self.offset = 0;
+            self.size = len as u32 * 8;
+
+            if self.size < self.n_bits {
+                return Ok(None);
+            }
+        }
+
+        let mut code = 0;
+        for i in 0..self.n_bits {
+            let pos = self.offset + i;
+            let bit = (self.buf[(pos / 8) as usize] >> (pos % 8)) & 1;
+            code |= (bit as usize) << i;
+        }
+        self.offset += self.n_bits;
+
+        Ok(Some(code))
+    }
+}
+
+pub struct ZDecoder<I, O> {
+    input_stream: CodeReader<I>,
+    output_stream: O,
+    max_bits: u32,
+    block_mode: bool,
+    prefix: Vec<usize>,
+    suffix: Vec<u8>,
+    stack: Vec<u8>,
+
+    output_len: usize,
+}
+
+impl<I: Read, O: Write> ZDecoder<I, O> {
+    pub fn new(input_stream: I, output_stream: O) -> Self {
+        Self {
+            input_stream: CodeReader::new(input_stream),
+            output_stream,
+            max_bits: MAX_BITS,
+            block_mode: true,
+            prefix: vec![],
+            suffix: vec![],
+            stack: vec![],
+            output_len: 0,
+        }
+    }
+
+    pub fn decode_to_end(&mut self) -> Result<(), ZError> {
+        self.read_header()?;
+
+        let first_free = if self.block_mode { FIRST_CODE } else { CLEAR_CODE };
+        self.reset_dict(first_free);
+
+        let mut last_code = None;
+        while let Some(code) = self.input_stream.read_code()? {
+            if self.block_mode && code == CLEAR_CODE {
+                self.reset_dict(first_free);
+                self.input_stream.set_bits(INIT_BITS, max_code(INIT_BITS));
+                last_code = None;
+                continue;
+            }
+
+            let free_code = self.prefix.len();
+            let Some(last) = last_code else {
+                if code > 255 {
+                    return Err(ZError::InvalidCode(code));
+                }
+                self.write(&[code as u8])?;
+                last_code = Some(code);
+                continue;
+            };
+
+            if code > free_code {
+                return Err(ZError::InvalidCode(code));
+            }
+
+            self.stack.clear();
+            let mut current = if code == free_code {
+                self.stack.push(self.first_byte(last));
+                last
+            } else {
+                code
+            };
+            while current > 255 {
+                self.stack.push(self.suffix[current]);
+                current = self.prefix[current];
+            }
+            self.stack.push(current as u8);
+            self.stack.reverse();
+
+            if free_code < 1 << self.max_bits {
+                self.prefix.push(last);
+                self.suffix.push(self.stack[0]);
+            }
+
+            let entry = std::mem::take(&mut self.stack);
+            self.write(&entry)?;
+            self.stack = entry;
+            last_code = Some(code);
+
+            if self.prefix.len() > self.input_stream.max_code {
+                let n_bits = self.input_stream.n_bits + 1;
+                self.input_stream.set_bits(n_bits, grown_max_code(n_bits, self.max_bits));
+            }
+        }
+
+        self.output_stream.flush()?;
+        Ok(())
+    }
+
+    pub fn get_stat(&self) -> (usize, usize) {
+        (self.input_stream.read_bytes, self.output_len)
+    }
+
+    fn read_header(&mut self) -> Result<(), ZError> {
+        let mut header = [0; 3];
+        if self.input_stream.read_bytes(&mut header)? < header.len() || header[..2] != Z_MAGIC {
+            return Err(ZError::InvalidMagic);
+        }
+
+        self.max_bits = (header[2] & BITS_MASK) as u32;
+        self.block_mode = header[2] & BLOCK_MODE != 0;
+        if !(INIT_BITS..=MAX_BITS).contains(&self.max_bits) {
+            return Err(ZError::UnsupportedBits(self.max_bits));
+        }
+
+        Ok(())
+    }
+
+    fn reset_dict(&mut self, first_free: usize) {
+        self.prefix = (0..first_free).collect();
+        self.suffix = (0..first_free).map(|i| i as u8).collect();
+    }
+
+    fn first_byte(&self, mut code: usize) -> u8 {
+        while code > 255 {
+            code = self.prefix[code];
+        }
+        code as u8
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), ZError> {
+        self.output_stream.write_all(bytes)?;
+        self.output_len += bytes.len();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // "TOBEORNOTTOBEORTOBEORNOT" with 12 max bits, checked against gzip -d
+    const TOBEORNOT_Z: [u8; 21] = [
+        0x1F, 0x9D, 0x8C, 0x54, 0x9E, 0x08, 0x29, 0xF2, 0x44, 0x8A, 0x93, 0x27, 0x54, 0x02, 0x0E, 0x2C, 0xA8,
+        0x90, 0xA0, 0x41, 0x84,
+    ];
+
+    #[test]
+    fn fixture() {
+        let mut output_buf = vec![];
+        let mut decoder = ZDecoder::new(TOBEORNOT_Z.as_slice(), &mut output_buf);
+        decoder.decode_to_end().unwrap();
+        assert_eq!(output_buf, b"TOBEORNOTTOBEORTOBEORNOT");
+
+        let mut coded_buf = vec![];
+        let mut coder = ZCoder::with_max_bits(b"TOBEORNOTTOBEORTOBEORNOT".as_slice(), &mut coded_buf, 12);
+        coder.code_to_end();
+        assert_eq!(coded_buf, TOBEORNOT_Z);
+    }
+
+    // Old style stream without block mode, so code 256 is a regular entry ("AB")
+    const NO_BLOCK_MODE_Z: [u8; 8] = [0x1F, 0x9D, 0x0C, 0x41, 0x84, 0x00, 0x14, 0x02];
+
+    #[test]
+    fn fixture_no_block_mode() {
+        let mut output_buf = vec![];
+        let mut decoder = ZDecoder::new(NO_BLOCK_MODE_Z.as_slice(), &mut output_buf);
+        decoder.decode_to_end().unwrap();
+        assert_eq!(output_buf, b"ABABB");
+    }
+
+    #[test]
+    fn round_trip() {
+        let buf = include_bytes!("./../../testy/pan_tadeusz.txt");
+
+        for max_bits in [9, 12, 16] {
+            let mut coded_buf = vec![];
+            let mut output_buf = vec![];
+
+            let mut coder = ZCoder::with_max_bits(buf.as_slice(), &mut coded_buf, max_bits);
+            coder.code_to_end();
+
+            println!("max bits {} coded len: {}", max_bits, coded_buf.len());
+
+            let mut decoder = ZDecoder::new(coded_buf.as_slice(), &mut output_buf);
+            decoder.decode_to_end().unwrap();
+
+            assert_eq!(buf.as_slice(), output_buf);
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        let mut output_buf = vec![];
+        let mut decoder = ZDecoder::new([0x1F, 0x8B, 0x08].as_slice(), &mut output_buf);
+        assert_eq!(decoder.decode_to_end(), Err(ZError::InvalidMagic));
+
+        let mut decoder = ZDecoder::new([0x1F, 0x9D, 0x91].as_slice(), &mut output_buf);
+        assert_eq!(decoder.decode_to_end(), Err(ZError::UnsupportedBits(17)));
+    }
+}
@@ -0,0 +1,424 @@
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    io::{Read, Write},
+};
+
+use crate::{
+    decoder::LzwError,
+    header::StreamHeader,
+    prefix_tree::PrefixTree,
+    universal_coder::{BitOutputStream, UniversalCoder},
+    universal_decoder::{BitInputStream, DecodeError, UniversalDecoder},
+    MAX_DICT_SIZE,
+};
+
+// Code of the escape that introduces a token seen for the first time
+pub const ESCAPE_CODE: usize = 0;
+
+// Longer runs are split, so token lengths fit any coder's range
+pub const MAX_TOKEN_LEN: usize = 255;
+
+const NO_PARENT: u32 = u32::MAX;
+
+// Letters, digits and everything outside ASCII (UTF-8 letters) make up words
+fn is_word_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte >= 0x80
+}
+
+// Splits data into alternating runs of word and separator bytes
+pub fn tokenize(data: &[u8]) -> Vec<&[u8]> {
+    let mut tokens = vec![];
+    let mut rest = data;
+
+    while let Some(&first) = rest.first() {
+        let len = rest
+            .iter()
+            .take(MAX_TOKEN_LEN)
+            .take_while(|&&byte| is_word_byte(byte) == is_word_byte(first))
+            .count();
+        let (token, tail) = rest.split_at(len);
+        tokens.push(token);
+        rest = tail;
+    }
+
+    tokens
+}
+
+// Counts the tokens of tokenize one byte at a time, for inputs that are not
+// kept in memory
+#[derive(Debug, Default)]
+pub struct TokenCounter {
+    token: Vec<u8>,
+    count: usize,
+    distinct: HashSet<Vec<u8>>,
+}
+
+impl TokenCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, byte: u8) {
+        if let Some(&first) = self.token.first() {
+            if self.token.len() == MAX_TOKEN_LEN || is_word_byte(byte) != is_word_byte(first) {
+                self.end_token();
+            }
+        }
+        self.token.push(byte);
+    }
+
+    // Token count and the number of distinct tokens
+    pub fn finish(mut self) -> (usize, usize) {
+        if !self.token.is_empty() {
+            self.end_token();
+        }
+        (self.count, self.distinct.len())
+    }
+
+    fn end_token(&mut self) {
+        self.count += 1;
+        if !self.distinct.contains(&self.token) {
+            self.distinct.insert(self.token.clone());
+        }
+        self.token.clear();
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct WordEntry {
+    parent: u32,
+    token: u32,
+}
+
+// Dictionary of token sequences plus the byte level dictionary used to spell
+// new tokens. The coder and the decoder update it in the same order.
+pub struct WordDict {
+    max_size: usize,
+    entries: Vec<WordEntry>,
+    children: HashMap<(u32, u32), u32>,
+    token_codes: Vec<u32>,
+    tokens: Vec<Vec<u8>>,
+    lexicon: PrefixTree,
+    last_spelled: Option<usize>,
+}
+
+impl WordDict {
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            max_size,
+            entries: vec![WordEntry {
+                parent: NO_PARENT,
+                token: 0,
+            }],
+            children: HashMap::new(),
+            token_codes: vec![],
+            tokens: vec![],
+            // Bounded like the token dictionary, so it does not grow with the input
+            lexicon: PrefixTree::with_size(256, max_size.clamp(256, MAX_DICT_SIZE)),
+            last_spelled: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn token_count(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn lexicon_len(&self) -> usize {
+        self.lexicon.len()
+    }
+
+    // Tokens get an entry of their own even in a full dictionary, otherwise
+    // they could not be coded again
+    pub fn add_token(&mut self, bytes: &[u8]) -> usize {
+        let code = self.entries.len();
+        self.entries.push(WordEntry {
+            parent: NO_PARENT,
+            token: self.tokens.len() as u32,
+        });
+        self.token_codes.push(code as u32);
+        self.tokens.push(bytes.to_vec());
+        code
+    }
+
+    // Longest entry at the start of ids, as (code, number of tokens)
+    pub fn longest_match(&self, ids: &[u32]) -> (usize, usize) {
+        let mut code = self.token_codes[ids[0] as usize];
+        let mut len = 1;
+
+        for &id in &ids[1..] {
+            let Some(&next) = self.children.get(&(code, id)) else {
+                break;
+            };
+            code = next;
+            len += 1;
+        }
+
+        (code as usize, len)
+    }
+
+    fn first_token(&self, mut code: usize) -> u32 {
+        while self.entries[code].parent != NO_PARENT {
+            code = self.entries[code].parent as usize;
+        }
+        self.entries[code].token
+    }
+
+    // Previous phrase plus the first token of the current one, as in LZW
+    pub fn extend(&mut self, previous: usize, current: usize) {
+        if self.entries.len() >= self.max_size {
+            return;
+        }
+
+        let token = self.first_token(current);
+        if let Entry::Vacant(slot) = self.children.entry((previous as u32, token)) {
+            slot.insert(self.entries.len() as u32);
+            self.entries.push(WordEntry {
+                parent: previous as u32,
+                token,
+            });
+        }
+    }
+
+    pub fn phrase(&self, mut code: usize, buf: &mut Vec<u8>) {
+        let mut tokens = vec![self.entries[code].token];
+        while self.entries[code].parent != NO_PARENT {
+            code = self.entries[code].parent as usize;
+            tokens.push(self.entries[code].token);
+        }
+
+        buf.clear();
+        for &token in tokens.iter().rev() {
+            buf.extend_from_slice(&self.tokens[token as usize]);
+        }
+    }
+
+    // Longest lexicon entry at the start of bytes, as (code, length)
+    fn lexicon_match(&self, bytes: &[u8]) -> (usize, usize) {
+        let mut node = bytes[0] as usize;
+        let mut len = 1;
+
+        for &byte in &bytes[1..] {
+            let Some(next) = self.lexicon.travel(node, byte) else {
+                break;
+            };
+            node = next;
+            len += 1;
+        }
+
+        (node, len)
+    }
+
+    fn extend_lexicon(&mut self, code: usize, first_byte: u8) {
+        if let Some(last) = self.last_spelled {
+            if !self.lexicon.is_full() && self.lexicon.travel(last, first_byte).is_none() {
+                self.lexicon.append(last, first_byte);
+            }
+        }
+        self.last_spelled = Some(code);
+    }
+}
+
+pub struct WordStats {
+    pub tokens: usize,
+    pub vocabulary: usize,
+}
+
+fn emit<O: Write, C: UniversalCoder>(
+    output_stream: &mut BitOutputStream<O>,
+    universal_coder: &mut C,
+    dict_size: usize,
+    code: usize,
+) {
+    universal_coder.set_dict_size(dict_size);
+    universal_coder.code_number(output_stream, code + 1);
+}
+
+pub fn code_words<O: Write, C: UniversalCoder>(
+    data: &[u8],
+    output_stream: &mut BitOutputStream<O>,
+    universal_coder: &mut C,
+    max_size: usize,
+) -> WordStats {
+    let tokens = tokenize(data);
+    let mut token_ids = HashMap::new();
+    let ids = tokens
+        .iter()
+        .map(|&token| {
+            let next_id = token_ids.len() as u32;
+            *token_ids.entry(token).or_insert(next_id)
+        })
+        .collect::<Vec<_>>();
+
+    let mut dict = WordDict::new(max_size);
+    let mut previous = None;
+    let mut pos = 0;
+
+    while pos < ids.len() {
+        let (code, len) = if ids[pos] as usize == dict.token_count() {
+            emit(output_stream, universal_coder, dict.len(), ESCAPE_CODE);
+
+            let token = tokens[pos];
+            emit(output_stream, universal_coder, MAX_TOKEN_LEN, token.len() - 1);
+            let mut spelled = 0;
+            while spelled < token.len() {
+                let (lexicon_code, len) = dict.lexicon_match(&token[spelled..]);
+                emit(output_stream, universal_coder, dict.lexicon_len(), lexicon_code);
+                dict.extend_lexicon(lexicon_code, token[spelled]);
+                spelled += len;
+            }
+
+            (dict.add_token(token), 1)
+        } else {
+            let (code, len) = dict.longest_match(&ids[pos..]);
+            emit(output_stream, universal_coder, dict.len(), code);
+            (code, len)
+        };
+
+        if let Some(previous) = previous {
+            dict.extend(previous, code);
+        }
+        previous = Some(code);
+        pos += len;
+    }
+
+    WordStats {
+        tokens: tokens.len(),
+        vocabulary: dict.token_count(),
+    }
+}
+
+pub fn decode_words<I: Read, O: Write, D: UniversalDecoder>(
+    input_stream: &mut BitInputStream<I>,
+    output_stream: &mut O,
+    universal_decoder: &mut D,
+    header: StreamHeader,
+) -> Result<usize, LzwError> {
+    let mut dict = WordDict::new(header.config.max_size);
+    let mut previous = None;
+    let mut phrase = vec![];
+    let mut output_len = 0u64;
+
+    let data_start = input_stream.position();
+    let mut read = |input_stream: &mut BitInputStream<I>, dict_size: usize, offset: u64| {
+        universal_decoder.set_dict_size(dict_size);
+        let err = match universal_decoder.decode_positive(input_stream) {
+            Ok(number) => return Ok(number - 1),
+            Err(err) => err,
+        };
+        match (err, input_stream.take_error()) {
+            (_, Some(err)) => Err(err.into()),
+            (DecodeError::Overflow, None) => Err(LzwError::Overflow { offset }),
+            (_, None) => Err(LzwError::Truncated { offset }),
+        }
+    };
+
+    while output_len < header.len {
+        let offset = input_stream.position() - data_start;
+        let code = read(input_stream, dict.len(), offset)?;
+
+        let code = if code == ESCAPE_CODE as u128 {
+            let offset = input_stream.position() - data_start;
+            let len = read(input_stream, MAX_TOKEN_LEN, offset)? + 1;
+            if len > MAX_TOKEN_LEN as u128 {
+                return Err(LzwError::InvalidCode { offset, code: len - 1 });
+            }
+
+            let mut token = vec![];
+            while (token.len() as u128) < len {
+                let offset = input_stream.position() - data_start;
+                let lexicon_code = read(input_stream, dict.lexicon_len(), offset)?;
+                if lexicon_code >= dict.lexicon_len() as u128 {
+                    return Err(LzwError::InvalidCode { offset, code: lexicon_code });
+                }
+
+                dict.lexicon.phrase(lexicon_code as usize, &mut phrase);
+                if (token.len() + phrase.len()) as u128 > len {
+                    return Err(LzwError::InvalidCode { offset, code: lexicon_code });
+                }
+                dict.extend_lexicon(lexicon_code as usize, phrase[0]);
+                token.extend_from_slice(&phrase);
+            }
+
+            dict.add_token(&token)
+        } else if code < dict.len() as u128 {
+            code as usize
+        } else {
+            return Err(LzwError::InvalidCode { offset, code });
+        };
+
+        dict.phrase(code, &mut phrase);
+        if output_len + phrase.len() as u64 > header.len {
+            return Err(LzwError::TooLong { offset });
+        }
+        output_stream.write_all(&phrase)?;
+        output_len += phrase.len() as u64;
+
+        if let Some(previous) = previous {
+            dict.extend(previous, code);
+        }
+        previous = Some(code);
+    }
+
+    Ok(output_len as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens() {
+        let tokens = tokenize("Litwo! Ojczyzno moja, ty jesteś jak zdrowie.\n".as_bytes());
+        let tokens = tokens.iter().map(|token| String::from_utf8_lossy(token)).collect::<Vec<_>>();
+        assert_eq!(
+            tokens,
+            ["Litwo", "! ", "Ojczyzno", " ", "moja", ", ", "ty", " ", "jesteś", " ", "jak", " ", "zdrowie", ".\n"]
+        );
+
+        let long = vec![b'a'; 600];
+        let lens = tokenize(&long).iter().map(|token| token.len()).collect::<Vec<_>>();
+        assert_eq!(lens, [255, 255, 90]);
+    }
+
+    #[test]
+    fn token_counter() {
+        let text = include_bytes!("./../../testy/pan_tadeusz.txt");
+        let mut counter = TokenCounter::new();
+        for &byte in text.iter().chain(&[b'a'; 600]) {
+            counter.push(byte);
+        }
+
+        let mut data = text.to_vec();
+        data.extend_from_slice(&[b'a'; 600]);
+        let tokens = tokenize(&data);
+        let distinct = tokens.iter().collect::<HashSet<_>>().len();
+        assert_eq!(counter.finish(), (tokens.len(), distinct));
+    }
+
+    #[test]
+    fn lexicon_size() {
+        let text = include_bytes!("./../../testy/pan_tadeusz.txt");
+
+        for max_size in [256, 300] {
+            let mut dict = WordDict::new(max_size);
+            for token in tokenize(text) {
+                let mut spelled = 0;
+                while spelled < token.len() {
+                    let (code, len) = dict.lexicon_match(&token[spelled..]);
+                    dict.extend_lexicon(code, token[spelled]);
+                    spelled += len;
+                }
+            }
+            println!("max size {}: lexicon {}", max_size, dict.lexicon_len());
+            assert_eq!(dict.lexicon_len(), max_size);
+        }
+    }
+}
@@ -1,79 +1,259 @@
 echo "======== Delta 1"
 ./lzw_coder delta ./testy/test1.bin ./wyniki/test1.comp
-./lzw_decoder delta ./wyniki/test1.comp ./wyniki/test1.decomp
+./lzw_decoder ./wyniki/test1.comp ./wyniki/test1.decomp
 diff ./testy/test1.bin ./wyniki/test1.decomp && echo ok
 
 echo "======== Gamma 1"
 ./lzw_coder gamma ./testy/test1.bin ./wyniki/test1.comp
-./lzw_decoder gamma ./wyniki/test1.comp ./wyniki/test1.decomp
+./lzw_decoder ./wyniki/test1.comp ./wyniki/test1.decomp
 diff ./testy/test1.bin ./wyniki/test1.decomp && echo ok
 
 echo "======== Omega 1"
 ./lzw_coder omega ./testy/test1.bin ./wyniki/test1.comp
-./lzw_decoder omega ./wyniki/test1.comp ./wyniki/test1.decomp
+./lzw_decoder ./wyniki/test1.comp ./wyniki/test1.decomp
 diff ./testy/test1.bin ./wyniki/test1.decomp && echo ok
 
 echo "======== Fib 1"
 ./lzw_coder fib ./testy/test1.bin ./wyniki/test1.comp
-./lzw_decoder fib ./wyniki/test1.comp ./wyniki/test1.decomp
+./lzw_decoder ./wyniki/test1.comp ./wyniki/test1.decomp
+diff ./testy/test1.bin ./wyniki/test1.decomp && echo ok
+
+echo "======== Binary 1"
+./lzw_coder binary ./testy/test1.bin ./wyniki/test1.comp
+./lzw_decoder ./wyniki/test1.comp ./wyniki/test1.decomp
+diff ./testy/test1.bin ./wyniki/test1.decomp && echo ok
+
+echo "======== Golomb 1"
+./lzw_coder golomb:10 ./testy/test1.bin ./wyniki/test1.comp
+./lzw_decoder ./wyniki/test1.comp ./wyniki/test1.decomp
+diff ./testy/test1.bin ./wyniki/test1.decomp && echo ok
+
+echo "======== Rice 1"
+./lzw_coder rice:6 ./testy/test1.bin ./wyniki/test1.comp
+./lzw_decoder ./wyniki/test1.comp ./wyniki/test1.decomp
+diff ./testy/test1.bin ./wyniki/test1.decomp && echo ok
+
+echo "======== ExpGolomb 1"
+./lzw_coder expgolomb:3 ./testy/test1.bin ./wyniki/test1.comp
+./lzw_decoder ./wyniki/test1.comp ./wyniki/test1.decomp
+diff ./testy/test1.bin ./wyniki/test1.decomp && echo ok
+
+echo "======== AdaptiveRice 1"
+./lzw_coder adaptive-rice ./testy/test1.bin ./wyniki/test1.comp
+./lzw_decoder ./wyniki/test1.comp ./wyniki/test1.decomp
+diff ./testy/test1.bin ./wyniki/test1.decomp && echo ok
+
+echo "======== Arithmetic 1"
+./lzw_coder arithmetic ./testy/test1.bin ./wyniki/test1.comp
+./lzw_decoder ./wyniki/test1.comp ./wyniki/test1.decomp
 diff ./testy/test1.bin ./wyniki/test1.decomp && echo ok
 
 echo "======== Delta 2"
 ./lzw_coder delta ./testy/test2.bin ./wyniki/test2.comp
-./lzw_decoder delta ./wyniki/test2.comp ./wyniki/test2.decomp
+./lzw_decoder ./wyniki/test2.comp ./wyniki/test2.decomp
 diff ./testy/test2.bin ./wyniki/test2.decomp && echo ok
 
 echo "======== Gamma 2"
 ./lzw_coder gamma ./testy/test2.bin ./wyniki/test2.comp
-./lzw_decoder gamma ./wyniki/test2.comp ./wyniki/test2.decomp
+./lzw_decoder ./wyniki/test2.comp ./wyniki/test2.decomp
 diff ./testy/test2.bin ./wyniki/test2.decomp && echo ok
 
 echo "======== Omega 2"
 ./lzw_coder omega ./testy/test2.bin ./wyniki/test2.comp
-./lzw_decoder omega ./wyniki/test2.comp ./wyniki/test2.decomp
+./lzw_decoder ./wyniki/test2.comp ./wyniki/test2.decomp
 diff ./testy/test2.bin ./wyniki/test2.decomp && echo ok
 
 echo "======== Fib 2"
 ./lzw_coder fib ./testy/test2.bin ./wyniki/test2.comp
-./lzw_decoder fib ./wyniki/test2.comp ./wyniki/test2.decomp
+./lzw_decoder ./wyniki/test2.comp ./wyniki/test2.decomp
+diff ./testy/test2.bin ./wyniki/test2.decomp && echo ok
+
+echo "======== Binary 2"
+./lzw_coder binary ./testy/test2.bin ./wyniki/test2.comp
+./lzw_decoder ./wyniki/test2.comp ./wyniki/test2.decomp
+diff ./testy/test2.bin ./wyniki/test2.decomp && echo ok
+
+echo "======== Golomb 2"
+./lzw_coder golomb:10 ./testy/test2.bin ./wyniki/test2.comp
+./lzw_decoder ./wyniki/test2.comp ./wyniki/test2.decomp
+diff ./testy/test2.bin ./wyniki/test2.decomp && echo ok
+
+echo "======== Rice 2"
+./lzw_coder rice:6 ./testy/test2.bin ./wyniki/test2.comp
+./lzw_decoder ./wyniki/test2.comp ./wyniki/test2.decomp
+diff ./testy/test2.bin ./wyniki/test2.decomp && echo ok
+
+echo "======== ExpGolomb 2"
+./lzw_coder expgolomb:3 ./testy/test2.bin ./wyniki/test2.comp
+./lzw_decoder ./wyniki/test2.comp ./wyniki/test2.decomp
+diff ./testy/test2.bin ./wyniki/test2.decomp && echo ok
+
+echo "======== AdaptiveRice 2"
+./lzw_coder adaptive-rice ./testy/test2.bin ./wyniki/test2.comp
+./lzw_decoder ./wyniki/test2.comp ./wyniki/test2.decomp
+diff ./testy/test2.bin ./wyniki/test2.decomp && echo ok
+
+echo "======== Arithmetic 2"
+./lzw_coder arithmetic ./testy/test2.bin ./wyniki/test2.comp
+./lzw_decoder ./wyniki/test2.comp ./wyniki/test2.decomp
 diff ./testy/test2.bin ./wyniki/test2.decomp && echo ok
 
 echo "======== Delta 3"
 ./lzw_coder delta ./testy/test3.bin ./wyniki/test3.comp
-./lzw_decoder delta ./wyniki/test3.comp ./wyniki/test3.decomp
+./lzw_decoder ./wyniki/test3.comp ./wyniki/test3.decomp
 diff ./testy/test3.bin ./wyniki/test3.decomp && echo ok
 
 echo "======== Gamma 3"
 ./lzw_coder gamma ./testy/test3.bin ./wyniki/test3.comp
-./lzw_decoder gamma ./wyniki/test3.comp ./wyniki/test3.decomp
+./lzw_decoder ./wyniki/test3.comp ./wyniki/test3.decomp
 diff ./testy/test3.bin ./wyniki/test3.decomp && echo ok
 
 echo "======== Omega 3"
 ./lzw_coder omega ./testy/test3.bin ./wyniki/test3.comp
-./lzw_decoder omega ./wyniki/test3.comp ./wyniki/test3.decomp
+./lzw_decoder ./wyniki/test3.comp ./wyniki/test3.decomp
 diff ./testy/test3.bin ./wyniki/test3.decomp && echo ok
 
 echo "======== Fib 3"
 ./lzw_coder fib ./testy/test3.bin ./wyniki/test3.comp
-./lzw_decoder fib ./wyniki/test3.comp ./wyniki/test3.decomp
+./lzw_decoder ./wyniki/test3.comp ./wyniki/test3.decomp
+diff ./testy/test3.bin ./wyniki/test3.decomp && echo ok
+
+echo "======== Binary 3"
+./lzw_coder binary ./testy/test3.bin ./wyniki/test3.comp
+./lzw_decoder ./wyniki/test3.comp ./wyniki/test3.decomp
+diff ./testy/test3.bin ./wyniki/test3.decomp && echo ok
+
+echo "======== Golomb 3"
+./lzw_coder golomb:10 ./testy/test3.bin ./wyniki/test3.comp
+./lzw_decoder ./wyniki/test3.comp ./wyniki/test3.decomp
+diff ./testy/test3.bin ./wyniki/test3.decomp && echo ok
+
+echo "======== Rice 3"
+./lzw_coder rice:6 ./testy/test3.bin ./wyniki/test3.comp
+./lzw_decoder ./wyniki/test3.comp ./wyniki/test3.decomp
+diff ./testy/test3.bin ./wyniki/test3.decomp && echo ok
+
+echo "======== ExpGolomb 3"
+./lzw_coder expgolomb:3 ./testy/test3.bin ./wyniki/test3.comp
+./lzw_decoder ./wyniki/test3.comp ./wyniki/test3.decomp
+diff ./testy/test3.bin ./wyniki/test3.decomp && echo ok
+
+echo "======== AdaptiveRice 3"
+./lzw_coder adaptive-rice ./testy/test3.bin ./wyniki/test3.comp
+./lzw_decoder ./wyniki/test3.comp ./wyniki/test3.decomp
+diff ./testy/test3.bin ./wyniki/test3.decomp && echo ok
+
+echo "======== Arithmetic 3"
+./lzw_coder arithmetic ./testy/test3.bin ./wyniki/test3.comp
+./lzw_decoder ./wyniki/test3.comp ./wyniki/test3.decomp
 diff ./testy/test3.bin ./wyniki/test3.decomp && echo ok
 
 echo "======== Delta Tadeusz"
 ./lzw_coder delta ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.comp
-./lzw_decoder delta ./wyniki/pan_tadeusz.comp ./wyniki/pan_tadeusz.decomp
+./lzw_decoder ./wyniki/pan_tadeusz.comp ./wyniki/pan_tadeusz.decomp
 diff ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.decomp && echo ok
 
-echo "======== Gamma Taduesz"
+echo "======== Gamma Tadeusz"
 ./lzw_coder gamma ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.comp
-./lzw_decoder gamma ./wyniki/pan_tadeusz.comp ./wyniki/pan_tadeusz.decomp
+./lzw_decoder ./wyniki/pan_tadeusz.comp ./wyniki/pan_tadeusz.decomp
 diff ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.decomp && echo ok
 
-echo "======== Omega Taduesz"
+echo "======== Omega Tadeusz"
 ./lzw_coder omega ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.comp
-./lzw_decoder omega ./wyniki/pan_tadeusz.comp ./wyniki/pan_tadeusz.decomp
+./lzw_decoder ./wyniki/pan_tadeusz.comp ./wyniki/pan_tadeusz.decomp
 diff ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.decomp && echo ok
 
-echo "======== Fib Taduesz"
+echo "======== Fib Tadeusz"
 ./lzw_coder fib ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.comp
-./lzw_decoder fib ./wyniki/pan_tadeusz.comp ./wyniki/pan_tadeusz.decomp
-diff ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.decomp && echo ok
\ No newline at end of file
+./lzw_decoder ./wyniki/pan_tadeusz.comp ./wyniki/pan_tadeusz.decomp
+diff ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.decomp && echo ok
+
+echo "======== Binary Tadeusz"
+./lzw_coder binary ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.comp
+./lzw_decoder ./wyniki/pan_tadeusz.comp ./wyniki/pan_tadeusz.decomp
+diff ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.decomp && echo ok
+
+echo "======== Golomb Tadeusz"
+./lzw_coder golomb:10 ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.comp
+./lzw_decoder ./wyniki/pan_tadeusz.comp ./wyniki/pan_tadeusz.decomp
+diff ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.decomp && echo ok
+
+echo "======== Rice Tadeusz"
+./lzw_coder rice:6 ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.comp
+./lzw_decoder ./wyniki/pan_tadeusz.comp ./wyniki/pan_tadeusz.decomp
+diff ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.decomp && echo ok
+
+echo "======== ExpGolomb Tadeusz"
+./lzw_coder expgolomb:3 ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.comp
+./lzw_decoder ./wyniki/pan_tadeusz.comp ./wyniki/pan_tadeusz.decomp
+diff ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.decomp && echo ok
+
+echo "======== AdaptiveRice Tadeusz"
+./lzw_coder adaptive-rice ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.comp
+./lzw_decoder ./wyniki/pan_tadeusz.comp ./wyniki/pan_tadeusz.decomp
+diff ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.decomp && echo ok
+
+echo "======== Arithmetic Tadeusz"
+./lzw_coder arithmetic ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.comp
+./lzw_decoder ./wyniki/pan_tadeusz.comp ./wyniki/pan_tadeusz.decomp
+diff ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.decomp && echo ok
+
+echo "======== Gamma freeze lzw Tadeusz"
+./lzw_coder gamma ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.comp freeze 4096 lzw
+./lzw_decoder ./wyniki/pan_tadeusz.comp ./wyniki/pan_tadeusz.decomp
+diff ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.decomp && echo ok
+
+echo "======== Gamma freeze lzmw Tadeusz"
+./lzw_coder gamma ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.comp freeze 4096 lzmw
+./lzw_decoder ./wyniki/pan_tadeusz.comp ./wyniki/pan_tadeusz.decomp
+diff ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.decomp && echo ok
+
+echo "======== Gamma freeze lzap Tadeusz"
+./lzw_coder gamma ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.comp freeze 4096 lzap
+./lzw_decoder ./wyniki/pan_tadeusz.comp ./wyniki/pan_tadeusz.decomp
+diff ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.decomp && echo ok
+
+echo "======== Gamma reset lzw Tadeusz"
+./lzw_coder gamma ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.comp reset 4096 lzw
+./lzw_decoder ./wyniki/pan_tadeusz.comp ./wyniki/pan_tadeusz.decomp
+diff ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.decomp && echo ok
+
+echo "======== Gamma reset lzmw Tadeusz"
+./lzw_coder gamma ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.comp reset 4096 lzmw
+./lzw_decoder ./wyniki/pan_tadeusz.comp ./wyniki/pan_tadeusz.decomp
+diff ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.decomp && echo ok
+
+echo "======== Gamma reset lzap Tadeusz"
+./lzw_coder gamma ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.comp reset 4096 lzap
+./lzw_decoder ./wyniki/pan_tadeusz.comp ./wyniki/pan_tadeusz.decomp
+diff ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.decomp && echo ok
+
+echo "======== Gamma adaptive lzw Tadeusz"
+./lzw_coder gamma ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.comp adaptive 4096 lzw
+./lzw_decoder ./wyniki/pan_tadeusz.comp ./wyniki/pan_tadeusz.decomp
+diff ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.decomp && echo ok
+
+echo "======== Gamma adaptive lzmw Tadeusz"
+./lzw_coder gamma ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.comp adaptive 4096 lzmw
+./lzw_decoder ./wyniki/pan_tadeusz.comp ./wyniki/pan_tadeusz.decomp
+diff ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.decomp && echo ok
+
+echo "======== Gamma adaptive lzap Tadeusz"
+./lzw_coder gamma ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.comp adaptive 4096 lzap
+./lzw_decoder ./wyniki/pan_tadeusz.comp ./wyniki/pan_tadeusz.decomp
+diff ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.decomp && echo ok
+
+echo "======== Gamma lru lzw Tadeusz"
+./lzw_coder gamma ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.comp lru 4096 lzw
+./lzw_decoder ./wyniki/pan_tadeusz.comp ./wyniki/pan_tadeusz.decomp
+diff ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.decomp && echo ok
+
+echo "======== Gamma words Tadeusz"
+./lzw_coder gamma ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.comp freeze 65536 words
+./lzw_decoder ./wyniki/pan_tadeusz.comp ./wyniki/pan_tadeusz.decomp
+diff ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.decomp && echo ok
+
+echo "======== Gamma chunked Tadeusz"
+./lzw_coder gamma ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.comp freeze 1000000 lzw - - 32768:4
+./lzw_decoder ./wyniki/pan_tadeusz.comp ./wyniki/pan_tadeusz.decomp
+diff ./testy/pan_tadeusz.txt ./wyniki/pan_tadeusz.decomp && echo ok
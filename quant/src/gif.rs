@@ -0,0 +1,339 @@
+use std::{collections::HashMap, fmt, fs::File, io::{BufWriter, Write}};
+
+use lzw::gif_lzw::{self, GifLzwError, MIN_CODE_SIZE};
+
+use crate::tga::{Bitmap, Channel};
+
+const GIF_HEADER: &[u8; 6] = b"GIF89a";
+
+const EXTENSION_INTRODUCER: u8 = 0x21;
+
+const IMAGE_SEPARATOR: u8 = 0x2C;
+
+const TRAILER: u8 = 0x3B;
+
+const COLOR_TABLE_FLAG: u8 = 0x80;
+
+const INTERLACE_FLAG: u8 = 0x40;
+
+const MAX_SUB_BLOCK: usize = 255;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GifError {
+    InvalidHeader,
+    Truncated,
+    MissingColorTable,
+    NoImage,
+    TooManyColors(usize),
+    TooLarge { width: isize, height: isize },
+    Lzw(GifLzwError),
+    Io(std::io::ErrorKind),
+}
+
+impl fmt::Display for GifError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GifError::InvalidHeader => write!(f, "not a GIF file"),
+            GifError::Truncated => write!(f, "GIF data ends unexpectedly"),
+            GifError::MissingColorTable => write!(f, "image has no color table"),
+            GifError::NoImage => write!(f, "GIF contains no image"),
+            GifError::TooManyColors(n) => write!(f, "{} colors do not fit in a GIF palette", n),
+            GifError::TooLarge { width, height } => {
+                write!(f, "{}x{} is larger than the 65535x65535 a GIF can hold", width, height)
+            }
+            GifError::Lzw(err) => write!(f, "image data: {}", err),
+            GifError::Io(kind) => write!(f, "io error: {}", kind),
+        }
+    }
+}
+
+impl std::error::Error for GifError {}
+
+impl From<std::io::Error> for GifError {
+    fn from(err: std::io::Error) -> Self {
+        GifError::Io(err.kind())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IndexedImage {
+    pub width: u16,
+    pub height: u16,
+    pub palette: Vec<(u8, u8, u8)>,
+    pub indices: Vec<u8>,
+}
+
+impl IndexedImage {
+    pub fn from_bitmap(bitmap: &Bitmap) -> Result<Self, GifError> {
+        let (width, height) = (bitmap.red.width, bitmap.red.height);
+        let (Ok(gif_width), Ok(gif_height)) = (u16::try_from(width), u16::try_from(height)) else {
+            return Err(GifError::TooLarge { width, height });
+        };
+
+        let mut palette = vec![];
+        let mut lookup = HashMap::new();
+        let mut indices = Vec::with_capacity(bitmap.red.data.len());
+
+        for col in bitmap.iter_zip() {
+            let index = *lookup.entry(col).or_insert_with(|| {
+                palette.push(col);
+                palette.len() - 1
+            });
+            indices.push(index);
+        }
+
+        if palette.len() > 256 {
+            return Err(GifError::TooManyColors(palette.len()));
+        }
+
+        Ok(Self {
+            width: gif_width,
+            height: gif_height,
+            palette,
+            indices: indices.into_iter().map(|i| i as u8).collect(),
+        })
+    }
+
+    pub fn to_bitmap(&self) -> Bitmap {
+        let (width, height) = (self.width as isize, self.height as isize);
+        let mut bitmap = Bitmap {
+            red: Channel::new(width, height),
+            green: Channel::new(width, height),
+            blue: Channel::new(width, height),
+        };
+
+        for &index in &self.indices {
+            bitmap.push(self.palette.get(index as usize).copied().unwrap_or((0, 0, 0)));
+        }
+
+        bitmap
+    }
+
+    fn table_bits(&self) -> u8 {
+        let mut bits = 1;
+        while (1 << bits) < self.palette.len() {
+            bits += 1;
+        }
+        bits
+    }
+
+    pub fn write_gif<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+        let table_bits = self.table_bits();
+
+        writer.write_all(GIF_HEADER)?;
+        writer.write_all(&self.width.to_le_bytes())?;
+        writer.write_all(&self.height.to_le_bytes())?;
+        writer.write_all(&[COLOR_TABLE_FLAG | 0x70 | (table_bits - 1), 0, 0])?;
+
+        for i in 0..1 << table_bits {
+            let (r, g, b) = self.palette.get(i).copied().unwrap_or((0, 0, 0));
+            writer.write_all(&[r, g, b])?;
+        }
+
+        writer.write_all(&[IMAGE_SEPARATOR, 0, 0, 0, 0])?;
+        writer.write_all(&self.width.to_le_bytes())?;
+        writer.write_all(&self.height.to_le_bytes())?;
+        writer.write_all(&[0])?;
+
+        let min_code_size = table_bits.max(MIN_CODE_SIZE);
+        writer.write_all(&[min_code_size])?;
+        for block in gif_lzw::encode(&self.indices, min_code_size).chunks(MAX_SUB_BLOCK) {
+            writer.write_all(&[block.len() as u8])?;
+            writer.write_all(block)?;
+        }
+        writer.write_all(&[0, TRAILER])?;
+
+        writer.flush()
+    }
+
+    pub fn read_gif(input: &[u8]) -> Result<Self, GifError> {
+        let mut reader = ByteReader { input };
+
+        let header = reader.take(6)?;
+        if &header[..3] != b"GIF" || (&header[3..] != b"87a" && &header[3..] != b"89a") {
+            return Err(GifError::InvalidHeader);
+        }
+
+        let screen = reader.take(7)?;
+        let mut global_palette = None;
+        if screen[4] & COLOR_TABLE_FLAG != 0 {
+            global_palette = Some(reader.read_palette(screen[4])?);
+        }
+
+        loop {
+            match reader.byte()? {
+                EXTENSION_INTRODUCER => {
+                    reader.byte()?;
+                    reader.read_sub_blocks()?;
+                }
+                IMAGE_SEPARATOR => {
+                    let descriptor = reader.take(9)?;
+                    let width = u16::from_le_bytes([descriptor[4], descriptor[5]]);
+                    let height = u16::from_le_bytes([descriptor[6], descriptor[7]]);
+                    let flags = descriptor[8];
+
+                    let palette = if flags & COLOR_TABLE_FLAG != 0 {
+                        reader.read_palette(flags)?
+                    } else {
+                        global_palette.ok_or(GifError::MissingColorTable)?
+                    };
+
+                    let min_code_size = reader.byte()?;
+                    let data = reader.read_sub_blocks()?;
+                    let mut indices = gif_lzw::decode(&data, min_code_size).map_err(GifError::Lzw)?;
+                    indices.resize(width as usize * height as usize, 0);
+
+                    if flags & INTERLACE_FLAG != 0 && width > 0 {
+                        indices = deinterlace(&indices, width as usize, height as usize);
+                    }
+
+                    return Ok(Self {
+                        width,
+                        height,
+                        palette,
+                        indices,
+                    });
+                }
+                _ => return Err(GifError::NoImage),
+            }
+        }
+    }
+}
+
+struct ByteReader<'a> {
+    input: &'a [u8],
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], GifError> {
+        if self.input.len() < n {
+            return Err(GifError::Truncated);
+        }
+        let (bytes, rest) = self.input.split_at(n);
+        self.input = rest;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8, GifError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn read_palette(&mut self, flags: u8) -> Result<Vec<(u8, u8, u8)>, GifError> {
+        let size = 1 << ((flags & 0x07) + 1);
+        Ok(self.take(size * 3)?.chunks(3).map(|c| (c[0], c[1], c[2])).collect())
+    }
+
+    fn read_sub_blocks(&mut self) -> Result<Vec<u8>, GifError> {
+        let mut data = vec![];
+        loop {
+            let len = self.byte()? as usize;
+            if len == 0 {
+                return Ok(data);
+            }
+            data.extend_from_slice(self.take(len)?);
+        }
+    }
+}
+
+fn deinterlace(indices: &[u8], width: usize, height: usize) -> Vec<u8> {
+    let mut output = vec![0; indices.len()];
+    let mut rows = indices.chunks(width);
+
+    for (start, step) in [(0, 8), (4, 8), (2, 4), (1, 2)] {
+        for y in (start..height).step_by(step) {
+            if let Some(row) = rows.next() {
+                output[y * width..(y + 1) * width].copy_from_slice(row);
+            }
+        }
+    }
+
+    output
+}
+
+pub fn read_gif_to_bitmap(input: &[u8]) -> Result<Bitmap, GifError> {
+    IndexedImage::read_gif(input).map(|image| image.to_bitmap())
+}
+
+impl Bitmap {
+    pub fn save_gif(&self, path: &str) -> Result<(), GifError> {
+        let image = IndexedImage::from_bitmap(self)?;
+        image.write_gif(BufWriter::new(File::create(path)?))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient(width: isize, height: isize, colors: u8) -> Bitmap {
+        let mut bitmap = Bitmap::empty(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let i = ((x + y * 3) % colors as isize) as u8;
+                bitmap.push((i.wrapping_mul(37), 255 - i, i / 2));
+            }
+        }
+        bitmap
+    }
+
+    #[test]
+    fn round_trip() {
+        for colors in [2, 5, 16, 200] {
+            let bitmap = gradient(97, 61, colors);
+            let image = IndexedImage::from_bitmap(&bitmap).unwrap();
+
+            let mut buf = vec![];
+            image.write_gif(&mut buf).unwrap();
+
+            let read = read_gif_to_bitmap(&buf).unwrap();
+            assert_eq!(read.iter_zip().collect::<Vec<_>>(), bitmap.iter_zip().collect::<Vec<_>>());
+
+            let decoded = image::load_from_memory(&buf).unwrap().to_rgb8();
+            assert_eq!(decoded.dimensions(), (97, 61));
+            for (x, y, pixel) in decoded.enumerate_pixels() {
+                let col = bitmap.get(x as isize, y as isize);
+                assert_eq!(pixel.0, [col.0, col.1, col.2]);
+            }
+        }
+    }
+
+    #[test]
+    fn read_from_image_crate() {
+        let bitmap = gradient(40, 30, 7);
+        let mut img = image::RgbaImage::new(40, 30);
+        for (x, y, pixel) in img.enumerate_pixels_mut() {
+            let col = bitmap.get(x as isize, y as isize);
+            *pixel = image::Rgba([col.0, col.1, col.2, 255]);
+        }
+
+        let mut buf = std::io::Cursor::new(vec![]);
+        img.write_to(&mut buf, image::ImageOutputFormat::Gif).unwrap();
+
+        let read = read_gif_to_bitmap(buf.get_ref()).unwrap();
+        assert_eq!(read.iter_zip().collect::<Vec<_>>(), bitmap.iter_zip().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn too_many_colors() {
+        let mut bitmap = Bitmap::empty(300, 1);
+        for i in 0..300 {
+            bitmap.push(((i % 256) as u8, (i / 256) as u8, 0));
+        }
+        assert_eq!(IndexedImage::from_bitmap(&bitmap).unwrap_err(), GifError::TooManyColors(300));
+    }
+
+    #[test]
+    fn too_large_and_io_errors() {
+        let bitmap = gradient(70_000, 1, 2);
+        assert_eq!(
+            IndexedImage::from_bitmap(&bitmap).unwrap_err(),
+            GifError::TooLarge { width: 70_000, height: 1 }
+        );
+
+        let bitmap = gradient(4, 4, 2);
+        let err = bitmap.save_gif("/nonexistent-dir/out.gif").unwrap_err();
+        println!("{}", err);
+        assert_eq!(err, GifError::Io(std::io::ErrorKind::NotFound));
+    }
+}
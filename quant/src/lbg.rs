@@ -88,7 +88,7 @@ impl<'a> LBG<'a> {
             counts[min] += 1;
         }
 
-        for (i, (sum, count)) in sums.into_iter().zip(counts.into_iter()).enumerate() {
+        for (i, (sum, count)) in sums.into_iter().zip(counts).enumerate() {
             if count == 0 {
                 continue;
             }
@@ -1,3 +1,4 @@
 pub mod tga;
 pub mod lbg;
-pub mod color;
\ No newline at end of file
+pub mod color;
+pub mod gif;
\ No newline at end of file
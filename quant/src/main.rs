@@ -30,7 +30,11 @@ fn main() {
         lbg.split();
         lbg.optimize(0.01);
         let b = lbg.code();
-        b.save(&format!("{}_col={}_{:?}_{:?}.png",output_path,i,metric,splitting));
+        if i <= 8 {
+            b.save_gif(&format!("{}_col={}_{:?}_{:?}.gif",output_path,i,metric,splitting)).unwrap();
+        } else {
+            b.save(&format!("{}_col={}_{:?}_{:?}.png",output_path,i,metric,splitting));
+        }
 
         let mse = lbg.error();
         let snr = lbg.signal_noise_ratio();
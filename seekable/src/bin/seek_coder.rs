@@ -0,0 +1,47 @@
+use std::{env::args, io::{BufReader, BufWriter}, fs::File, time::Instant};
+
+use seekable::{codec::FrameCodec, coder::SeekableCoder, DEFAULT_CHECKPOINT_INTERVAL};
+
+pub fn main() {
+    let args = args().collect::<Vec<_>>();
+
+    let coding = args.get(1).expect("Input coding arithmetic/gamma/delta/omega/fib");
+    let input_path = args.get(2).expect("Please input the input file path");
+    let output_path = args.get(3).expect("Please input the output file path");
+    let interval = args
+        .get(4)
+        .map(|s| s.parse().expect("Please input a correct checkpoint interval"))
+        .unwrap_or(DEFAULT_CHECKPOINT_INTERVAL);
+
+    let codec = FrameCodec::from_name(coding)
+        .expect("Input coding as first argument (arithmetic/gamma/delta/omega/fib)");
+
+    let input_stream = BufReader::new(File::open(input_path).unwrap());
+    let mut output_stream = BufWriter::new(File::create(output_path).unwrap());
+
+    let time_start = Instant::now();
+
+    let mut coder = SeekableCoder::new(input_stream, &mut output_stream, codec, interval);
+    coder.code_all();
+    let (input_len, output_len) = coder.get_stat();
+    let frames = coder.index().frames.len();
+
+    let time_end = Instant::now();
+    let dur = (time_end - time_start).as_secs_f64();
+
+    drop(output_stream);
+
+    println!("Compression statistics:");
+    println!("Input size: {:.3} MB", input_len as f64 / 1_000_000.0);
+    println!("Output size: {:.3} MB", output_len as f64 / 1_000_000.0);
+    println!("Checkpoints: {}", frames);
+    println!("Compression ratio: {:.3}", input_len as f64 / output_len as f64);
+    println!("Average code length: {:.3}", output_len as f64 * 8.0 / input_len as f64);
+    println!("Speed: {:.2} MB/s", input_len as f64 / (dur * 1_000_000.0));
+
+    let entropy = entropy::quick_entropy_of_file(input_path);
+    println!("Entropy of source: {}", entropy);
+
+    let entropy = entropy::quick_entropy_of_file(output_path);
+    println!("Entropy of code: {}", entropy);
+}
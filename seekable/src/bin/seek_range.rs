@@ -0,0 +1,46 @@
+use std::{env::args, fs::{write, File}, io::BufReader, time::Instant};
+
+use seekable::reader::SeekableReader;
+
+pub fn main() {
+    let args = args().collect::<Vec<_>>();
+
+    let input_path = args.get(1).expect("Please input the input file path");
+    let output_path = args.get(2).expect("Please input the output file path");
+
+    let mut reader = match SeekableReader::new(BufReader::new(File::open(input_path).unwrap())) {
+        Ok(reader) => reader,
+        Err(err) => {
+            eprintln!("Opening failed: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let start = args
+        .get(3)
+        .map(|s| s.parse().expect("Please input a correct start offset"))
+        .unwrap_or(0);
+    let end = args
+        .get(4)
+        .map(|s| s.parse().expect("Please input a correct end offset"))
+        .unwrap_or(reader.len());
+
+    let time_start = Instant::now();
+
+    let range = match reader.read_range(start, end) {
+        Ok(range) => range,
+        Err(err) => {
+            eprintln!("Decoding failed: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let time_end = Instant::now();
+    let dur = (time_end - time_start).as_secs_f64();
+
+    write(output_path, &range).unwrap();
+
+    println!("Compression statistics:");
+    println!("Output size: {:.3} MB", range.len() as f64 / 1_000_000.0);
+    println!("Speed: {:.2} MB/s", range.len() as f64 / (dur * 1_000_000.0));
+}
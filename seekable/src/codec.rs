@@ -0,0 +1,85 @@
+use arithmetic::{coder::Coder, decoder::Decoder};
+use lzw::{
+    coder::LZWCoder,
+    decoder::LZWDecoder,
+    universal_coder::{DeltaCoder, FibonacciCoder, GammaCoder, OmegaCoder},
+    universal_decoder::{DeltaDecoder, FibonacciDecoder, GammaDecoder, OmegaDecoder},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameCodec {
+    Arithmetic,
+    LzwGamma,
+    LzwDelta,
+    LzwOmega,
+    LzwFib,
+}
+
+impl FrameCodec {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "arithmetic" => Some(FrameCodec::Arithmetic),
+            "gamma" => Some(FrameCodec::LzwGamma),
+            "delta" => Some(FrameCodec::LzwDelta),
+            "omega" => Some(FrameCodec::LzwOmega),
+            "fib" => Some(FrameCodec::LzwFib),
+            _ => None,
+        }
+    }
+
+    pub fn id(self) -> u8 {
+        match self {
+            FrameCodec::Arithmetic => 0,
+            FrameCodec::LzwGamma => 1,
+            FrameCodec::LzwDelta => 2,
+            FrameCodec::LzwOmega => 3,
+            FrameCodec::LzwFib => 4,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(FrameCodec::Arithmetic),
+            1 => Some(FrameCodec::LzwGamma),
+            2 => Some(FrameCodec::LzwDelta),
+            3 => Some(FrameCodec::LzwOmega),
+            4 => Some(FrameCodec::LzwFib),
+            _ => None,
+        }
+    }
+
+    pub fn compress(self, input: &[u8]) -> Vec<u8> {
+        let mut output = vec![];
+
+        match self {
+            FrameCodec::Arithmetic => Coder::new(input, &mut output).code_all(),
+            FrameCodec::LzwGamma => LZWCoder::new(input, &mut output, GammaCoder).with_input_len(input.len() as u64).code_to_end().unwrap(),
+            FrameCodec::LzwDelta => LZWCoder::new(input, &mut output, DeltaCoder).with_input_len(input.len() as u64).code_to_end().unwrap(),
+            FrameCodec::LzwOmega => LZWCoder::new(input, &mut output, OmegaCoder).with_input_len(input.len() as u64).code_to_end().unwrap(),
+            FrameCodec::LzwFib => LZWCoder::new(input, &mut output, FibonacciCoder::new()).with_input_len(input.len() as u64).code_to_end().unwrap(),
+        }
+
+        output
+    }
+
+    // len comes from the index, so it is only checked, not preallocated
+    pub fn decompress(self, frame: &[u8], len: usize) -> Option<Vec<u8>> {
+        let mut output = vec![];
+
+        match self {
+            FrameCodec::Arithmetic => Decoder::new(frame, &mut output).decode_all().ok()?,
+            FrameCodec::LzwGamma => LZWDecoder::new(frame, &mut output, GammaDecoder).decode_to_end().ok()?,
+            FrameCodec::LzwDelta => LZWDecoder::new(frame, &mut output, DeltaDecoder).decode_to_end().ok()?,
+            FrameCodec::LzwOmega => LZWDecoder::new(frame, &mut output, OmegaDecoder).decode_to_end().ok()?,
+            FrameCodec::LzwFib => {
+                LZWDecoder::new(frame, &mut output, FibonacciDecoder::new()).decode_to_end().ok()?
+            }
+        }
+
+        if output.len() == len {
+            Some(output)
+        } else {
+            None
+        }
+    }
+}
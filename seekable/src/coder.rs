@@ -0,0 +1,64 @@
+use std::io::{Read, Write};
+
+use crate::{
+    codec::FrameCodec,
+    index::{FrameEntry, SeekIndex},
+};
+
+pub struct SeekableCoder<I, O> {
+    input_stream: I,
+    output_stream: O,
+    index: SeekIndex,
+
+    input_len: usize,
+    output_len: usize,
+}
+
+impl<I: Read, O: Write> SeekableCoder<I, O> {
+    pub fn new(input_stream: I, output_stream: O, codec: FrameCodec, checkpoint_interval: usize) -> Self {
+        assert!(checkpoint_interval > 0, "Checkpoint interval has to be positive");
+
+        Self {
+            input_stream,
+            output_stream,
+            index: SeekIndex::new(codec, checkpoint_interval as u64),
+            input_len: 0,
+            output_len: 0,
+        }
+    }
+
+    pub fn code_all(&mut self) {
+        loop {
+            let mut frame = vec![];
+            (&mut self.input_stream)
+                .take(self.index.checkpoint_interval)
+                .read_to_end(&mut frame)
+                .unwrap();
+            if frame.is_empty() {
+                break;
+            }
+
+            let compressed = self.index.codec.compress(&frame);
+            self.output_stream.write_all(&compressed).unwrap();
+
+            self.index.frames.push(FrameEntry {
+                compressed_len: compressed.len() as u64,
+                uncompressed_len: frame.len() as u64,
+            });
+            self.input_len += frame.len();
+            self.output_len += compressed.len();
+        }
+
+        self.index.write_to(&mut self.output_stream).unwrap();
+        self.output_len += self.index.footer_len() as usize;
+        self.output_stream.flush().unwrap();
+    }
+
+    pub fn get_stat(&self) -> (usize, usize) {
+        (self.input_len, self.output_len)
+    }
+
+    pub fn index(&self) -> &SeekIndex {
+        &self.index
+    }
+}
@@ -0,0 +1,158 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use crate::{codec::FrameCodec, SeekError, SEEK_MAGIC};
+
+const FRAME_ENTRY_LEN: u64 = 16;
+const FOOTER_TAIL_LEN: u64 = 1 + 8 + 8 + 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameEntry {
+    pub compressed_len: u64,
+    pub uncompressed_len: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeekIndex {
+    pub codec: FrameCodec,
+    pub checkpoint_interval: u64,
+    pub frames: Vec<FrameEntry>,
+}
+
+impl SeekIndex {
+    pub fn new(codec: FrameCodec, checkpoint_interval: u64) -> Self {
+        Self {
+            codec,
+            checkpoint_interval,
+            frames: vec![],
+        }
+    }
+
+    pub fn uncompressed_len(&self) -> u64 {
+        self.frames.iter().map(|frame| frame.uncompressed_len).sum()
+    }
+
+    pub fn compressed_len(&self) -> u64 {
+        self.frames.iter().map(|frame| frame.compressed_len).sum()
+    }
+
+    pub fn footer_len(&self) -> u64 {
+        self.frames.len() as u64 * FRAME_ENTRY_LEN + FOOTER_TAIL_LEN
+    }
+
+    pub fn write_to<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+        for frame in &self.frames {
+            writer.write_all(&frame.compressed_len.to_le_bytes())?;
+            writer.write_all(&frame.uncompressed_len.to_le_bytes())?;
+        }
+        writer.write_all(&[self.codec.id()])?;
+        writer.write_all(&self.checkpoint_interval.to_le_bytes())?;
+        writer.write_all(&(self.frames.len() as u64).to_le_bytes())?;
+        writer.write_all(SEEK_MAGIC)
+    }
+
+    pub fn read_from<R: Read + Seek>(mut reader: R) -> Result<Self, SeekError> {
+        let file_len = reader.seek(SeekFrom::End(0))?;
+        if file_len < FOOTER_TAIL_LEN {
+            return Err(SeekError::InvalidIndex);
+        }
+
+        let mut tail = [0; FOOTER_TAIL_LEN as usize];
+        reader.seek(SeekFrom::Start(file_len - FOOTER_TAIL_LEN))?;
+        reader.read_exact(&mut tail)?;
+
+        if &tail[17..] != SEEK_MAGIC {
+            return Err(SeekError::InvalidIndex);
+        }
+        let codec = FrameCodec::from_id(tail[0]).ok_or(SeekError::UnknownCodec(tail[0]))?;
+        let checkpoint_interval = u64::from_le_bytes(tail[1..9].try_into().unwrap());
+        let frame_count = u64::from_le_bytes(tail[9..17].try_into().unwrap());
+
+        let entries_len = frame_count
+            .checked_mul(FRAME_ENTRY_LEN)
+            .filter(|&len| len <= file_len - FOOTER_TAIL_LEN)
+            .ok_or(SeekError::InvalidIndex)?;
+
+        reader.seek(SeekFrom::Start(file_len - FOOTER_TAIL_LEN - entries_len))?;
+        let mut entries = vec![0; entries_len as usize];
+        reader.read_exact(&mut entries)?;
+
+        let frames = entries
+            .chunks_exact(FRAME_ENTRY_LEN as usize)
+            .map(|entry| FrameEntry {
+                compressed_len: u64::from_le_bytes(entry[..8].try_into().unwrap()),
+                uncompressed_len: u64::from_le_bytes(entry[8..].try_into().unwrap()),
+            })
+            .collect();
+
+        let index = Self {
+            codec,
+            checkpoint_interval,
+            frames,
+        };
+
+        let valid_frames = index
+            .frames
+            .iter()
+            .all(|frame| frame.uncompressed_len > 0 && frame.uncompressed_len <= checkpoint_interval);
+        // Crafted lengths can add up past u64::MAX
+        let compressed_len = checked_sum(index.frames.iter().map(|frame| frame.compressed_len));
+        let uncompressed_len = checked_sum(index.frames.iter().map(|frame| frame.uncompressed_len));
+        let total_len = compressed_len.and_then(|len| len.checked_add(index.footer_len()));
+        if !valid_frames || uncompressed_len.is_none() || total_len != Some(file_len) {
+            return Err(SeekError::InvalidIndex);
+        }
+
+        Ok(index)
+    }
+}
+
+fn checked_sum(mut values: impl Iterator<Item = u64>) -> Option<u64> {
+    values.try_fold(0u64, |sum, value| sum.checked_add(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn index_round_trip() {
+        let mut index = SeekIndex::new(FrameCodec::LzwFib, 100);
+        index.frames.push(FrameEntry {
+            compressed_len: 3,
+            uncompressed_len: 100,
+        });
+        index.frames.push(FrameEntry {
+            compressed_len: 2,
+            uncompressed_len: 7,
+        });
+
+        let mut buf = vec![0; 5];
+        index.write_to(&mut buf).unwrap();
+
+        assert_eq!(SeekIndex::read_from(Cursor::new(&buf)).unwrap(), index);
+        assert_eq!(
+            SeekIndex::read_from(Cursor::new(&buf[1..])),
+            Err(SeekError::InvalidIndex)
+        );
+    }
+
+    #[test]
+    fn index_overflow() {
+        let data = [0; 5];
+        for (compressed, uncompressed) in [([3, 2], [u64::MAX / 2 + 1, u64::MAX / 2 + 1]), ([u64::MAX, 6], [1, 1])] {
+            let mut index = SeekIndex::new(FrameCodec::LzwFib, u64::MAX);
+            for (compressed_len, uncompressed_len) in compressed.into_iter().zip(uncompressed) {
+                index.frames.push(FrameEntry {
+                    compressed_len,
+                    uncompressed_len,
+                });
+            }
+
+            let mut buf = data.to_vec();
+            index.write_to(&mut buf).unwrap();
+            assert_eq!(SeekIndex::read_from(Cursor::new(&buf)), Err(SeekError::InvalidIndex));
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use std::{error::Error, fmt::Display};
+
+pub mod codec;
+pub mod index;
+pub mod coder;
+pub mod reader;
+
+pub const SEEK_MAGIC: &[u8; 4] = b"SEEK";
+
+pub const DEFAULT_CHECKPOINT_INTERVAL: usize = 1 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeekError {
+    InvalidIndex,
+    UnknownCodec(u8),
+    OutOfRange { start: u64, end: u64, len: u64 },
+    CorruptedFrame(usize),
+    Io(std::io::ErrorKind),
+}
+
+impl Display for SeekError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SeekError::InvalidIndex => write!(f, "missing or invalid seek index"),
+            SeekError::UnknownCodec(id) => write!(f, "unknown codec id {}", id),
+            SeekError::OutOfRange { start, end, len } => {
+                write!(f, "range {}..{} outside of the {} decompressed bytes", start, end, len)
+            }
+            SeekError::CorruptedFrame(frame) => write!(f, "corrupted frame {}", frame),
+            SeekError::Io(kind) => write!(f, "io error: {}", kind),
+        }
+    }
+}
+
+impl Error for SeekError {}
+
+impl From<std::io::Error> for SeekError {
+    fn from(err: std::io::Error) -> Self {
+        SeekError::Io(err.kind())
+    }
+}
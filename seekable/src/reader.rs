@@ -0,0 +1,170 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::{index::SeekIndex, SeekError};
+
+pub struct SeekableReader<R> {
+    reader: R,
+    index: SeekIndex,
+    compressed_offsets: Vec<u64>,
+    uncompressed_offsets: Vec<u64>,
+}
+
+impl<R: Read + Seek> SeekableReader<R> {
+    pub fn new(mut reader: R) -> Result<Self, SeekError> {
+        let index = SeekIndex::read_from(&mut reader)?;
+
+        let mut compressed_offsets = vec![0u64];
+        let mut uncompressed_offsets = vec![0u64];
+        for frame in &index.frames {
+            let compressed_offset = compressed_offsets.last().unwrap().checked_add(frame.compressed_len);
+            let uncompressed_offset = uncompressed_offsets.last().unwrap().checked_add(frame.uncompressed_len);
+            compressed_offsets.push(compressed_offset.ok_or(SeekError::InvalidIndex)?);
+            uncompressed_offsets.push(uncompressed_offset.ok_or(SeekError::InvalidIndex)?);
+        }
+
+        Ok(Self {
+            reader,
+            index,
+            compressed_offsets,
+            uncompressed_offsets,
+        })
+    }
+
+    pub fn index(&self) -> &SeekIndex {
+        &self.index
+    }
+
+    pub fn len(&self) -> u64 {
+        *self.uncompressed_offsets.last().unwrap()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn read_range(&mut self, start: u64, end: u64) -> Result<Vec<u8>, SeekError> {
+        if start > end || end > self.len() {
+            return Err(SeekError::OutOfRange {
+                start,
+                end,
+                len: self.len(),
+            });
+        }
+
+        // Grows with the decoded frames, the lengths in the index are not
+        // trusted for allocating
+        let mut output = vec![];
+        if start == end {
+            return Ok(output);
+        }
+
+        let first_frame = self.uncompressed_offsets.partition_point(|&offset| offset <= start) - 1;
+
+        for frame in first_frame..self.index.frames.len() {
+            let frame_start = self.uncompressed_offsets[frame];
+            if frame_start >= end {
+                break;
+            }
+
+            let data = self.decode_frame(frame)?;
+            let from = start.saturating_sub(frame_start) as usize;
+            let to = ((end - frame_start) as usize).min(data.len());
+            output.extend_from_slice(&data[from..to]);
+        }
+
+        Ok(output)
+    }
+
+    pub fn read_all(&mut self) -> Result<Vec<u8>, SeekError> {
+        self.read_range(0, self.len())
+    }
+
+    fn decode_frame(&mut self, frame: usize) -> Result<Vec<u8>, SeekError> {
+        let entry = self.index.frames[frame];
+
+        self.reader.seek(SeekFrom::Start(self.compressed_offsets[frame]))?;
+        let mut compressed = vec![0; entry.compressed_len as usize];
+        self.reader.read_exact(&mut compressed)?;
+
+        self.index
+            .codec
+            .decompress(&compressed, entry.uncompressed_len as usize)
+            .ok_or(SeekError::CorruptedFrame(frame))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::{codec::FrameCodec, coder::SeekableCoder};
+
+    use super::*;
+
+    fn compress(buf: &[u8], codec: FrameCodec, interval: usize) -> Vec<u8> {
+        let mut coded_buf = vec![];
+        SeekableCoder::new(buf, &mut coded_buf, codec, interval).code_all();
+        coded_buf
+    }
+
+    #[test]
+    fn seek_ranges() {
+        let buf = include_bytes!("./../../testy/pan_tadeusz.txt");
+
+        for codec in [
+            FrameCodec::Arithmetic,
+            FrameCodec::LzwGamma,
+            FrameCodec::LzwDelta,
+            FrameCodec::LzwOmega,
+            FrameCodec::LzwFib,
+        ] {
+            let coded_buf = compress(buf, codec, 10_000);
+            let mut reader = SeekableReader::new(Cursor::new(coded_buf)).unwrap();
+
+            assert_eq!(reader.len(), buf.len() as u64);
+            for (start, end) in [(0, 10), (9_995, 10_005), (12_345, 54_321), (0, buf.len()), (buf.len(), buf.len())] {
+                let range = reader.read_range(start as u64, end as u64).unwrap();
+                assert_eq!(range, &buf[start..end]);
+            }
+            assert!(reader.read_range(0, buf.len() as u64 + 1).is_err());
+        }
+    }
+
+    #[test]
+    fn seek_empty() {
+        let coded_buf = compress(b"", FrameCodec::Arithmetic, 10);
+        let mut reader = SeekableReader::new(Cursor::new(coded_buf)).unwrap();
+
+        assert!(reader.is_empty());
+        assert_eq!(reader.read_all().unwrap(), b"");
+    }
+
+    #[test]
+    fn seek_corrupted_frame() {
+        let buf = include_bytes!("./../../testy/pan_tadeusz.txt");
+        let mut coded_buf = compress(buf, FrameCodec::Arithmetic, 10_000);
+        coded_buf[50] ^= 0xFF;
+
+        let mut reader = SeekableReader::new(Cursor::new(coded_buf)).unwrap();
+
+        assert!(reader.read_range(20_000, 30_000).is_ok());
+        assert_eq!(reader.read_range(0, 10), Err(SeekError::CorruptedFrame(0)));
+    }
+
+    #[test]
+    fn seek_huge_frame_len() {
+        let buf = include_bytes!("./../../testy/pan_tadeusz.txt");
+        let coded_buf = compress(&buf[..5000], FrameCodec::LzwGamma, 10_000);
+
+        // One frame, claiming far more than it holds
+        let reader = SeekableReader::new(Cursor::new(coded_buf.clone())).unwrap();
+        let mut index = reader.index().clone();
+        index.checkpoint_interval = u64::MAX / 2;
+        index.frames[0].uncompressed_len = u64::MAX / 2;
+        let mut crafted = coded_buf[..index.compressed_len() as usize].to_vec();
+        index.write_to(&mut crafted).unwrap();
+
+        let mut reader = SeekableReader::new(Cursor::new(crafted)).unwrap();
+        assert_eq!(reader.read_range(0, 10), Err(SeekError::CorruptedFrame(0)));
+    }
+}